@@ -0,0 +1,52 @@
+//! Benchmarks for HNSW index construction
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::Rng;
+use vexlake_core::index::{HnswConfig, HnswIndex};
+
+fn random_vector(dim: usize) -> Vec<f32> {
+    let mut rng = rand::thread_rng();
+    (0..dim).map(|_| rng.gen()).collect()
+}
+
+fn build(config: &HnswConfig, vectors: &[Vec<f32>]) -> HnswIndex {
+    let mut index = HnswIndex::new(config.clone());
+    for (id, v) in vectors.iter().enumerate() {
+        index.insert(id as u64, v.clone()).unwrap();
+    }
+    index
+}
+
+fn bench_per_layer_ef_construction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hnsw_build_ef_construction");
+    group.sample_size(10);
+
+    let dim = 128;
+    let vectors: Vec<Vec<f32>> = (0..2000).map(|_| random_vector(dim)).collect();
+
+    let uniform = HnswConfig {
+        dimension: dim,
+        ..Default::default()
+    };
+    let per_layer = HnswConfig {
+        dimension: dim,
+        ef_construction: 32,
+        ef_construction_per_layer: vec![200],
+        ..Default::default()
+    };
+
+    for (name, config) in [("uniform", &uniform), ("per_layer", &per_layer)] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            config,
+            |bench, config| {
+                bench.iter(|| build(config, &vectors));
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_per_layer_ef_construction);
+criterion_main!(benches);
@@ -0,0 +1,40 @@
+//! Benchmarks for HNSW search
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::Rng;
+use vexlake_core::index::{HnswConfig, HnswIndex};
+
+fn random_vector(dim: usize) -> Vec<f32> {
+    let mut rng = rand::thread_rng();
+    (0..dim).map(|_| rng.gen()).collect()
+}
+
+fn bench_parallel_expand(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hnsw_search_parallel_expand");
+
+    let dim = 1024;
+    let mut index = HnswIndex::new(HnswConfig {
+        dimension: dim,
+        ef_construction: 100,
+        ..Default::default()
+    });
+    for id in 0..5000 {
+        index.insert(id, random_vector(dim)).unwrap();
+    }
+    let query = random_vector(dim);
+
+    // Small ef should not benefit; large ef is where parallelism can pay off
+    for ef in [16, 512] {
+        for (name, threshold) in [("sequential", None), ("parallel", Some(16))] {
+            index.set_parallel_expand_threshold(threshold);
+            group.bench_with_input(BenchmarkId::new(name, ef), &ef, |bench, &ef| {
+                bench.iter(|| index.search(black_box(&query), 10, ef).unwrap());
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_parallel_expand);
+criterion_main!(benches);
@@ -38,6 +38,64 @@ fn bench_l2_distance(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_l2_squared_vs_sqrt(c: &mut Criterion) {
+    let mut group = c.benchmark_group("l2_squared_vs_sqrt");
+
+    let a = random_vector(1024);
+    let b = random_vector(1024);
+    group.bench_function("l2_distance", |bench| {
+        bench.iter(|| vexlake_core::vector::l2_distance(black_box(&a), black_box(&b)));
+    });
+    group.bench_function("l2_distance_squared", |bench| {
+        bench.iter(|| vexlake_core::vector::l2_distance_squared(black_box(&a), black_box(&b)));
+    });
+
+    group.finish();
+}
+
+fn bench_dispatch_overhead(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dispatch_overhead");
+
+    // Tiny vectors so the per-call cost dominates the arithmetic
+    let a = random_vector(4);
+    let b = random_vector(4);
+
+    group.bench_function("dispatched", |bench| {
+        bench.iter(|| vexlake_core::vector::dot_product(black_box(&a), black_box(&b)));
+    });
+    group.bench_function("scalar", |bench| {
+        bench.iter(|| vexlake_core::vector::simd::dot_scalar(black_box(&a), black_box(&b)));
+    });
+
+    group.finish();
+}
+
+fn bench_simd_vs_scalar(c: &mut Criterion) {
+    use vexlake_core::vector::simd;
+
+    let mut group = c.benchmark_group("simd_vs_scalar");
+
+    for dim in [512, 1024] {
+        let a = random_vector(dim);
+        let b = random_vector(dim);
+
+        group.bench_with_input(BenchmarkId::new("dot_simd", dim), &dim, |bench, _| {
+            bench.iter(|| vexlake_core::vector::dot_product(black_box(&a), black_box(&b)));
+        });
+        group.bench_with_input(BenchmarkId::new("dot_scalar", dim), &dim, |bench, _| {
+            bench.iter(|| simd::dot_scalar(black_box(&a), black_box(&b)));
+        });
+        group.bench_with_input(BenchmarkId::new("l2_simd", dim), &dim, |bench, _| {
+            bench.iter(|| vexlake_core::vector::l2_distance(black_box(&a), black_box(&b)));
+        });
+        group.bench_with_input(BenchmarkId::new("l2_scalar", dim), &dim, |bench, _| {
+            bench.iter(|| simd::l2_squared_scalar(black_box(&a), black_box(&b)).sqrt());
+        });
+    }
+
+    group.finish();
+}
+
 fn bench_brute_force_topk(c: &mut Criterion) {
     let mut group = c.benchmark_group("brute_force_topk");
 
@@ -63,10 +121,40 @@ fn bench_brute_force_topk(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_index_norm_cache(c: &mut Criterion) {
+    use vexlake_core::index::{IndexConfig, VectorIndex};
+
+    let mut group = c.benchmark_group("index_norm_cache");
+
+    let dim = 128;
+    let query = random_vector(dim);
+    for cache_norms in [false, true] {
+        let mut index = VectorIndex::new(IndexConfig {
+            dimension: dim,
+            cache_norms,
+            ..Default::default()
+        });
+        for _ in 0..10000 {
+            index.insert(random_vector(dim)).unwrap();
+        }
+
+        let name = if cache_norms { "cached" } else { "uncached" };
+        group.bench_function(name, |bench| {
+            bench.iter(|| index.search(black_box(&query), black_box(10)).unwrap());
+        });
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_cosine_similarity,
     bench_l2_distance,
-    bench_brute_force_topk
+    bench_l2_squared_vs_sqrt,
+    bench_dispatch_overhead,
+    bench_simd_vs_scalar,
+    bench_brute_force_topk,
+    bench_index_norm_cache
 );
 criterion_main!(benches);
@@ -24,6 +24,10 @@ pub enum Error {
     #[error("Bincode error: {0}")]
     Bincode(String),
 
+    /// Decompressing stored data failed
+    #[error("Decompression error: {0}")]
+    Decompression(String),
+
     /// Index operation failed
     #[error("Index error: {0}")]
     Index(String),
@@ -40,6 +44,14 @@ pub enum Error {
     #[error("Invalid configuration: {0}")]
     InvalidConfig(String),
 
+    /// A concurrent writer got there first
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    /// Operation cancelled before completion
+    #[error("Cancelled after {completed} items")]
+    Cancelled { completed: usize },
+
     /// FFI error
     #[error("FFI error: {0}")]
     Ffi(String),
@@ -0,0 +1,94 @@
+//! Search quality evaluation against exact ground truth
+//!
+//! Used to tune `m`, `ef_construction` and `ef_search`: compare what an
+//! approximate index returns with brute-force results for the same queries.
+
+use std::collections::HashSet;
+
+use crate::vector::{brute_force_topk, SearchResult};
+
+/// Exact top-k neighbours of `query` by cosine similarity
+pub fn ground_truth(query: &[f32], vectors: &[(u64, Vec<f32>)], k: usize) -> Vec<SearchResult> {
+    brute_force_topk(query, vectors, k)
+}
+
+/// Fraction of the exact top-k ids found in the approximate top-k
+///
+/// Only the first `k` entries of each list are considered. The denominator
+/// is `min(k, exact.len())`; with nothing to find, recall is 1.0.
+pub fn recall_at_k(approx: &[SearchResult], exact: &[SearchResult], k: usize) -> f32 {
+    let expected: HashSet<u64> = exact.iter().take(k).map(|r| r.id).collect();
+    if expected.is_empty() {
+        return 1.0;
+    }
+    let found = approx
+        .iter()
+        .take(k)
+        .map(|r| r.id)
+        .collect::<HashSet<_>>()
+        .intersection(&expected)
+        .count();
+    found as f32 / expected.len() as f32
+}
+
+/// Mean of [`recall_at_k`] over paired approximate and exact results
+///
+/// Returns 1.0 for an empty batch. Panics if the batches differ in length.
+pub fn mean_recall_at_k(
+    approx: &[Vec<SearchResult>],
+    exact: &[Vec<SearchResult>],
+    k: usize,
+) -> f32 {
+    assert_eq!(
+        approx.len(),
+        exact.len(),
+        "approximate and exact batches must have the same length"
+    );
+    if approx.is_empty() {
+        return 1.0;
+    }
+    let total: f32 = approx
+        .iter()
+        .zip(exact)
+        .map(|(a, e)| recall_at_k(a, e, k))
+        .sum();
+    total / approx.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(ids: impl IntoIterator<Item = u64>) -> Vec<SearchResult> {
+        ids.into_iter()
+            .map(|id| SearchResult::new(id, 0.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_recall_at_k() {
+        let exact = results(0..10);
+        let approx = results((0..7).chain(100..103));
+        assert!((recall_at_k(&approx, &exact, 10) - 0.7).abs() < 1e-6);
+        assert_eq!(recall_at_k(&exact, &exact, 10), 1.0);
+        assert_eq!(recall_at_k(&results(100..110), &exact, 10), 0.0);
+        // Order within the top k does not matter, entries past k do
+        assert_eq!(recall_at_k(&results((0..5).rev()), &exact, 5), 1.0);
+        assert_eq!(recall_at_k(&results([9, 0]), &exact, 1), 0.0);
+        assert_eq!(recall_at_k(&[], &[], 10), 1.0);
+    }
+
+    #[test]
+    fn test_mean_recall_and_ground_truth() {
+        let vectors: Vec<(u64, Vec<f32>)> = (0..20)
+            .map(|i| (i, vec![(i as f32).cos(), (i as f32).sin()]))
+            .collect();
+        let query = [1.0, 0.0];
+        let exact = ground_truth(&query, &vectors, 4);
+        assert_eq!(exact, brute_force_topk(&query, &vectors, 4));
+
+        let half = results(exact.iter().take(2).map(|r| r.id).chain(100..102));
+        let mean = mean_recall_at_k(&[exact.clone(), half], &[exact.clone(), exact], 4);
+        assert!((mean - 0.75).abs() < 1e-6);
+    }
+}
@@ -1,16 +1,134 @@
 //! FFI exports for Go integration
 //!
 //! This module provides C-compatible FFI functions for the Go layer.
-//! Uses Arrow C Data Interface for zero-copy data exchange.
+//! Each index lives behind an opaque handle from `vexlake_create`, so one
+//! process can host many collections. The single-engine functions
+//! (`vexlake_init`, `vexlake_insert`, ...) operate on the reserved handle 0.
+//! Bulk results can be exported through the Arrow C Data Interface
+//! (`vexlake_search_arrow`) instead of JSON. Failures return a negative
+//! [`VexErrorCode`] and leave a message for `vexlake_last_error`.
+//! Indexes are persisted to local files with `vexlake_save` /
+//! `vexlake_index_save` and restored with `vexlake_load` / `vexlake_open`.
+//!
+//! Each index is a [`ConcurrentHnsw`], so searches on one handle run in
+//! parallel and only inserts are serialized.
 
+use arrow::array::{Array, ArrayRef, Float32Array, RecordBatch, StructArray, UInt64Array};
+use arrow::ffi::{to_ffi, FFI_ArrowArray, FFI_ArrowSchema};
 use once_cell::sync::Lazy;
-use std::ffi::{c_char, c_int, CString};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{c_char, c_int, CStr, CString};
 use std::panic::catch_unwind;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::index::hnsw::{ConcurrentHnsw, HnswConfig, HnswIndex};
+use crate::vector::DistanceMetric;
+use crate::Error;
+
+/// Handle of the engine used by the single-engine functions
+const DEFAULT_HANDLE: u64 = 0;
+
+static ENGINES: Lazy<RwLock<HashMap<u64, ConcurrentHnsw>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+static NEXT_HANDLE: AtomicU64 = AtomicU64::new(DEFAULT_HANDLE + 1);
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+/// Status codes returned by the FFI functions
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VexErrorCode {
+    /// Success
+    Ok = 0,
+    /// Any failure without a more specific code
+    Internal = -1,
+    /// Vector length does not match the index dimension
+    DimMismatch = -2,
+    /// No index for the handle (not initialized or already destroyed)
+    NotInitialized = -3,
+    /// A previous call panicked while holding the engine lock
+    LockPoisoned = -4,
+}
+
+impl From<&Error> for VexErrorCode {
+    fn from(err: &Error) -> Self {
+        match err {
+            Error::DimensionMismatch { .. } => VexErrorCode::DimMismatch,
+            _ => VexErrorCode::Internal,
+        }
+    }
+}
+
+/// Record `message` as this thread's last error and return `code`
+fn fail(code: VexErrorCode, message: impl std::fmt::Display) -> VexErrorCode {
+    let message = CString::new(message.to_string().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+    code
+}
+
+fn fail_with(err: Error) -> VexErrorCode {
+    fail((&err).into(), err)
+}
+
+/// Flatten a result into the C status code
+fn status(result: std::thread::Result<Result<(), VexErrorCode>>) -> c_int {
+    let code = match result {
+        Ok(Ok(())) => VexErrorCode::Ok,
+        Ok(Err(code)) => code,
+        Err(_) => fail(VexErrorCode::Internal, "panic in vexlake"),
+    };
+    code as c_int
+}
+
+fn poisoned<T>(_: T) -> VexErrorCode {
+    fail(VexErrorCode::LockPoisoned, "engine lock poisoned")
+}
 
-use crate::index::hnsw::{HnswConfig, HnswIndex};
+/// Shared access to the handle table, for using existing engines
+fn engines() -> Result<RwLockReadGuard<'static, HashMap<u64, ConcurrentHnsw>>, VexErrorCode> {
+    ENGINES.read().map_err(poisoned)
+}
 
-static ENGINE: Lazy<Mutex<Option<HnswIndex>>> = Lazy::new(|| Mutex::new(None));
+/// Exclusive access to the handle table, for adding and removing engines
+fn engines_mut() -> Result<RwLockWriteGuard<'static, HashMap<u64, ConcurrentHnsw>>, VexErrorCode> {
+    ENGINES.write().map_err(poisoned)
+}
+
+fn lookup(
+    engines: &HashMap<u64, ConcurrentHnsw>,
+    handle: u64,
+) -> Result<&ConcurrentHnsw, VexErrorCode> {
+    engines.get(&handle).ok_or_else(|| {
+        fail(
+            VexErrorCode::NotInitialized,
+            format!("no index for handle {}", handle),
+        )
+    })
+}
+
+/// Run `f` on the index behind `handle` under its shared lock
+fn with_engine<R>(
+    handle: u64,
+    f: impl FnOnce(&HnswIndex) -> crate::Result<R>,
+) -> Result<R, VexErrorCode> {
+    let engines = engines()?;
+    let engine = lookup(&engines, handle)?.read().map_err(poisoned)?;
+    f(&engine).map_err(fail_with)
+}
+
+/// Run `f` on the index behind `handle` under its exclusive lock
+fn with_engine_mut<R>(
+    handle: u64,
+    f: impl FnOnce(&mut HnswIndex) -> crate::Result<R>,
+) -> Result<R, VexErrorCode> {
+    let engines = engines()?;
+    let mut engine = lookup(&engines, handle)?.write().map_err(poisoned)?;
+    f(&mut engine).map_err(fail_with)
+}
 
 /// Health check - returns 1 if the library is functional
 #[no_mangle]
@@ -25,52 +143,165 @@ pub extern "C" fn vexlake_version() -> *const c_char {
     concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
 }
 
+/// Get the message for the most recent failure on the calling thread
+/// Returns an empty string if nothing has failed yet. Successful calls do not
+/// clear it. The pointer stays valid until the next failure on this thread;
+/// the caller must NOT free it.
+#[no_mangle]
+pub extern "C" fn vexlake_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ptr())
+}
+
 /// Initialize the VexLake engine
-/// Returns 0 on success, negative on error
+/// Returns 0 on success, a negative `VexErrorCode` on error
 #[no_mangle]
 pub extern "C" fn vexlake_init(dim: c_int) -> c_int {
+    if dim < 0 {
+        return fail(VexErrorCode::Internal, format!("invalid dimension {}", dim)) as c_int;
+    }
+    init_engine(HnswConfig {
+        dimension: dim as usize,
+        ..Default::default()
+    })
+}
+
+/// Initialize the VexLake engine with extended options
+/// `metric` selects the distance metric (0 = cosine, 1 = L2, 2 = dot, 3 = L1).
+/// A non-zero `auto_normalize` normalizes every inserted and queried vector.
+/// Returns 0 on success, a negative `VexErrorCode` on error
+#[no_mangle]
+pub extern "C" fn vexlake_init_ex(dim: c_int, metric: c_int, auto_normalize: c_int) -> c_int {
+    let metric = match metric {
+        0 => DistanceMetric::Cosine,
+        1 => DistanceMetric::L2,
+        2 => DistanceMetric::Dot,
+        3 => DistanceMetric::L1,
+        _ => return fail(VexErrorCode::Internal, format!("unknown metric {}", metric)) as c_int,
+    };
+    if dim < 0 {
+        return fail(VexErrorCode::Internal, format!("invalid dimension {}", dim)) as c_int;
+    }
+    init_engine(HnswConfig {
+        dimension: dim as usize,
+        metric,
+        auto_normalize: auto_normalize != 0,
+        ..Default::default()
+    })
+}
+
+fn init_engine(config: HnswConfig) -> c_int {
+    status(catch_unwind(|| {
+        engines_mut()?.insert(DEFAULT_HANDLE, HnswIndex::new(config).into());
+        Ok(())
+    }))
+}
+
+/// Create a new index and return its handle
+/// A `dim` of 0 takes the dimension from the first inserted vector.
+/// Returns 0 on error (0 is never a valid handle from this function)
+#[no_mangle]
+pub extern "C" fn vexlake_create(dim: c_int) -> u64 {
+    if dim < 0 {
+        fail(VexErrorCode::Internal, format!("invalid dimension {}", dim));
+        return 0;
+    }
     catch_unwind(|| {
-        let mut engine = ENGINE.lock().unwrap();
-        let config = HnswConfig {
+        let handle = NEXT_HANDLE.fetch_add(1, Ordering::Relaxed);
+        let index = HnswIndex::new(HnswConfig {
             dimension: dim as usize,
             ..Default::default()
-        };
-        *engine = Some(HnswIndex::new(config));
-        0
+        });
+        engines_mut().map_or(0, |mut engines| {
+            engines.insert(handle, index.into());
+            handle
+        })
     })
-    .unwrap_or(-1)
+    .unwrap_or(0)
+}
+
+/// Free the index behind a handle
+/// Returns 0 on success, a negative `VexErrorCode` on error
+#[no_mangle]
+pub extern "C" fn vexlake_destroy(handle: u64) -> c_int {
+    status(catch_unwind(|| match engines_mut()?.remove(&handle) {
+        Some(_) => Ok(()),
+        None => Err(fail(
+            VexErrorCode::NotInitialized,
+            format!("no index for handle {}", handle),
+        )),
+    }))
+}
+
+/// Initialize the VexLake engine without a fixed dimension
+/// The dimension is taken from the first inserted vector, and later inserts
+/// of a different length are rejected.
+/// Returns 0 on success, a negative `VexErrorCode` on error
+#[no_mangle]
+pub extern "C" fn vexlake_init_auto() -> c_int {
+    vexlake_init(0)
 }
 
 /// Shutdown the VexLake engine
 #[no_mangle]
 pub extern "C" fn vexlake_shutdown() {
-    let mut engine = ENGINE.lock().unwrap();
-    *engine = None;
+    vexlake_destroy(DEFAULT_HANDLE);
 }
 
 /// Insert a vector into the index
-/// Returns 0 on success, negative on error
+/// Returns 0 on success, a negative `VexErrorCode` on error
 ///
 /// # Safety
 /// The caller must ensure that `vec_ptr` points to a valid array of at least `len` f32 values.
 #[no_mangle]
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn vexlake_insert(id: u64, vec_ptr: *const f32, len: c_int) -> c_int {
-    catch_unwind(|| {
-        let mut engine_lock = ENGINE.lock().unwrap();
-        if let Some(engine) = engine_lock.as_mut() {
-            let vec = unsafe { std::slice::from_raw_parts(vec_ptr, len as usize) }.to_vec();
-            if engine.insert(id, vec).is_ok() {
-                return 0;
-            }
-        }
-        -1
-    })
-    .unwrap_or(-1)
+    vexlake_index_insert(DEFAULT_HANDLE, id, vec_ptr, len)
+}
+
+/// Insert a vector into the index behind a handle
+/// Returns 0 on success, a negative `VexErrorCode` on error
+///
+/// # Safety
+/// The caller must ensure that `vec_ptr` points to a valid array of at least `len` f32 values.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn vexlake_index_insert(
+    handle: u64,
+    id: u64,
+    vec_ptr: *const f32,
+    len: c_int,
+) -> c_int {
+    if vec_ptr.is_null() || len < 0 {
+        return fail(VexErrorCode::Internal, "null or negative-length vector") as c_int;
+    }
+    status(catch_unwind(|| {
+        let vec = unsafe { std::slice::from_raw_parts(vec_ptr, len as usize) }.to_vec();
+        with_engine_mut(handle, |engine| engine.insert(id, vec))
+    }))
+}
+
+/// Delete a vector from the index
+/// Returns 0 if it was removed, 1 if the ID was not present, or a negative
+/// `VexErrorCode` on error
+#[no_mangle]
+pub extern "C" fn vexlake_delete(id: u64) -> c_int {
+    vexlake_index_delete(DEFAULT_HANDLE, id)
+}
+
+/// Delete a vector from the index behind a handle
+/// Returns 0 if it was removed, 1 if the ID was not present, or a negative
+/// `VexErrorCode` on error
+#[no_mangle]
+pub extern "C" fn vexlake_index_delete(handle: u64, id: u64) -> c_int {
+    match catch_unwind(|| with_engine_mut(handle, |engine| engine.delete(id))) {
+        Ok(Ok(false)) => 1,
+        result => status(result.map(|r| r.map(drop))),
+    }
 }
 
 /// Search for the top K most similar vectors
-/// Returns a JSON string of results (caller must free via vexlake_free_string)
+/// Returns a JSON string of results (caller must free via vexlake_free_string),
+/// or null on error (see `vexlake_last_error`)
 ///
 /// # Safety
 /// The caller must ensure that `query_ptr` points to a valid array of at least `len` f32 values.
@@ -82,25 +313,259 @@ pub extern "C" fn vexlake_search(
     k: c_int,
     ef: c_int,
 ) -> *mut c_char {
+    vexlake_index_search(DEFAULT_HANDLE, query_ptr, len, k, ef)
+}
+
+/// Search the index behind a handle for the top K most similar vectors
+/// Returns a JSON string of results (caller must free via vexlake_free_string),
+/// or null on error (see `vexlake_last_error`)
+///
+/// # Safety
+/// The caller must ensure that `query_ptr` points to a valid array of at least `len` f32 values.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn vexlake_index_search(
+    handle: u64,
+    query_ptr: *const f32,
+    len: c_int,
+    k: c_int,
+    ef: c_int,
+) -> *mut c_char {
+    if query_ptr.is_null() || len < 0 {
+        fail(VexErrorCode::Internal, "null or negative-length query");
+        return std::ptr::null_mut();
+    }
+    if k < 0 || ef < 0 {
+        fail(
+            VexErrorCode::Internal,
+            format!("negative k {} or ef {}", k, ef),
+        );
+        return std::ptr::null_mut();
+    }
     let result = catch_unwind(|| {
-        let engine_lock = ENGINE.lock().unwrap();
-        if let Some(engine) = engine_lock.as_ref() {
-            let query = unsafe { std::slice::from_raw_parts(query_ptr, len as usize) };
-            if let Ok(results) = engine.search(query, k as usize, ef as usize) {
-                if let Ok(json) = serde_json::to_string(&results) {
-                    return CString::new(json).unwrap().into_raw();
-                }
-            }
-        }
-        std::ptr::null_mut()
+        let query = unsafe { std::slice::from_raw_parts(query_ptr, len as usize) };
+        let results = with_engine(handle, |engine| {
+            engine.search(query, k as usize, ef as usize)
+        })?;
+        let json = serde_json::to_string(&results).map_err(|e| fail_with(e.into()))?;
+        CString::new(json)
+            .map(CString::into_raw)
+            .map_err(|e| fail(VexErrorCode::Internal, e))
     });
-
     match result {
-        Ok(ptr) => ptr,
-        Err(_) => std::ptr::null_mut(),
+        Ok(Ok(ptr)) => ptr,
+        Ok(Err(_)) => std::ptr::null_mut(),
+        Err(_) => {
+            fail(VexErrorCode::Internal, "panic in vexlake");
+            std::ptr::null_mut()
+        }
     }
 }
 
+/// Search for the top K most similar vectors, exporting an Arrow record batch
+/// The batch is exported as a struct array with columns `id` (UInt64) and
+/// `score` (Float32), in rank order.
+/// Returns 0 on success, a negative `VexErrorCode` on error
+///
+/// # Ownership
+/// On success the caller owns the structs written to `out_array` and
+/// `out_schema` and must call each one's `release` callback exactly once when
+/// done; the buffers stay valid until then. On error nothing is written and
+/// there is nothing to release.
+///
+/// # Safety
+/// The caller must ensure that `query_ptr` points to a valid array of at least `len` f32 values,
+/// and that `out_array` and `out_schema` point to writable `ArrowArray` and `ArrowSchema` structs.
+/// Any previous contents of those structs are overwritten without being released.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn vexlake_search_arrow(
+    query_ptr: *const f32,
+    len: c_int,
+    k: c_int,
+    ef: c_int,
+    out_array: *mut FFI_ArrowArray,
+    out_schema: *mut FFI_ArrowSchema,
+) -> c_int {
+    vexlake_index_search_arrow(DEFAULT_HANDLE, query_ptr, len, k, ef, out_array, out_schema)
+}
+
+/// Search the index behind a handle, exporting an Arrow record batch
+/// Same layout and ownership rules as `vexlake_search_arrow`.
+/// Returns 0 on success, a negative `VexErrorCode` on error
+///
+/// # Safety
+/// Same requirements as `vexlake_search_arrow`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn vexlake_index_search_arrow(
+    handle: u64,
+    query_ptr: *const f32,
+    len: c_int,
+    k: c_int,
+    ef: c_int,
+    out_array: *mut FFI_ArrowArray,
+    out_schema: *mut FFI_ArrowSchema,
+) -> c_int {
+    if query_ptr.is_null() || out_array.is_null() || out_schema.is_null() || len < 0 {
+        return fail(VexErrorCode::Internal, "null pointer or negative length") as c_int;
+    }
+    if k < 0 || ef < 0 {
+        return fail(
+            VexErrorCode::Internal,
+            format!("negative k {} or ef {}", k, ef),
+        ) as c_int;
+    }
+
+    status(catch_unwind(|| {
+        let query = unsafe { std::slice::from_raw_parts(query_ptr, len as usize) };
+        let results = with_engine(handle, |engine| {
+            engine.search(query, k as usize, ef as usize)
+        })?;
+
+        let batch = RecordBatch::try_from_iter([
+            (
+                "id",
+                Arc::new(UInt64Array::from_iter_values(results.iter().map(|r| r.id))) as ArrayRef,
+            ),
+            (
+                "score",
+                Arc::new(Float32Array::from_iter_values(
+                    results.iter().map(|r| r.score),
+                )),
+            ),
+        ]);
+        let (array, schema) = batch
+            .and_then(|b| to_ffi(&StructArray::from(b).into_data()))
+            .map_err(|e| fail_with(e.into()))?;
+        unsafe {
+            std::ptr::write(out_array, array);
+            std::ptr::write(out_schema, schema);
+        }
+        Ok(())
+    }))
+}
+
+/// Borrow a path passed from C, rejecting null and invalid UTF-8
+fn c_path<'a>(path: *const c_char) -> Result<&'a str, VexErrorCode> {
+    if path.is_null() {
+        return Err(fail(VexErrorCode::Internal, "null path"));
+    }
+    unsafe { CStr::from_ptr(path) }
+        .to_str()
+        .map_err(|e| fail(VexErrorCode::Internal, format!("invalid path: {}", e)))
+}
+
+/// Read an index written by `vexlake_index_save`
+fn read_index(path: &str) -> Result<HnswIndex, VexErrorCode> {
+    let data = std::fs::read(path)
+        .map_err(|e| fail(VexErrorCode::Internal, format!("{}: {}", path, e)))?;
+    HnswIndex::deserialize_saved(&data, path).map_err(fail_with)
+}
+
+/// Save the engine to a local file
+/// Returns 0 on success, a negative `VexErrorCode` on error
+///
+/// # Safety
+/// The caller must ensure that `path` is null or a valid null-terminated string.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn vexlake_save(path: *const c_char) -> c_int {
+    vexlake_index_save(DEFAULT_HANDLE, path)
+}
+
+/// Save the index behind a handle to a local file
+/// The file is overwritten if it exists. Searches can continue while the
+/// index is serialized; inserts wait.
+/// Returns 0 on success, a negative `VexErrorCode` on error
+///
+/// # Safety
+/// The caller must ensure that `path` is null or a valid null-terminated string.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn vexlake_index_save(handle: u64, path: *const c_char) -> c_int {
+    status(catch_unwind(|| {
+        let path = c_path(path)?;
+        let data = with_engine(handle, |engine| engine.serialize())?;
+        std::fs::write(path, data)
+            .map_err(|e| fail(VexErrorCode::Internal, format!("{}: {}", path, e)))
+    }))
+}
+
+/// Replace the engine with an index loaded from a local file
+/// Works whether or not the engine was initialized. On error the current
+/// engine is left unchanged.
+/// Returns 0 on success, a negative `VexErrorCode` on error
+///
+/// # Safety
+/// The caller must ensure that `path` is null or a valid null-terminated string.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn vexlake_load(path: *const c_char) -> c_int {
+    status(catch_unwind(|| {
+        let index = read_index(c_path(path)?)?;
+        engines_mut()?.insert(DEFAULT_HANDLE, index.into());
+        Ok(())
+    }))
+}
+
+/// Load an index from a local file under a new handle
+/// Returns 0 on error (0 is never a valid handle from this function)
+///
+/// # Safety
+/// The caller must ensure that `path` is null or a valid null-terminated string.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn vexlake_open(path: *const c_char) -> u64 {
+    catch_unwind(|| {
+        let index = read_index(c_path(path)?)?;
+        let handle = NEXT_HANDLE.fetch_add(1, Ordering::Relaxed);
+        engines_mut()?.insert(handle, index.into());
+        Ok::<_, VexErrorCode>(handle)
+    })
+    .map_or(0, |result| result.unwrap_or(0))
+}
+
+/// Normalize `n` vectors of length `dim` in place to unit length
+/// The buffer is row-major; zero vectors are left unchanged.
+/// Returns 0 on success, negative on error
+///
+/// # Safety
+/// The caller must ensure that `vecs_ptr` points to a valid, writable array of at least `n * dim` f32 values.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn vexlake_normalize_batch(vecs_ptr: *mut f32, n: c_int, dim: c_int) -> c_int {
+    if vecs_ptr.is_null() || n < 0 || dim <= 0 {
+        return fail(
+            VexErrorCode::Internal,
+            "null buffer, negative count or non-positive dimension",
+        ) as c_int;
+    }
+    let Some(len) = (n as usize).checked_mul(dim as usize) else {
+        return fail(VexErrorCode::Internal, "n * dim overflows") as c_int;
+    };
+
+    status(catch_unwind(|| {
+        let vecs = unsafe { std::slice::from_raw_parts_mut(vecs_ptr, len) };
+        for v in vecs.chunks_exact_mut(dim as usize) {
+            crate::vector::normalize(v);
+        }
+        Ok(())
+    }))
+}
+
+/// Get operation latency percentiles as a JSON string
+/// Caller must free via vexlake_free_string
+#[no_mangle]
+pub extern "C" fn vexlake_metrics_json() -> *mut c_char {
+    catch_unwind(|| {
+        CString::new(crate::metrics::global().to_json())
+            .map(CString::into_raw)
+            .unwrap_or(std::ptr::null_mut())
+    })
+    .unwrap_or(std::ptr::null_mut())
+}
+
 /// Free a string allocated by Rust
 ///
 /// # Safety
@@ -118,7 +583,9 @@ pub extern "C" fn vexlake_free_string(ptr: *mut c_char) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::ffi::CStr;
+
+    /// Serializes tests that touch the global engine
+    static TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
 
     #[test]
     fn test_health_check() {
@@ -136,7 +603,285 @@ mod tests {
 
     #[test]
     fn test_init_shutdown() {
+        let _guard = TEST_LOCK.lock().unwrap();
         assert_eq!(vexlake_init(128), 0);
         vexlake_shutdown();
     }
+
+    #[test]
+    fn test_init_ex_auto_normalize() {
+        let _guard = TEST_LOCK.lock().unwrap();
+        assert_eq!(vexlake_init_ex(3, 7, 1), -1);
+        assert_eq!(vexlake_init_ex(3, 0, 1), 0);
+
+        let a = [3.0f32, 4.0, 0.0];
+        let b = [0.0f32, 0.0, 7.0];
+        assert_eq!(vexlake_insert(1, a.as_ptr(), 3), 0);
+        assert_eq!(vexlake_insert(2, b.as_ptr(), 3), 0);
+
+        let ptr = vexlake_search(a.as_ptr(), 3, 1, 10);
+        assert!(!ptr.is_null());
+        let json = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+        vexlake_free_string(ptr);
+
+        let results: Vec<crate::vector::SearchResult> = serde_json::from_str(&json).unwrap();
+        assert_eq!(results[0].id, 1);
+        assert!((results[0].score - 1.0).abs() < 1e-5);
+
+        vexlake_shutdown();
+    }
+
+    #[test]
+    fn test_init_auto() {
+        let _guard = TEST_LOCK.lock().unwrap();
+        assert_eq!(vexlake_init_auto(), 0);
+
+        let first = [1.0f32, 0.0, 0.0];
+        assert_eq!(vexlake_insert(1, first.as_ptr(), first.len() as c_int), 0);
+
+        let mismatched = [1.0f32, 0.0];
+        assert_eq!(
+            vexlake_insert(2, mismatched.as_ptr(), mismatched.len() as c_int),
+            VexErrorCode::DimMismatch as c_int
+        );
+
+        let second = [0.0f32, 1.0, 0.0];
+        assert_eq!(vexlake_insert(3, second.as_ptr(), second.len() as c_int), 0);
+
+        let ptr = vexlake_search(first.as_ptr(), first.len() as c_int, 1, 10);
+        assert!(!ptr.is_null());
+        let json = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+        vexlake_free_string(ptr);
+        assert!(json.contains("\"id\":1"));
+
+        vexlake_shutdown();
+    }
+
+    #[test]
+    fn test_metrics_json() {
+        let _guard = TEST_LOCK.lock().unwrap();
+        assert_eq!(vexlake_init(2), 0);
+        let v = [1.0f32, 0.0];
+        assert_eq!(vexlake_insert(1, v.as_ptr(), 2), 0);
+        vexlake_free_string(vexlake_search(v.as_ptr(), 2, 1, 10));
+        vexlake_shutdown();
+
+        let ptr = vexlake_metrics_json();
+        assert!(!ptr.is_null());
+        let json = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+        vexlake_free_string(ptr);
+
+        let metrics: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(metrics["search"]["count"].as_u64().unwrap() >= 1);
+        assert!(metrics["insert"]["count"].as_u64().unwrap() >= 1);
+        assert!(metrics["search"]["p99_us"].is_u64());
+    }
+
+    #[test]
+    fn test_search_arrow_roundtrip() {
+        use arrow::array::AsArray;
+        use arrow::datatypes::{Float32Type, UInt64Type};
+        use arrow::ffi::from_ffi;
+
+        let _guard = TEST_LOCK.lock().unwrap();
+        assert_eq!(vexlake_init(2), 0);
+        for (id, v) in [(1u64, [1.0f32, 0.0]), (2, [0.0, 1.0]), (3, [0.8, 0.6])] {
+            assert_eq!(vexlake_insert(id, v.as_ptr(), 2), 0);
+        }
+
+        let query = [1.0f32, 0.0];
+        let mut array = FFI_ArrowArray::empty();
+        let mut schema = FFI_ArrowSchema::empty();
+        assert_eq!(
+            vexlake_search_arrow(query.as_ptr(), 2, 3, 10, &mut array, &mut schema),
+            0
+        );
+        assert_eq!(
+            vexlake_search_arrow(query.as_ptr(), 3, 3, 10, &mut array, &mut schema),
+            VexErrorCode::DimMismatch as c_int
+        );
+        assert_eq!(
+            vexlake_search_arrow(query.as_ptr(), 2, -3, 10, &mut array, &mut schema),
+            VexErrorCode::Internal as c_int
+        );
+        vexlake_shutdown();
+
+        // Importing takes ownership; the batch outlives the engine
+        let data = unsafe { from_ffi(array, &schema) }.unwrap();
+        let batch = RecordBatch::from(StructArray::from(data));
+        assert_eq!(batch.schema().field(0).name(), "id");
+        assert_eq!(batch.schema().field(1).name(), "score");
+
+        let ids = batch.column(0).as_primitive::<UInt64Type>();
+        let scores = batch.column(1).as_primitive::<Float32Type>();
+        assert_eq!(ids.values(), &[1, 3, 2]);
+        assert!((scores.value(0) - 1.0).abs() < 1e-6);
+        assert!((scores.value(1) - 0.8).abs() < 1e-6);
+        assert!(scores.value(2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_multiple_handles() {
+        let a = vexlake_create(2);
+        let b = vexlake_create(3);
+        assert!(a != 0 && b != 0 && a != b);
+
+        let v2 = [1.0f32, 0.0];
+        let v3 = [0.0f32, 0.0, 1.0];
+        assert_eq!(vexlake_index_insert(a, 1, v2.as_ptr(), 2), 0);
+        assert_eq!(vexlake_index_insert(b, 2, v3.as_ptr(), 3), 0);
+        // Each index keeps its own dimension
+        assert_eq!(vexlake_index_insert(a, 3, v3.as_ptr(), 3), -2);
+        assert_eq!(vexlake_index_insert(b, 4, v2.as_ptr(), 2), -2);
+
+        let search = |handle, query: &[f32]| {
+            let ptr = vexlake_index_search(handle, query.as_ptr(), query.len() as c_int, 5, 10);
+            assert!(!ptr.is_null());
+            let json = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+            vexlake_free_string(ptr);
+            serde_json::from_str::<Vec<crate::vector::SearchResult>>(&json).unwrap()
+        };
+        assert_eq!(search(a, &v2)[0].id, 1);
+        assert_eq!(search(b, &v3)[0].id, 2);
+        assert_eq!(search(a, &v2).len(), 1);
+
+        assert_eq!(vexlake_destroy(a), 0);
+        assert_eq!(vexlake_destroy(a), VexErrorCode::NotInitialized as c_int);
+        assert!(vexlake_index_search(a, v2.as_ptr(), 2, 1, 10).is_null());
+        assert_eq!(search(b, &v3).len(), 1);
+        assert_eq!(vexlake_destroy(b), 0);
+        assert_eq!(vexlake_create(-1), 0);
+    }
+
+    #[test]
+    fn test_error_codes_and_last_error() {
+        let last_error = || {
+            unsafe { CStr::from_ptr(vexlake_last_error()) }
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+
+        let handle = vexlake_create(3);
+        let v = [1.0f32, 0.0, 0.0];
+        assert_eq!(vexlake_index_insert(handle, 1, v.as_ptr(), 3), 0);
+        assert_eq!(
+            vexlake_index_insert(handle, 2, v.as_ptr(), 2),
+            VexErrorCode::DimMismatch as c_int
+        );
+        let message = last_error();
+        assert!(message.contains("expected 3, got 2"), "{}", message);
+
+        // A success leaves the previous message in place
+        assert_eq!(vexlake_index_insert(handle, 3, v.as_ptr(), 3), 0);
+        assert_eq!(last_error(), message);
+
+        assert!(vexlake_index_search(handle, v.as_ptr(), 1, 1, 10).is_null());
+        assert!(last_error().contains("Dimension mismatch"));
+
+        assert_eq!(vexlake_destroy(handle), 0);
+        assert_eq!(
+            vexlake_index_insert(handle, 1, v.as_ptr(), 3),
+            VexErrorCode::NotInitialized as c_int
+        );
+        assert!(last_error().contains(&handle.to_string()));
+        assert_eq!(vexlake_init_ex(3, 9, 0), VexErrorCode::Internal as c_int);
+        assert_eq!(last_error(), "unknown metric 9");
+    }
+
+    #[test]
+    fn test_delete() {
+        let handle = vexlake_create(2);
+        let a = [1.0f32, 0.0];
+        let b = [0.8f32, 0.6];
+        assert_eq!(vexlake_index_insert(handle, 1, a.as_ptr(), 2), 0);
+        assert_eq!(vexlake_index_insert(handle, 2, b.as_ptr(), 2), 0);
+
+        assert_eq!(vexlake_index_delete(handle, 1), 0);
+        assert_eq!(vexlake_index_delete(handle, 1), 1);
+        let ptr = vexlake_index_search(handle, a.as_ptr(), 2, 5, 10);
+        assert!(!ptr.is_null());
+        let json = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+        vexlake_free_string(ptr);
+        let results: Vec<crate::vector::SearchResult> = serde_json::from_str(&json).unwrap();
+        assert_eq!(results.iter().map(|r| r.id).collect::<Vec<_>>(), vec![2]);
+
+        assert_eq!(vexlake_destroy(handle), 0);
+        assert_eq!(
+            vexlake_index_delete(handle, 2),
+            VexErrorCode::NotInitialized as c_int
+        );
+    }
+
+    #[test]
+    fn test_save_load() {
+        let _guard = TEST_LOCK.lock().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = CString::new(dir.path().join("engine.bin").to_str().unwrap()).unwrap();
+        let query = |handle| {
+            let q = [1.0f32, 0.1, 0.0];
+            let ptr = vexlake_index_search(handle, q.as_ptr(), 3, 1, 10);
+            assert!(!ptr.is_null());
+            let json = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+            vexlake_free_string(ptr);
+            serde_json::from_str::<Vec<crate::vector::SearchResult>>(&json).unwrap()
+        };
+
+        assert_eq!(vexlake_init(3), 0);
+        let a = [1.0f32, 0.0, 0.0];
+        let b = [0.0f32, 1.0, 0.0];
+        assert_eq!(vexlake_insert(1, a.as_ptr(), 3), 0);
+        assert_eq!(vexlake_insert(2, b.as_ptr(), 3), 0);
+        assert_eq!(vexlake_save(path.as_ptr()), 0);
+        vexlake_shutdown();
+        assert_eq!(vexlake_save(path.as_ptr()), -3);
+
+        assert_eq!(vexlake_load(path.as_ptr()), 0);
+        assert_eq!(query(DEFAULT_HANDLE)[0].id, 1);
+        // The loaded engine keeps its dimension
+        assert_eq!(vexlake_insert(3, a.as_ptr(), 2), -2);
+
+        let handle = vexlake_open(path.as_ptr());
+        assert_ne!(handle, 0);
+        assert_eq!(query(handle)[0].id, 1);
+        let q = [1.0f32, 0.1, 0.0];
+        assert!(vexlake_index_search(handle, q.as_ptr(), 3, -1, 10).is_null());
+        assert!(vexlake_index_search(handle, q.as_ptr(), 3, 1, -10).is_null());
+        assert_eq!(vexlake_destroy(handle), 0);
+        vexlake_shutdown();
+
+        let missing = CString::new(dir.path().join("missing.bin").to_str().unwrap()).unwrap();
+        assert_eq!(vexlake_load(missing.as_ptr()), -1);
+        assert_eq!(vexlake_open(missing.as_ptr()), 0);
+        assert_eq!(vexlake_load(std::ptr::null()), -1);
+        // An index without a dimension is rejected, as in HnswIndex::load
+        let empty = dir.path().join("empty.bin");
+        let config = HnswConfig {
+            dimension: 0,
+            ..Default::default()
+        };
+        std::fs::write(&empty, HnswIndex::new(config).serialize().unwrap()).unwrap();
+        let empty = CString::new(empty.to_str().unwrap()).unwrap();
+        assert_eq!(vexlake_load(empty.as_ptr()), -1);
+        assert_eq!(vexlake_open(empty.as_ptr()), 0);
+        let invalid = [0xffu8 as c_char, 0];
+        assert_eq!(vexlake_save(invalid.as_ptr()), -1);
+    }
+
+    #[test]
+    fn test_normalize_batch() {
+        let mut buf = vec![3.0f32, 4.0, 0.0, 0.0, 0.0, 2.0, 1.0, 1.0, 1.0];
+        assert_eq!(vexlake_normalize_batch(buf.as_mut_ptr(), 3, 3), 0);
+        for v in buf.chunks(3) {
+            let norm: f32 = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+            assert!((norm - 1.0).abs() < 1e-6);
+        }
+        assert!((buf[0] - 0.6).abs() < 1e-6);
+
+        assert_eq!(vexlake_normalize_batch(buf.as_mut_ptr(), 3, 0), -1);
+        assert_eq!(vexlake_normalize_batch(buf.as_mut_ptr(), -1, 3), -1);
+        assert_eq!(vexlake_normalize_batch(std::ptr::null_mut(), 1, 3), -1);
+        let message = unsafe { CStr::from_ptr(vexlake_last_error()) };
+        assert!(message.to_str().unwrap().contains("null buffer"));
+    }
 }
@@ -3,38 +3,231 @@
 //! Based on the paper: "Efficient and robust approximate nearest neighbor
 //! search using Hierarchical Navigable Small World graphs" by Yu. A. Malkov and D. A. Yashunin.
 
-use rand::{thread_rng, Rng};
+use arrow::array::{ArrayRef, Float32Array, RecordBatch, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::{Arc, LockResult, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Instant;
 
-use crate::vector::{cosine_similarity, SearchResult};
+use super::query_log::{now_ms, QueryLogHandle, QueryRecord};
+use super::Index;
+use crate::metrics;
+use crate::storage::{ParquetWriter, StorageClient};
+use crate::vector::{
+    cosine_similarity, cosine_similarity_stable, dot_product, l1_distance, l2_distance,
+    l2_distance_squared, normalize, DistanceMetric, Matrix, SearchResult,
+};
 use crate::{Error, Result};
 
+/// Neighbor count from which [`HnswIndex::insert_batch_with_progress`]
+/// expands in parallel
+const BATCH_PARALLEL_EXPAND: usize = 16;
+
+/// Header of [`HnswIndex::serialize`] output, before the format version
+const INDEX_MAGIC: &[u8; 4] = b"VXHI";
+/// Version of the [`HnswIndex::serialize`] layout; bump it when the layout changes
+const INDEX_FORMAT_VERSION: u32 = 1;
+
+/// Header of [`HnswIndex::serialize_compressed`] output, before the codec byte
+const COMPRESSED_MAGIC: &[u8; 4] = b"VXHC";
+const CODEC_GZIP: u8 = 1;
+const CODEC_ZSTD: u8 = 2;
+
+/// Largest group of vectors whose candidates [`HnswIndex::build`] searches
+/// in parallel
+const BUILD_CHUNK: usize = 1024;
+
+/// Layer-0 expansions, as a multiple of `ef`, past which a search is logged
+/// as a sign of graph degradation (with the `tracing` feature)
+#[cfg(feature = "tracing")]
+const DEGRADED_EXPANSION_FACTOR: usize = 2;
+
 /// Configuration for HNSW index
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HnswConfig {
-    /// Vector dimension
+    /// Vector dimension (0 to infer it from the first insert)
     pub dimension: usize,
+    /// Distance metric for graph construction and search
+    ///
+    /// Result scores follow the metric: similarity for cosine and dot
+    /// (higher is closer), distance for L2 and L1 (lower is closer).
+    pub metric: DistanceMetric,
     /// Maximum number of connections per node per layer
     pub m: usize,
     /// Max connections for layer 0
     pub m_max_0: usize,
     /// Construction parameter for search breadth
     pub ef_construction: usize,
+    /// Per-layer overrides of `ef_construction`, indexed by layer
+    ///
+    /// Upper layers hold few nodes and need less breadth than layer 0. Layers
+    /// beyond the end of the list use `ef_construction`.
+    pub ef_construction_per_layer: Vec<usize>,
     /// Scaling factor for layer level generation
     pub ml: f64,
+    /// Use compensated summation for distances (slower, more accurate at high dimensions)
+    pub stable_sums: bool,
+    /// Keep per-vector metadata in the index so searches can return it inline
+    pub store_metadata: bool,
+    /// Vectors are unit length, so distances come from L2 and skip the norm computation
+    ///
+    /// Scores are still reported as cosine similarity via
+    /// [`crate::vector::l2_sq_to_cosine`].
+    pub assume_normalized: bool,
+    /// Tombstone ratio above which the next insert rebuilds the graph (None disables)
+    pub auto_compact_ratio: Option<f32>,
+    /// Normalize every inserted and queried vector to unit length
+    pub auto_normalize: bool,
+    /// Linear transform applied to queries before search
+    ///
+    /// Supports asymmetric dual encoders whose query projection differs from
+    /// the stored documents. Must have `dimension` rows; its column count is
+    /// the expected query length.
+    pub query_transform: Option<Matrix>,
+    /// Number of results returned by [`HnswIndex::search_default`]
+    pub default_k: usize,
+    /// Search breadth used by [`HnswIndex::search_default`]
+    pub default_ef: usize,
+    /// Compute layer-0 neighbor distances in parallel for nodes with at least
+    /// this many neighbors (None disables)
+    ///
+    /// Heap updates stay sequential, so results are unchanged. Only pays off
+    /// for expensive distances, i.e. high dimensions.
+    pub parallel_expand_threshold: Option<usize>,
+    /// Probability (0.0-1.0) of keeping a rejected neighbor as a detour
+    ///
+    /// Once the greedy layer-0 search converges, the detours are expanded as
+    /// well, which lets queries escape local minima on hard data at the cost
+    /// of extra distance computations. 0.0 keeps the search purely greedy.
+    /// Only affects queries, not graph construction.
+    pub exploration: f32,
+    /// Pick neighbors with the diversity heuristic of the HNSW paper instead
+    /// of simply the closest candidates
+    pub select_heuristic: bool,
+    /// Also consider the candidates' own neighbors when selecting neighbors
+    ///
+    /// Only used with `select_heuristic`. Helps on heavily clustered data at
+    /// the cost of slower construction.
+    pub extend_candidates: bool,
+    /// Distance between old and new vector above which
+    /// [`HnswIndex::update_vector`] reselects the node's neighbors
+    pub repair_threshold: f32,
+    /// Seed for layer generation, for reproducible builds
+    ///
+    /// With a seed, inserting the same vectors in the same order builds an
+    /// identical graph. The RNG state is not serialized, so a deserialized
+    /// index restarts the sequence from the seed. Query-time `exploration`
+    /// stays unseeded.
+    pub seed: Option<u64>,
 }
 
 impl Default for HnswConfig {
     fn default() -> Self {
         Self {
             dimension: 128,
+            metric: DistanceMetric::Cosine,
             m: 16,
             m_max_0: 32,
             ef_construction: 200,
+            ef_construction_per_layer: Vec::new(),
             ml: 1.0 / (16.0f64).ln(), // 1/ln(M)
+            stable_sums: false,
+            store_metadata: false,
+            assume_normalized: false,
+            auto_compact_ratio: None,
+            auto_normalize: false,
+            query_transform: None,
+            default_k: 10,
+            default_ef: 50,
+            parallel_expand_threshold: None,
+            exploration: 0.0,
+            select_heuristic: true,
+            extend_candidates: false,
+            repair_threshold: 0.05,
+            seed: None,
+        }
+    }
+}
+
+impl HnswConfig {
+    /// Derive a starting configuration from the dataset size and dimension
+    ///
+    /// `m` grows logarithmically with the dimension and `ef_construction` with
+    /// the number of vectors, following the ranges suggested in the HNSW paper
+    /// (`m` of 5-48, `ml` of 1/ln(m)). Other fields keep their defaults.
+    pub fn recommended(n_vectors: usize, dimension: usize) -> Self {
+        let m = ((dimension.max(2) as f64).log2() * 2.0).round() as usize;
+        let m = m.clamp(8, 48);
+        let ef_construction = (50.0 * (n_vectors.max(10) as f64).log10()) as usize;
+        let ef_construction = ef_construction.clamp(100, 800).max(2 * m);
+
+        Self {
+            dimension,
+            m,
+            m_max_0: 2 * m,
+            ef_construction,
+            ml: 1.0 / (m as f64).ln(),
+            ..Default::default()
+        }
+    }
+
+    /// Check that the configuration is usable
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |msg: String| Err(Error::InvalidConfig(msg));
+
+        if self.m < 2 {
+            return invalid(format!("m must be at least 2, got {}", self.m));
         }
+        if self.m_max_0 < self.m {
+            return invalid(format!(
+                "m_max_0 ({}) must be at least m ({})",
+                self.m_max_0, self.m
+            ));
+        }
+        if self.ef_construction == 0 || self.ef_construction_per_layer.contains(&0) {
+            return invalid("ef_construction must be positive".to_string());
+        }
+        if !(self.ml.is_finite() && self.ml > 0.0) {
+            return invalid(format!("ml must be positive and finite, got {}", self.ml));
+        }
+        if let Some(ratio) = self.auto_compact_ratio {
+            if !(ratio > 0.0 && ratio <= 1.0) {
+                return invalid(format!(
+                    "auto_compact_ratio must be in (0, 1], got {}",
+                    ratio
+                ));
+            }
+        }
+        if let Some(transform) = &self.query_transform {
+            if self.dimension != 0 && transform.rows() != self.dimension {
+                return invalid(format!(
+                    "query_transform has {} rows, expected {}",
+                    transform.rows(),
+                    self.dimension
+                ));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.exploration) {
+            return invalid(format!(
+                "exploration must be within [0, 1], got {}",
+                self.exploration
+            ));
+        }
+        Ok(())
+    }
+
+    /// Get the construction search breadth for a layer
+    pub fn ef_construction_for(&self, layer: usize) -> usize {
+        self.ef_construction_per_layer
+            .get(layer)
+            .copied()
+            .unwrap_or(self.ef_construction)
     }
 }
 
@@ -47,6 +240,85 @@ pub struct HnswNode {
     pub vector: Vec<f32>,
     /// Neighbors at each layer (layer_idx -> neighbors)
     pub neighbors: Vec<Vec<u64>>,
+    /// Optional metadata (only kept when `store_metadata` is enabled)
+    pub metadata: Option<String>,
+}
+
+/// How [`HnswIndex::search_boolean`] combines per-query result sets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoolOp {
+    /// IDs found for every query, scored by their worst score
+    And,
+    /// IDs found for any query, scored by their best score
+    Or,
+}
+
+/// Work done by one search, from [`HnswIndex::search_with_stats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchStats {
+    /// Distances computed, in the upper-layer descent and at layer 0
+    pub distance_computations: usize,
+    /// Nodes whose neighbor lists were scanned, over all layers
+    pub nodes_visited: usize,
+    /// Moves to a closer node while descending the upper layers
+    pub hops: usize,
+}
+
+/// Codec for [`HnswIndex::serialize_compressed`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Zstandard at `level` (1-22; 3 is zstd's default)
+    Zstd { level: i32 },
+    /// Gzip at the default level
+    Gzip,
+}
+
+/// Graph size statistics
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GraphStats {
+    /// Number of nodes present at each layer, starting from layer 0
+    pub nodes_per_layer: Vec<usize>,
+    /// Total directed edges across all layers
+    pub total_edges: usize,
+    /// Number of soft-deleted nodes
+    pub tombstones: usize,
+}
+
+impl GraphStats {
+    fn add_node(&mut self, node: &HnswNode) {
+        if self.nodes_per_layer.len() < node.neighbors.len() {
+            self.nodes_per_layer.resize(node.neighbors.len(), 0);
+        }
+        for (layer, neighbors) in node.neighbors.iter().enumerate() {
+            self.nodes_per_layer[layer] += 1;
+            self.total_edges += neighbors.len();
+        }
+    }
+
+    fn remove_node(&mut self, node: &HnswNode) {
+        for (layer, neighbors) in node.neighbors.iter().enumerate() {
+            self.nodes_per_layer[layer] -= 1;
+            self.total_edges -= neighbors.len();
+        }
+        while self.nodes_per_layer.last() == Some(&0) {
+            self.nodes_per_layer.pop();
+        }
+    }
+}
+
+/// Size and shape summary from [`HnswIndex::stats`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HnswStats {
+    /// Stored nodes, including soft-deleted ones
+    pub num_nodes: usize,
+    /// Highest layer in the graph (0 when empty)
+    pub max_layer: usize,
+    /// Node where searches start
+    pub entry_point: Option<u64>,
+    /// Mean layer-0 neighbor count
+    pub avg_degree_layer0: f32,
+    /// Approximate heap bytes held by vectors, adjacency lists and metadata
+    pub memory_estimate_bytes: usize,
 }
 
 /// Comparison wrapper for Min-Heap (closest first)
@@ -98,6 +370,26 @@ impl Ord for MaxCandidate {
     }
 }
 
+/// Search results yielded closest first from a min-heap
+struct StreamedResults<'a> {
+    index: &'a HnswIndex,
+    heap: BinaryHeap<MinCandidate>,
+}
+
+impl Iterator for StreamedResults<'_> {
+    type Item = SearchResult;
+
+    fn next(&mut self) -> Option<SearchResult> {
+        self.heap
+            .pop()
+            .map(|c| SearchResult::new(c.id, self.index.score(c.distance)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.heap.len(), Some(self.heap.len()))
+    }
+}
+
 /// Hierarchical Navigable Small World Index
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HnswIndex {
@@ -105,6 +397,20 @@ pub struct HnswIndex {
     nodes: HashMap<u64, HnswNode>,
     entry_point: Option<u64>,
     max_layer: i32,
+    /// Soft-deleted IDs, still linked in the graph but excluded from results
+    tombstones: HashSet<u64>,
+    /// Running node and edge counters, kept in step with `nodes`
+    stats: GraphStats,
+    /// Destination for search records, if query logging is enabled
+    #[serde(skip)]
+    query_log: Option<QueryLogHandle>,
+    /// Layer generator when `config.seed` is set, created on first use
+    #[serde(skip)]
+    rng: Option<StdRng>,
+    /// Sources `(node, layer)` of the edges into each node, built on the
+    /// first removal and kept in step with every edge change after that
+    #[serde(skip)]
+    incoming: Option<HashMap<u64, HashSet<(u64, usize)>>>,
 }
 
 impl HnswIndex {
@@ -115,26 +421,275 @@ impl HnswIndex {
             nodes: HashMap::new(),
             entry_point: None,
             max_layer: -1,
+            tombstones: HashSet::new(),
+            stats: GraphStats::default(),
+            query_log: None,
+            rng: None,
+            incoming: None,
+        }
+    }
+
+    /// Build an index from a full dataset
+    ///
+    /// Faster than inserting the items one by one on multi-core machines.
+    /// Items are added in chunks: the construction search for every vector of
+    /// a chunk runs in parallel against the graph built so far, then the
+    /// vectors are linked one at a time, each also considering the chunk's
+    /// earlier vectors as neighbor candidates since the searches could not see
+    /// them. Chunks start at a single vector and grow with the graph (an
+    /// eighth of its size, at most 1024), so the early graph is built exactly
+    /// as by sequential inserts.
+    ///
+    /// The distance work is that of sequential inserts, about
+    /// O(n log n * ef_construction * d), plus O(n * chunk * d) for the
+    /// in-chunk candidates; the searches, which dominate, are spread across
+    /// the rayon pool while linking stays sequential. Items are taken in the
+    /// given order; with `seed` set the build is reproducible. The graph is
+    /// not identical to one from sequential inserts, but of the same quality.
+    ///
+    /// Fails before building anything if the config is invalid, a vector has
+    /// the wrong dimension or an ID appears twice.
+    pub fn build(config: HnswConfig, mut items: Vec<(u64, Vec<f32>)>) -> Result<Self> {
+        config.validate()?;
+        let mut index = Self::new(config);
+
+        let expected = match index.config.dimension {
+            0 => items.first().map_or(0, |(_, v)| v.len()),
+            dimension => dimension,
+        };
+        if let Some((_, v)) = items.iter().find(|(_, v)| v.len() != expected) {
+            return Err(Error::DimensionMismatch {
+                expected,
+                actual: v.len(),
+            });
+        }
+        let mut ids = HashSet::with_capacity(items.len());
+        if let Some((id, _)) = items.iter().find(|(id, _)| !ids.insert(*id)) {
+            return Err(Error::InvalidConfig(format!("duplicate id {}", id)));
+        }
+        if items.is_empty() {
+            return Ok(index);
+        }
+        index.config.dimension = expected;
+        if index.config.auto_normalize {
+            items.par_iter_mut().for_each(|(_, v)| normalize(v));
+        }
+
+        let mut items = items.into_iter();
+        loop {
+            let chunk_len = (index.nodes.len() / 8).clamp(1, BUILD_CHUNK);
+            let chunk: Vec<(u64, Vec<f32>, i32)> = items
+                .by_ref()
+                .take(chunk_len)
+                .map(|(id, vector)| (id, vector, index.generate_random_layer()))
+                .collect();
+            if chunk.is_empty() {
+                break;
+            }
+
+            let candidates: Vec<Vec<Vec<(u64, f32)>>> = chunk
+                .par_iter()
+                .map(|(_, vector, level)| index.construction_candidates(vector, *level))
+                .collect();
+            let mut linked = Vec::with_capacity(chunk.len());
+            for ((id, vector, level), layers) in chunk.into_iter().zip(candidates) {
+                index.link_with_candidates(id, vector, level, layers, &linked);
+                linked.push(id);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Construction candidates for a vector at each layer up to `level`
+    ///
+    /// Each layer's list is sorted closest first. Empty for an empty graph.
+    fn construction_candidates(&self, vector: &[f32], level: i32) -> Vec<Vec<(u64, f32)>> {
+        if self.entry_point.is_none() {
+            return Vec::new();
+        }
+        let top = level.min(self.max_layer);
+        let mut ep = self.descend_to(vector, top);
+        let mut layers = vec![Vec::new(); top as usize + 1];
+        for l in (0..=top as usize).rev() {
+            let ef = self.config.ef_construction_for(l);
+            let found: Vec<(u64, f32)> = self
+                .search_layer(vector, ep, ef, l, 0.0)
+                .into_sorted_vec()
+                .into_iter()
+                .map(|c| (c.id, c.distance))
+                .collect();
+            if let Some(&(closest, _)) = found.first() {
+                ep = closest;
+            }
+            layers[l] = found;
+        }
+        layers
+    }
+
+    /// Link a vector using precomputed candidates plus the nodes in `batch`
+    ///
+    /// `batch` holds nodes linked after the candidates were searched.
+    fn link_with_candidates(
+        &mut self,
+        id: u64,
+        vector: Vec<f32>,
+        level: i32,
+        mut layers: Vec<Vec<(u64, f32)>>,
+        batch: &[u64],
+    ) {
+        if self.entry_point.is_none() {
+            // Only the very first vector; linking an empty graph cannot fail
+            let _ = self.link_node(id, vector, None, level, None);
+            return;
+        }
+
+        let mut new_node = HnswNode {
+            id,
+            vector,
+            neighbors: vec![vec![]; (level + 1) as usize],
+            metadata: None,
+        };
+        for l in (0..=level.min(self.max_layer) as usize).rev() {
+            let mut candidates = if l < layers.len() {
+                std::mem::take(&mut layers[l])
+            } else {
+                Vec::new()
+            };
+            for &other in batch {
+                let node = &self.nodes[&other];
+                if l < node.neighbors.len() {
+                    candidates.push((other, self.distance(&new_node.vector, &node.vector)));
+                }
+            }
+            candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+
+            let m = if l == 0 {
+                self.config.m_max_0
+            } else {
+                self.config.m
+            };
+            let vector = &new_node.vector;
+            let neighbor_ids = self.select_neighbors((id, vector), candidates, m, l, (id, vector));
+            self.link_back(id, vector, &neighbor_ids, l, m);
+            new_node.neighbors[l] = neighbor_ids;
+        }
+
+        self.put_node(new_node);
+        if level > self.max_layer {
+            self.max_layer = level;
+            self.entry_point = Some(id);
+        }
+    }
+
+    /// Change `exploration` on a built index
+    pub fn set_exploration(&mut self, exploration: f32) -> Result<()> {
+        if !(0.0..=1.0).contains(&exploration) {
+            return Err(Error::InvalidConfig(format!(
+                "exploration must be within [0, 1], got {}",
+                exploration
+            )));
+        }
+        self.config.exploration = exploration;
+        Ok(())
+    }
+
+    /// Change `parallel_expand_threshold` on a built index
+    ///
+    /// Only affects how searches compute distances, never the graph.
+    pub fn set_parallel_expand_threshold(&mut self, threshold: Option<usize>) {
+        self.config.parallel_expand_threshold = threshold;
+    }
+
+    /// Enable or disable query logging for this index
+    pub fn set_query_log(&mut self, handle: Option<QueryLogHandle>) {
+        self.query_log = handle;
+    }
+
+    /// Internal distance, smaller is closer for every metric
+    fn distance(&self, a: &[f32], b: &[f32]) -> f32 {
+        match self.config.metric {
+            DistanceMetric::Cosine => self.cosine_distance(a, b),
+            DistanceMetric::L2 => l2_distance(a, b),
+            DistanceMetric::Dot => -dot_product(a, b),
+            DistanceMetric::L1 => l1_distance(a, b),
+        }
+    }
+
+    /// Convert an internal distance to the metric's result score
+    fn score(&self, distance: f32) -> f32 {
+        match self.config.metric {
+            DistanceMetric::Cosine => 1.0 - distance,
+            DistanceMetric::Dot => -distance,
+            DistanceMetric::L2 | DistanceMetric::L1 => distance,
+        }
+    }
+
+    fn cosine_distance(&self, a: &[f32], b: &[f32]) -> f32 {
+        if self.config.assume_normalized {
+            // 1 - cos = ||a - b||² / 2 for unit vectors
+            0.5 * l2_distance_squared(a, b)
+        } else if self.config.stable_sums {
+            1.0 - cosine_similarity_stable(a, b)
+        } else {
+            1.0 - cosine_similarity(a, b)
         }
     }
 
     fn get_distance(&self, q: &[f32], target_id: u64) -> f32 {
         let target_node = self.nodes.get(&target_id).expect("Node must exist");
-        1.0 - cosine_similarity(q, &target_node.vector)
+        self.distance(q, &target_node.vector)
     }
 
     /// Search for the nearest neighbors at a specific layer
+    ///
+    /// With a nonzero `exploration`, neighbors rejected by the greedy rule
+    /// are kept with that probability and expanded once the greedy search
+    /// has converged.
     fn search_layer(
         &self,
         q: &[f32],
         ep: u64,
         ef: usize,
         layer: usize,
+        exploration: f32,
+    ) -> BinaryHeap<MaxCandidate> {
+        let mut stats = SearchStats::default();
+        let parallel_expand = self.config.parallel_expand_threshold;
+        self.search_layer_counted(
+            q,
+            ep,
+            ef,
+            layer,
+            exploration,
+            parallel_expand,
+            false,
+            &mut stats,
+        )
+    }
+
+    /// [`Self::search_layer`] with an explicit parallel expansion threshold,
+    /// adding its work to `stats`
+    ///
+    /// With `live_only`, soft-deleted nodes are traversed but never enter the
+    /// result set, so up to `ef` live nodes are returned.
+    #[allow(clippy::too_many_arguments)]
+    fn search_layer_counted(
+        &self,
+        q: &[f32],
+        ep: u64,
+        ef: usize,
+        layer: usize,
+        exploration: f32,
+        parallel_expand: Option<usize>,
+        live_only: bool,
+        stats: &mut SearchStats,
     ) -> BinaryHeap<MaxCandidate> {
+        let is_result = |id: &u64| !live_only || !self.tombstones.contains(id);
         let mut visited = HashSet::new();
         visited.insert(ep);
 
         let dist = self.get_distance(q, ep);
+        stats.distance_computations += 1;
         let mut candidates = BinaryHeap::new();
         candidates.push(MinCandidate {
             id: ep,
@@ -142,51 +697,283 @@ impl HnswIndex {
         });
 
         let mut found_neighbors = BinaryHeap::new();
-        found_neighbors.push(MaxCandidate {
-            id: ep,
-            distance: dist,
-        });
+        if is_result(&ep) {
+            found_neighbors.push(MaxCandidate {
+                id: ep,
+                distance: dist,
+            });
+        }
 
-        while let Some(current_candidate) = candidates.pop() {
-            let furthest_neighbor = found_neighbors.peek().unwrap();
-            if current_candidate.distance > furthest_neighbor.distance {
-                break;
-            }
+        let mut expanded: Vec<(u64, f32)> = Vec::new();
+        let mut detours: Vec<MinCandidate> = Vec::new();
+        let mut rng = thread_rng();
+        // No result yet (all soft-deleted) bounds nothing
+        let furthest =
+            |found: &BinaryHeap<MaxCandidate>| found.peek().map_or(f32::INFINITY, |c| c.distance);
+        loop {
+            // Skipped soft-deleted nodes keep the search going until `ef`
+            // live results are found
+            let current_candidate = match candidates.pop() {
+                Some(c)
+                    if c.distance <= furthest(&found_neighbors)
+                        || (live_only && found_neighbors.len() < ef) =>
+                {
+                    c
+                }
+                // Greedy search converged; follow a detour if any
+                _ => match detours.pop() {
+                    Some(c) => c,
+                    None => break,
+                },
+            };
 
             if let Some(node) = self.nodes.get(&current_candidate.id) {
                 if layer < node.neighbors.len() {
-                    for &neighbor_id in &node.neighbors[layer] {
-                        if visited.insert(neighbor_id) {
-                            let neighbor_dist = self.get_distance(q, neighbor_id);
-                            let furthest_in_found = found_neighbors.peek().unwrap();
-
-                            if neighbor_dist < furthest_in_found.distance
-                                || found_neighbors.len() < ef
-                            {
-                                candidates.push(MinCandidate {
-                                    id: neighbor_id,
-                                    distance: neighbor_dist,
-                                });
+                    let neighbors = &node.neighbors[layer];
+                    let parallel =
+                        layer == 0 && parallel_expand.is_some_and(|t| neighbors.len() >= t);
+
+                    // Unvisited neighbors with their distances, in list order
+                    expanded.clear();
+                    if parallel {
+                        let fresh: Vec<u64> = neighbors
+                            .iter()
+                            .copied()
+                            .filter(|&id| visited.insert(id))
+                            .collect();
+                        expanded.par_extend(
+                            fresh
+                                .into_par_iter()
+                                .map(|id| (id, self.get_distance(q, id))),
+                        );
+                    } else {
+                        expanded.extend(
+                            neighbors
+                                .iter()
+                                .filter(|&&id| visited.insert(id))
+                                .map(|&id| (id, self.get_distance(q, id))),
+                        );
+                    }
+                    stats.nodes_visited += 1;
+                    stats.distance_computations += expanded.len();
+
+                    for &(neighbor_id, neighbor_dist) in &expanded {
+                        if neighbor_dist < furthest(&found_neighbors) || found_neighbors.len() < ef
+                        {
+                            candidates.push(MinCandidate {
+                                id: neighbor_id,
+                                distance: neighbor_dist,
+                            });
+                            if is_result(&neighbor_id) {
                                 found_neighbors.push(MaxCandidate {
                                     id: neighbor_id,
                                     distance: neighbor_dist,
                                 });
-
                                 if found_neighbors.len() > ef {
                                     found_neighbors.pop();
                                 }
                             }
+                        } else if exploration > 0.0 && rng.gen::<f32>() < exploration {
+                            detours.push(MinCandidate {
+                                id: neighbor_id,
+                                distance: neighbor_dist,
+                            });
                         }
                     }
                 }
             }
         }
 
+        #[cfg(feature = "tracing")]
+        if layer == 0 {
+            self.trace_visits(ef, visited.len());
+        }
         found_neighbors
     }
 
+    /// Record a layer-0 visit count on the current span
+    ///
+    /// Each expansion visits at most `m_max_0` new nodes, so more visits than
+    /// `DEGRADED_EXPANSION_FACTOR * ef * m_max_0` means the search expanded
+    /// far more nodes than `ef`, typically because deletes or updates left
+    /// the graph poorly connected.
+    #[cfg(feature = "tracing")]
+    fn trace_visits(&self, ef: usize, visited: usize) {
+        tracing::Span::current().record("visited", visited);
+        let limit = DEGRADED_EXPANSION_FACTOR * ef * self.config.m_max_0.max(1);
+        if visited > limit {
+            tracing::warn!(
+                ef,
+                visited,
+                limit,
+                "HNSW search visited unusually many nodes; graph may be degraded"
+            );
+        }
+    }
+
     /// Insert a vector into the index
     pub fn insert(&mut self, id: u64, vector: Vec<f32>) -> Result<()> {
+        self.insert_with_metadata(id, vector, None)
+    }
+
+    /// Insert a batch of vectors, optionally stopping when `stop` is set
+    ///
+    /// The flag is checked before each insert. On cancellation this returns
+    /// [`Error::Cancelled`] and the vectors inserted so far remain searchable.
+    /// Returns the number of vectors inserted.
+    pub fn insert_batch<I>(&mut self, items: I, stop: Option<&AtomicBool>) -> Result<usize>
+    where
+        I: IntoIterator<Item = (u64, Vec<f32>)>,
+    {
+        let mut inserted = 0;
+        for (id, vector) in items {
+            if stop.is_some_and(|s| s.load(AtomicOrdering::Relaxed)) {
+                return Err(Error::Cancelled {
+                    completed: inserted,
+                });
+            }
+            self.insert(id, vector)?;
+            inserted += 1;
+        }
+        Ok(inserted)
+    }
+
+    /// Insert a batch of vectors, reporting progress
+    ///
+    /// All dimensions are checked before anything is inserted, so a bad vector
+    /// leaves the index untouched. Layer-0 neighbor distances are computed in
+    /// parallel (unless `parallel_expand_threshold` is already set), while the
+    /// graph is still mutated one vector at a time, so the result matches
+    /// inserting the items in order. `on_progress` is called with the number
+    /// of vectors inserted so far after each insert.
+    pub fn insert_batch_with_progress(
+        &mut self,
+        items: &[(u64, Vec<f32>)],
+        on_progress: Option<&dyn Fn(usize)>,
+    ) -> Result<()> {
+        let expected = match self.config.dimension {
+            0 if self.entry_point.is_none() => items.first().map_or(0, |(_, v)| v.len()),
+            dimension => dimension,
+        };
+        if let Some((_, v)) = items.iter().find(|(_, v)| v.len() != expected) {
+            return Err(Error::DimensionMismatch {
+                expected,
+                actual: v.len(),
+            });
+        }
+
+        let parallel_expand = self
+            .config
+            .parallel_expand_threshold
+            .or(Some(BATCH_PARALLEL_EXPAND));
+        for (i, (id, vector)) in items.iter().enumerate() {
+            self.insert_recorded(*id, vector.clone(), None, parallel_expand)?;
+            if let Some(on_progress) = on_progress {
+                on_progress(i + 1);
+            }
+        }
+        Ok(())
+    }
+
+    /// Insert every live vector of `other` into this index
+    ///
+    /// For consolidating indexes built per shard. Vectors go through the
+    /// regular insert path in ID order, so the merged graph is as valid as one
+    /// built by inserting everything; metadata is carried over. The metrics
+    /// must match, and so must the dimensions unless this index is empty with
+    /// an inferred dimension. An ID present in both fails the merge before
+    /// anything is inserted, unless `overwrite` is set, in which case the
+    /// vector from `other` replaces this index's one.
+    pub fn merge(&mut self, other: HnswIndex, overwrite: bool) -> Result<()> {
+        if other.config.metric != self.config.metric {
+            return Err(Error::InvalidConfig(format!(
+                "cannot merge a {:?} index into a {:?} index",
+                other.config.metric, self.config.metric
+            )));
+        }
+        let inferring = self.config.dimension == 0 && self.entry_point.is_none();
+        if !inferring && !other.nodes.is_empty() && other.config.dimension != self.config.dimension
+        {
+            return Err(Error::DimensionMismatch {
+                expected: self.config.dimension,
+                actual: other.config.dimension,
+            });
+        }
+
+        let mut nodes: Vec<HnswNode> = other
+            .nodes
+            .into_values()
+            .filter(|n| !other.tombstones.contains(&n.id))
+            .collect();
+        nodes.sort_by_key(|n| n.id);
+
+        if !overwrite {
+            let live = |id: &u64| self.nodes.contains_key(id) && !self.tombstones.contains(id);
+            if let Some(node) = nodes.iter().find(|n| live(&n.id)) {
+                return Err(Error::Index(format!(
+                    "vector {} exists in both indexes",
+                    node.id
+                )));
+            }
+        }
+
+        for node in nodes {
+            self.remove_node(node.id, true);
+            self.insert_with_metadata(node.id, node.vector, node.metadata)?;
+        }
+        Ok(())
+    }
+
+    /// Insert a vector with associated metadata
+    ///
+    /// The metadata is discarded unless `store_metadata` is enabled.
+    pub fn insert_with_metadata(
+        &mut self,
+        id: u64,
+        vector: Vec<f32>,
+        metadata: Option<String>,
+    ) -> Result<()> {
+        let parallel_expand = self.config.parallel_expand_threshold;
+        self.insert_recorded(id, vector, metadata, parallel_expand)
+    }
+
+    /// Insert under the tracing span and insert metrics
+    fn insert_recorded(
+        &mut self,
+        id: u64,
+        vector: Vec<f32>,
+        metadata: Option<String>,
+        parallel_expand: Option<usize>,
+    ) -> Result<()> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "hnsw_insert",
+            id,
+            ef_construction = self.config.ef_construction,
+            visited = tracing::field::Empty,
+        )
+        .entered();
+
+        let start = Instant::now();
+        let result = self.insert_node(id, vector, metadata, parallel_expand);
+        metrics::global().insert.record(start.elapsed());
+        result
+    }
+
+    fn insert_node(
+        &mut self,
+        id: u64,
+        mut vector: Vec<f32>,
+        metadata: Option<String>,
+        parallel_expand: Option<usize>,
+    ) -> Result<()> {
+        let metadata = metadata.filter(|_| self.config.store_metadata);
+
+        // A zero dimension is fixed by the first vector inserted
+        if self.config.dimension == 0 && self.entry_point.is_none() && !vector.is_empty() {
+            self.config.dimension = vector.len();
+        }
+
         if vector.len() != self.config.dimension {
             return Err(Error::DimensionMismatch {
                 expected: self.config.dimension,
@@ -194,108 +981,96 @@ impl HnswIndex {
             });
         }
 
+        if self.config.auto_normalize {
+            normalize(&mut vector);
+        }
+
+        if self.needs_compaction() {
+            self.compact()?;
+        }
+        // A re-inserted id is relinked from scratch, as in `merge`, so none
+        // of its old edges survive and it cannot pick itself as a neighbor
+        self.remove_node(id, false);
+
         let level = self.generate_random_layer();
+        self.link_node(id, vector, metadata, level, parallel_expand)
+    }
 
+    /// Link a validated vector into the graph with its top layer fixed
+    ///
+    /// When `level` exceeds the current `max_layer`, the node is linked at
+    /// every layer it shares with existing nodes and becomes the entry point;
+    /// the layers above have no other nodes yet and stay empty.
+    /// `parallel_expand` overrides `parallel_expand_threshold` for the
+    /// construction searches.
+    fn link_node(
+        &mut self,
+        id: u64,
+        vector: Vec<f32>,
+        metadata: Option<String>,
+        level: i32,
+        parallel_expand: Option<usize>,
+    ) -> Result<()> {
         if self.entry_point.is_none() {
             let node = HnswNode {
                 id,
                 vector,
                 neighbors: vec![vec![]; (level + 1) as usize],
+                metadata,
             };
-            self.nodes.insert(id, node);
+            self.put_node(node);
             self.entry_point = Some(id);
             self.max_layer = level;
             return Ok(());
         }
 
-        let mut curr_ep = self.entry_point.unwrap();
-        let mut curr_dist = self.get_distance(&vector, curr_ep);
-
         // 1. Zoom in from top layers
-        for l in (level + 1..=self.max_layer).rev() {
-            let mut changed = true;
-            while changed {
-                changed = false;
-                let node = self.nodes.get(&curr_ep).unwrap();
-                if (l as usize) < node.neighbors.len() {
-                    for &neighbor_id in &node.neighbors[l as usize] {
-                        let d = self.get_distance(&vector, neighbor_id);
-                        if d < curr_dist {
-                            curr_dist = d;
-                            curr_ep = neighbor_id;
-                            changed = true;
-                        }
-                    }
-                }
-            }
-        }
+        let mut curr_ep = self.descend_to(&vector, level);
 
         // 2. Insert into layers from level down to 0
         let mut new_node = HnswNode {
             id,
             vector: vector.clone(),
             neighbors: vec![vec![]; (level + 1) as usize],
+            metadata,
         };
 
         for l in (0..=std::cmp::min(level, self.max_layer)).rev() {
-            let candidates =
-                self.search_layer(&vector, curr_ep, self.config.ef_construction, l as usize);
+            let ef = self.config.ef_construction_for(l as usize);
+            let candidates = self.search_layer_counted(
+                &vector,
+                curr_ep,
+                ef,
+                l as usize,
+                0.0,
+                parallel_expand,
+                false,
+                &mut SearchStats::default(),
+            );
             let m = if l == 0 {
                 self.config.m_max_0
             } else {
                 self.config.m
             };
 
-            let neighbor_ids: Vec<u64> = candidates.into_iter().take(m).map(|c| c.id).collect();
+            // Max-heap sorted ascending puts the closest candidates first
+            let candidates: Vec<(u64, f32)> = candidates
+                .into_sorted_vec()
+                .into_iter()
+                .map(|c| (c.id, c.distance))
+                .collect();
+            let neighbor_ids =
+                self.select_neighbors((id, &vector), candidates, m, l as usize, (id, &vector));
 
             new_node.neighbors[l as usize] = neighbor_ids.clone();
-
-            // Bidirectional links and pruning
-            let mut neighbor_updates = Vec::new();
-            for &neighbor_id in &neighbor_ids {
-                let mut neighbor_neighbors = {
-                    let neighbor_node = self.nodes.get(&neighbor_id).unwrap();
-                    if (l as usize) < neighbor_node.neighbors.len() {
-                        neighbor_node.neighbors[l as usize].clone()
-                    } else {
-                        continue;
-                    }
-                };
-
-                neighbor_neighbors.push(id);
-
-                if neighbor_neighbors.len() > m {
-                    let neighbor_node = self.nodes.get(&neighbor_id).unwrap();
-                    let neighbor_vec = neighbor_node.vector.clone();
-                    let mut connections: Vec<_> = neighbor_neighbors
-                        .into_iter()
-                        .map(|cid| {
-                            (
-                                cid,
-                                1.0 - cosine_similarity(
-                                    &neighbor_vec,
-                                    &self.nodes.get(&cid).unwrap().vector,
-                                ),
-                            )
-                        })
-                        .collect();
-                    connections.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
-                    neighbor_neighbors = connections.into_iter().take(m).map(|c| c.0).collect();
-                }
-                neighbor_updates.push((neighbor_id, neighbor_neighbors));
-            }
-
-            for (nid, nbs) in neighbor_updates {
-                let neighbor_node = self.nodes.get_mut(&nid).unwrap();
-                neighbor_node.neighbors[l as usize] = nbs;
-            }
+            self.link_back(id, &vector, &neighbor_ids, l as usize, m);
 
             if let Some(closest) = neighbor_ids.first() {
                 curr_ep = *closest;
             }
         }
 
-        self.nodes.insert(id, new_node);
+        self.put_node(new_node);
 
         if level > self.max_layer {
             self.max_layer = level;
@@ -305,103 +1080,3228 @@ impl HnswIndex {
         Ok(())
     }
 
-    /// Search for the top K most similar vectors
-    pub fn search(&self, query: &[f32], k: usize, ef: usize) -> Result<Vec<SearchResult>> {
-        if query.len() != self.config.dimension {
-            return Err(Error::DimensionMismatch {
-                expected: self.config.dimension,
-                actual: query.len(),
-            });
+    /// Add the reverse edges to `id` from its new neighbors at `layer`
+    ///
+    /// A neighbor whose list grows beyond `m` is pruned with neighbor
+    /// selection. `id` need not be stored yet; `vector` is its vector.
+    fn link_back(&mut self, id: u64, vector: &[f32], neighbor_ids: &[u64], layer: usize, m: usize) {
+        let mut neighbor_updates = Vec::new();
+        for &neighbor_id in neighbor_ids {
+            let neighbor_node = self.nodes.get(&neighbor_id).unwrap();
+            let Some(existing) = neighbor_node.neighbors.get(layer) else {
+                continue;
+            };
+            if existing.contains(&id) {
+                continue;
+            }
+            let mut neighbor_neighbors = existing.clone();
+            neighbor_neighbors.push(id);
+
+            if neighbor_neighbors.len() > m {
+                let neighbor_vec = &neighbor_node.vector;
+                let mut connections: Vec<_> = neighbor_neighbors
+                    .into_iter()
+                    .map(|cid| {
+                        // The new node is not in `nodes` until the end of insert
+                        let cvec = if cid == id {
+                            vector
+                        } else {
+                            &self.nodes.get(&cid).unwrap().vector
+                        };
+                        (cid, self.distance(neighbor_vec, cvec))
+                    })
+                    .collect();
+                connections.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+                neighbor_neighbors = self.select_neighbors(
+                    (neighbor_id, neighbor_vec),
+                    connections,
+                    m,
+                    layer,
+                    (id, vector),
+                );
+            }
+            neighbor_updates.push((neighbor_id, neighbor_neighbors));
         }
 
-        if self.entry_point.is_none() {
-            return Ok(vec![]);
+        for (nid, nbs) in neighbor_updates {
+            self.set_neighbors(nid, layer, nbs);
         }
+    }
 
-        let mut curr_ep = self.entry_point.unwrap();
-        let mut curr_dist = self.get_distance(query, curr_ep);
+    /// Choose up to `m` neighbors for `base` from candidates sorted closest first
+    ///
+    /// Candidates are `(id, distance to base)`. `pending` supplies the vector
+    /// of the node being inserted, which is not stored yet.
+    fn select_neighbors(
+        &self,
+        base: (u64, &[f32]),
+        candidates: Vec<(u64, f32)>,
+        m: usize,
+        layer: usize,
+        pending: (u64, &[f32]),
+    ) -> Vec<u64> {
+        if self.config.select_heuristic {
+            self.select_neighbors_heuristic(base, candidates, m, layer, pending)
+        } else {
+            candidates.into_iter().take(m).map(|(id, _)| id).collect()
+        }
+    }
 
-        for l in (1..=self.max_layer).rev() {
-            let mut changed = true;
-            while changed {
-                changed = false;
-                let node = self.nodes.get(&curr_ep).unwrap();
-                if (l as usize) < node.neighbors.len() {
-                    for &neighbor_id in &node.neighbors[l as usize] {
-                        let d = self.get_distance(query, neighbor_id);
-                        if d < curr_dist {
-                            curr_dist = d;
-                            curr_ep = neighbor_id;
-                            changed = true;
-                        }
+    /// SELECT-NEIGHBORS-HEURISTIC (Algorithm 4 of the HNSW paper)
+    ///
+    /// Walking the candidates closest first, keeps one only if it is closer
+    /// to `base` than to every neighbor kept so far. This skips candidates
+    /// already covered by a kept neighbor in the same direction, so links
+    /// reach across clusters instead of piling into the nearest one.
+    fn select_neighbors_heuristic(
+        &self,
+        base: (u64, &[f32]),
+        mut candidates: Vec<(u64, f32)>,
+        m: usize,
+        layer: usize,
+        pending: (u64, &[f32]),
+    ) -> Vec<u64> {
+        let vector_of = |id: u64| -> &[f32] {
+            if id == pending.0 {
+                pending.1
+            } else {
+                &self.nodes[&id].vector
+            }
+        };
+
+        if self.config.extend_candidates {
+            let mut seen: HashSet<u64> = candidates.iter().map(|c| c.0).collect();
+            seen.insert(base.0);
+            let mut extra = Vec::new();
+            for &(cid, _) in &candidates {
+                let Some(node) = self.nodes.get(&cid) else {
+                    continue;
+                };
+                for &n in node.neighbors.get(layer).into_iter().flatten() {
+                    if seen.insert(n) {
+                        extra.push((n, self.distance(base.1, vector_of(n))));
                     }
                 }
             }
+            candidates.extend(extra);
+            candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
         }
 
-        let candidates = self.search_layer(query, curr_ep, std::cmp::max(ef, k), 0);
-        let mut results: Vec<_> = candidates
-            .into_iter()
-            .map(|c| SearchResult::new(c.id, 1.0 - c.distance))
+        let mut selected: Vec<(u64, &[f32])> = Vec::with_capacity(m);
+        for (cid, dist) in candidates {
+            if selected.len() >= m {
+                break;
+            }
+            let v = vector_of(cid);
+            if selected.iter().all(|&(_, s)| self.distance(v, s) > dist) {
+                selected.push((cid, v));
+            }
+        }
+        selected.into_iter().map(|(cid, _)| cid).collect()
+    }
+
+    /// Search for the top K most similar vectors
+    ///
+    /// `k` and `ef` are clamped to the number of stored nodes, so a `k` larger
+    /// than the index returns every live node.
+    ///
+    /// Search only reads the graph (metrics and the query log are
+    /// thread-safe), so any number of threads may search one index at once,
+    /// e.g. under the read side of [`ConcurrentHnsw`].
+    pub fn search(&self, query: &[f32], k: usize, ef: usize) -> Result<Vec<SearchResult>> {
+        self.search_with_stats(query, k, ef)
+            .map(|(results, _)| results)
+    }
+
+    /// Like [`HnswIndex::search`], also reporting how much work it did
+    ///
+    /// All counts are zero for an empty index.
+    pub fn search_with_stats(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: usize,
+    ) -> Result<(Vec<SearchResult>, SearchStats)> {
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::debug_span!("hnsw_search", k, ef, visited = tracing::field::Empty).entered();
+
+        let start = Instant::now();
+        let mut stats = SearchStats::default();
+        let candidates = self.search_candidates_counted(query, std::cmp::max(ef, k), &mut stats)?;
+        let results = candidates
+            .into_iter()
+            .take(k)
+            .map(|c| SearchResult::new(c.id, self.score(c.distance)))
+            .collect();
+
+        metrics::global().search.record(start.elapsed());
+        if let Some(log) = &self.query_log {
+            log.record(QueryRecord {
+                query: query.to_vec(),
+                k,
+                ef,
+                timestamp_ms: now_ms(),
+                latency: start.elapsed(),
+            });
+        }
+        Ok((results, stats))
+    }
+
+    /// Search using the index's configured `default_k` and `default_ef`
+    pub fn search_default(&self, query: &[f32]) -> Result<Vec<SearchResult>> {
+        self.search(query, self.config.default_k, self.config.default_ef)
+    }
+
+    /// Search for the top K most similar vectors along with a confidence margin
+    ///
+    /// The margin is the score gap between rank 0 and rank k-1 (or the last
+    /// result if fewer were found); a larger margin means a more confident top
+    /// match. It is 0.0 when fewer than two results are returned.
+    pub fn search_with_margin(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: usize,
+    ) -> Result<(Vec<SearchResult>, f32)> {
+        let results = self.search(query, k, ef)?;
+        let margin = match (results.first(), results.last()) {
+            (Some(top), Some(last)) => (top.score - last.score).abs(),
+            _ => 0.0,
+        };
+        Ok((results, margin))
+    }
+
+    /// Search for the top K most similar vectors among `allowed` IDs
+    ///
+    /// Filters the `ef` nearest candidates, so a selective filter may return
+    /// fewer than `k` results; raise `ef` to compensate. Pair with
+    /// [`crate::storage::MetadataIndex::ids_for`] for attribute filters.
+    pub fn search_filtered(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: usize,
+        allowed: &[u64],
+    ) -> Result<Vec<SearchResult>> {
+        let allowed: HashSet<u64> = allowed.iter().copied().collect();
+        self.search_matching(query, k, ef, |id| allowed.contains(&id))
+    }
+
+    /// Search for the top K most similar vectors, skipping `excluded` IDs
+    pub fn search_excluding(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: usize,
+        excluded: &[u64],
+    ) -> Result<Vec<SearchResult>> {
+        let excluded: HashSet<u64> = excluded.iter().copied().collect();
+        self.search_matching(query, k, ef, |id| !excluded.contains(&id))
+    }
+
+    /// Search with several queries and combine the result sets
+    ///
+    /// Each query contributes its `ef` nearest neighbors, so raise `ef` to
+    /// find more overlap. `And`
+    /// keeps IDs present in every set and scores them by the worst per-query
+    /// score (minimum similarity); `Or` keeps all IDs, scored by the best.
+    /// Returns the top K, best first.
+    pub fn search_boolean(
+        &self,
+        queries: &[Vec<f32>],
+        op: BoolOp,
+        k: usize,
+        ef: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let higher_is_better = self.config.metric.higher_is_better();
+        let better = |a: f32, b: f32| if higher_is_better { a.max(b) } else { a.min(b) };
+        let worse = |a: f32, b: f32| if higher_is_better { a.min(b) } else { a.max(b) };
+
+        let mut combined: HashMap<u64, (f32, usize)> = HashMap::new();
+        for query in queries {
+            for r in self.search(query, ef, ef)? {
+                combined
+                    .entry(r.id)
+                    .and_modify(|(score, hits)| {
+                        *score = match op {
+                            BoolOp::And => worse(*score, r.score),
+                            BoolOp::Or => better(*score, r.score),
+                        };
+                        *hits += 1;
+                    })
+                    .or_insert((r.score, 1));
+            }
+        }
+
+        let mut results: Vec<SearchResult> = combined
+            .into_iter()
+            .filter(|(_, (_, hits))| op == BoolOp::Or || *hits == queries.len())
+            .map(|(id, (score, _))| SearchResult::new(id, score))
+            .collect();
+        results.sort_by(|a, b| {
+            let order = if higher_is_better {
+                b.score.total_cmp(&a.score)
+            } else {
+                a.score.total_cmp(&b.score)
+            };
+            order.then(a.id.cmp(&b.id))
+        });
+        results.truncate(k);
+        Ok(results)
+    }
+
+    fn search_matching(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: usize,
+        keep: impl Fn(u64) -> bool,
+    ) -> Result<Vec<SearchResult>> {
+        Ok(self
+            .search_candidates(query, std::cmp::max(ef, k))?
+            .into_iter()
+            .filter(|c| keep(c.id))
+            .take(k)
+            .map(|c| SearchResult::new(c.id, self.score(c.distance)))
+            .collect())
+    }
+
+    /// Search for the top K most similar vectors, returning stored metadata inline
+    pub fn search_with_metadata(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: usize,
+    ) -> Result<Vec<(SearchResult, Option<String>)>> {
+        Ok(self
+            .search(query, k, ef)?
+            .into_iter()
+            .map(|r| {
+                let metadata = self.nodes.get(&r.id).and_then(|n| n.metadata.clone());
+                (r, metadata)
+            })
+            .collect())
+    }
+
+    /// Stream the `ef` nearest results to `sink`, closest first
+    ///
+    /// Avoids building a result `Vec` for very large result sets, e.g. when
+    /// exporting every neighbor of a query. The search's candidate heap is
+    /// turned into a min-heap and each result is popped just before `sink`
+    /// sees it, so nothing beyond the heap itself is allocated.
+    pub fn search_streaming(
+        &self,
+        query: &[f32],
+        ef: usize,
+        mut sink: impl FnMut(SearchResult),
+    ) -> Result<()> {
+        for result in self.stream_results(query, ef)? {
+            sink(result);
+        }
+        Ok(())
+    }
+
+    /// The `ef` nearest results, popped closest first on demand
+    fn stream_results(&self, query: &[f32], ef: usize) -> Result<StreamedResults<'_>> {
+        let heap = self
+            .search_heap_counted(query, ef, &mut SearchStats::default())?
+            .into_iter()
+            .map(|c| MinCandidate {
+                id: c.id,
+                distance: c.distance,
+            })
+            .collect();
+        Ok(StreamedResults { index: self, heap })
+    }
+
+    /// Write every result scoring at least `threshold` to a Parquet file
+    ///
+    /// For the L2 and L1 metrics `threshold` is a maximum distance instead.
+    /// Only the `ef` nearest candidates are considered, so raise `ef` to at
+    /// least the expected result count; matches beyond it are not exported.
+    /// Results are popped closest first from the search heap and streamed to
+    /// storage in batches (columns `id`, `score`), so no result `Vec` or
+    /// encoded file is held in memory. Returns the number of rows written.
+    pub async fn search_threshold_to_parquet(
+        &self,
+        query: &[f32],
+        threshold: f32,
+        ef: usize,
+        client: &StorageClient,
+        out_path: &str,
+    ) -> Result<usize> {
+        const CHUNK_ROWS: usize = 8192;
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::UInt64, false),
+            Field::new("score", DataType::Float32, false),
+        ]));
+        let batch = |ids: &mut Vec<u64>, scores: &mut Vec<f32>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(UInt64Array::from(std::mem::take(ids))) as ArrayRef,
+                    Arc::new(Float32Array::from(std::mem::take(scores))) as ArrayRef,
+                ],
+            )
+            .map_err(Error::Arrow)
+        };
+
+        let results = self.stream_results(query, ef)?;
+        let mut writer = ParquetWriter::new(client, 0)
+            .open_with_schema(out_path, schema.clone())
+            .await?;
+        let mut ids = Vec::with_capacity(CHUNK_ROWS);
+        let mut scores = Vec::with_capacity(CHUNK_ROWS);
+        // Closest first, so the first result past the threshold ends the export
+        for r in results.take_while(|r| {
+            if self.config.metric.higher_is_better() {
+                r.score >= threshold
+            } else {
+                r.score <= threshold
+            }
+        }) {
+            ids.push(r.id);
+            scores.push(r.score);
+            if ids.len() == CHUNK_ROWS {
+                writer.write_batch(&batch(&mut ids, &mut scores)?).await?;
+            }
+        }
+        if !ids.is_empty() {
+            writer.write_batch(&batch(&mut ids, &mut scores)?).await?;
+        }
+        writer.close().await
+    }
+
+    /// Find every vector within `radius` distance of the query
+    ///
+    /// The radius is in the metric's distance: `1 - similarity` for cosine,
+    /// the negated dot product for dot. For cosine this returns results with
+    /// similarity of at least `1 - radius`, closest first, capped at
+    /// `max_results`. A regular `ef` search seeds the result set, then
+    /// the frontier keeps expanding through in-radius nodes until no unvisited
+    /// neighbor falls inside the radius, so the result count is data-dependent.
+    pub fn search_radius(
+        &self,
+        query: &[f32],
+        radius: f32,
+        max_results: usize,
+        ef: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let Some(query) = self.prepare_query(query)? else {
+            return Ok(vec![]);
+        };
+        let query = &query[..];
+
+        let seeds = self.search_layer(
+            query,
+            self.descend(query),
+            ef.max(1),
+            0,
+            self.config.exploration,
+        );
+        let mut visited: HashSet<u64> = seeds.iter().map(|c| c.id).collect();
+        let mut frontier: BinaryHeap<MinCandidate> = BinaryHeap::new();
+        let mut found = Vec::new();
+        for c in seeds {
+            if c.distance <= radius {
+                frontier.push(MinCandidate {
+                    id: c.id,
+                    distance: c.distance,
+                });
+                found.push(c);
+            }
+        }
+
+        while let Some(current) = frontier.pop() {
+            let node = &self.nodes[&current.id];
+            for &neighbor_id in node.neighbors.first().into_iter().flatten() {
+                if !visited.insert(neighbor_id) {
+                    continue;
+                }
+                let distance = self.get_distance(query, neighbor_id);
+                if distance <= radius {
+                    frontier.push(MinCandidate {
+                        id: neighbor_id,
+                        distance,
+                    });
+                    found.push(MaxCandidate {
+                        id: neighbor_id,
+                        distance,
+                    });
+                }
+            }
+        }
+
+        found.retain(|c| !self.tombstones.contains(&c.id));
+        found.sort();
+        Ok(found
+            .into_iter()
+            .take(max_results)
+            .map(|c| SearchResult::new(c.id, self.score(c.distance)))
+            .collect())
+    }
+
+    /// Find every vector within `max_distance` of the query, uncapped
+    ///
+    /// [`HnswIndex::search_radius`] without a result limit: traversal keeps
+    /// expanding while neighbors fall inside the radius, so a small `ef` does
+    /// not cut the result set short. Results are sorted closest first.
+    pub fn search_range(
+        &self,
+        query: &[f32],
+        max_distance: f32,
+        ef: usize,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_radius(query, max_distance, usize::MAX, ef)
+    }
+
+    /// Descend through the layers and return layer-0 candidates, closest first
+    fn search_candidates(&self, query: &[f32], ef: usize) -> Result<Vec<MaxCandidate>> {
+        self.search_candidates_counted(query, ef, &mut SearchStats::default())
+    }
+
+    /// [`Self::search_candidates`], adding its work to `stats`
+    fn search_candidates_counted(
+        &self,
+        query: &[f32],
+        ef: usize,
+        stats: &mut SearchStats,
+    ) -> Result<Vec<MaxCandidate>> {
+        // Max-heap sorted ascending yields the closest candidates first
+        self.search_heap_counted(query, ef, stats)
+            .map(BinaryHeap::into_sorted_vec)
+    }
+
+    /// Descend through the layers and return the layer-0 candidate heap
+    fn search_heap_counted(
+        &self,
+        query: &[f32],
+        ef: usize,
+        stats: &mut SearchStats,
+    ) -> Result<BinaryHeap<MaxCandidate>> {
+        let Some(query) = self.prepare_query(query)? else {
+            return Ok(BinaryHeap::new());
+        };
+        let query = &query[..];
+
+        // A wider search than the whole graph only grows the heaps
+        let ef = ef.min(self.nodes.len());
+
+        let ep = self.descend_to_counted(query, 0, stats);
+        Ok(self.search_layer_counted(
+            query,
+            ep,
+            ef,
+            0,
+            self.config.exploration,
+            self.config.parallel_expand_threshold,
+            true,
+            stats,
+        ))
+    }
+
+    /// Validate a query and apply auto-normalization
+    ///
+    /// Returns `None` when the index is empty.
+    fn prepare_query<'q>(&self, query: &'q [f32]) -> Result<Option<Cow<'q, [f32]>>> {
+        // Dimension not inferred yet, so nothing has been inserted
+        if self.config.dimension == 0 {
+            return Ok(None);
+        }
+
+        let input_dimension = match &self.config.query_transform {
+            Some(transform) => transform.cols(),
+            None => self.config.dimension,
+        };
+        if query.len() != input_dimension {
+            return Err(Error::DimensionMismatch {
+                expected: input_dimension,
+                actual: query.len(),
+            });
+        }
+
+        if self.entry_point.is_none() {
+            return Ok(None);
+        }
+
+        let mut query = Cow::Borrowed(query);
+        if let Some(transform) = &self.config.query_transform {
+            if transform.rows() != self.config.dimension {
+                return Err(Error::DimensionMismatch {
+                    expected: self.config.dimension,
+                    actual: transform.rows(),
+                });
+            }
+            query = Cow::Owned(transform.apply(&query));
+        }
+
+        if self.config.auto_normalize {
+            normalize(query.to_mut());
+        }
+        Ok(Some(query))
+    }
+
+    /// Greedily descend the upper layers and return the layer-0 entry point
+    fn descend(&self, query: &[f32]) -> u64 {
+        self.descend_to(query, 0)
+    }
+
+    /// Greedily descend the layers above `layer` and return the entry point there
+    fn descend_to(&self, query: &[f32], layer: i32) -> u64 {
+        self.descend_to_counted(query, layer, &mut SearchStats::default())
+    }
+
+    /// [`Self::descend_to`], adding its work to `stats`
+    fn descend_to_counted(&self, query: &[f32], layer: i32, stats: &mut SearchStats) -> u64 {
+        let mut curr_ep = self.entry_point.expect("Index must not be empty");
+        let mut curr_dist = self.get_distance(query, curr_ep);
+        stats.distance_computations += 1;
+
+        for l in (layer + 1..=self.max_layer).rev() {
+            let mut changed = true;
+            while changed {
+                changed = false;
+                let node = self.nodes.get(&curr_ep).unwrap();
+                if (l as usize) < node.neighbors.len() {
+                    stats.nodes_visited += 1;
+                    for &neighbor_id in &node.neighbors[l as usize] {
+                        let d = self.get_distance(query, neighbor_id);
+                        stats.distance_computations += 1;
+                        if d < curr_dist {
+                            curr_dist = d;
+                            curr_ep = neighbor_id;
+                            changed = true;
+                            stats.hops += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        curr_ep
+    }
+
+    /// Store a node, keeping the graph statistics in step
+    fn put_node(&mut self, node: HnswNode) {
+        self.stats.add_node(&node);
+        if let Some(incoming) = &mut self.incoming {
+            for (layer, neighbors) in node.neighbors.iter().enumerate() {
+                for &n in neighbors {
+                    incoming.entry(n).or_default().insert((node.id, layer));
+                }
+            }
+        }
+        if let Some(old) = self.nodes.insert(node.id, node) {
+            self.stats.remove_node(&old);
+        }
+    }
+
+    /// Reverse edge index, built from the graph if not built yet
+    fn incoming_edges(&mut self) -> &mut HashMap<u64, HashSet<(u64, usize)>> {
+        let nodes = &self.nodes;
+        self.incoming.get_or_insert_with(|| {
+            let mut incoming: HashMap<u64, HashSet<(u64, usize)>> = HashMap::new();
+            for node in nodes.values() {
+                for (layer, neighbors) in node.neighbors.iter().enumerate() {
+                    for &n in neighbors {
+                        incoming.entry(n).or_default().insert((node.id, layer));
+                    }
+                }
+            }
+            incoming
+        })
+    }
+
+    /// Graph statistics maintained incrementally, without a traversal
+    pub fn live_stats(&self) -> GraphStats {
+        GraphStats {
+            tombstones: self.tombstones.len(),
+            ..self.stats.clone()
+        }
+    }
+
+    /// Number of searchable vectors, excluding soft-deleted ones
+    pub fn len(&self) -> usize {
+        self.nodes.len() - self.tombstones.len()
+    }
+
+    /// Check if the index has no searchable vectors
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Summarize the graph's size for monitoring and capacity planning
+    ///
+    /// Walks the nodes once to count layer-0 edges and metadata bytes. The
+    /// memory estimate covers vector data, neighbor lists and per-node map
+    /// overhead, not allocator slack.
+    pub fn stats(&self) -> HnswStats {
+        let mut layer0_edges = 0;
+        let mut metadata_bytes = 0;
+        for node in self.nodes.values() {
+            layer0_edges += node.neighbors.first().map_or(0, Vec::len);
+            metadata_bytes += node.metadata.as_ref().map_or(0, String::len);
+        }
+        let layers = self.stats.nodes_per_layer.iter().sum::<usize>();
+        let per_node = std::mem::size_of::<(u64, HnswNode)>()
+            + self.config.dimension * std::mem::size_of::<f32>();
+        let memory_estimate_bytes = self.nodes.len() * per_node
+            + layers * std::mem::size_of::<Vec<u64>>()
+            + self.stats.total_edges * std::mem::size_of::<u64>()
+            + metadata_bytes;
+
+        HnswStats {
+            num_nodes: self.nodes.len(),
+            max_layer: self.max_layer.max(0) as usize,
+            entry_point: self.entry_point,
+            avg_degree_layer0: if self.nodes.is_empty() {
+                0.0
+            } else {
+                layer0_edges as f32 / self.nodes.len() as f32
+            },
+            memory_estimate_bytes,
+        }
+    }
+
+    /// Graph statistics computed by a full traversal
+    ///
+    /// Matches [`HnswIndex::live_stats`]; useful to audit the running counters.
+    pub fn diagnostics(&self) -> GraphStats {
+        let mut stats = GraphStats::default();
+        for node in self.nodes.values() {
+            stats.add_node(node);
+        }
+        stats.tombstones = self.tombstones.len();
+        stats
+    }
+
+    /// Remove a vector and every edge pointing to it
+    ///
+    /// Nodes that linked to it lose that edge; they are found through a
+    /// reverse edge index built on the first removal, so later removals only
+    /// touch the affected nodes. Any node left with fewer than
+    /// half its allowed neighbors at a layer is reconnected to the closest
+    /// nodes found by a search from its position; see also
+    /// [`HnswIndex::delete_with_healing`]. If the entry point is deleted, a
+    /// node from the highest remaining layer replaces it.
+    /// Returns `Ok(false)` if the ID does not exist.
+    pub fn delete(&mut self, id: u64) -> Result<bool> {
+        Ok(self.remove_node(id, false))
+    }
+
+    /// Remove a vector, re-linking the nodes that pointed to it
+    ///
+    /// Each such node considers the deleted node's neighbors as replacement
+    /// candidates and keeps its closest `m` (`m_max_0` at layer 0), so local
+    /// connectivity and recall survive delete-heavy workloads. Costs distance
+    /// computations per affected neighbor.
+    /// Under-connected nodes are then repaired as in [`HnswIndex::delete`].
+    /// Returns `Ok(false)` if the ID does not exist.
+    pub fn delete_with_healing(&mut self, id: u64) -> Result<bool> {
+        Ok(self.remove_node(id, true))
+    }
+
+    fn remove_node(&mut self, id: u64, heal: bool) -> bool {
+        let Some(removed) = self.nodes.remove(&id) else {
+            return false;
+        };
+        self.stats.remove_node(&removed);
+        self.tombstones.remove(&id);
+
+        let incoming = self.incoming_edges();
+        let mut sources: Vec<(u64, usize)> = incoming
+            .remove(&id)
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        for (layer, neighbors) in removed.neighbors.iter().enumerate() {
+            for n in neighbors {
+                if let Some(edges) = incoming.get_mut(n) {
+                    edges.remove(&(id, layer));
+                }
+            }
+        }
+        sources.sort_unstable();
+
+        let mut updates = Vec::new();
+        for (nid, layer) in sources {
+            let node = &self.nodes[&nid];
+            let mut kept: Vec<u64> = node.neighbors[layer]
+                .iter()
+                .copied()
+                .filter(|&n| n != id)
+                .collect();
+            if heal && layer < removed.neighbors.len() {
+                kept = self.heal_neighbors(node, layer, kept, &removed.neighbors[layer]);
+            }
+            updates.push((nid, layer, kept));
+        }
+        let affected: Vec<(u64, usize)> = updates.iter().map(|(n, l, _)| (*n, *l)).collect();
+        for (nid, layer, neighbors) in updates {
+            self.set_neighbors(nid, layer, neighbors);
+        }
+
+        if self.entry_point == Some(id) {
+            // Promote a node from the highest remaining layer: the removed
+            // node's neighbors at its top layer, else whichever node is tallest
+            let height = |n: &&HnswNode| (n.neighbors.len(), n.id);
+            let top = removed
+                .neighbors
+                .last()
+                .into_iter()
+                .flatten()
+                .filter_map(|n| self.nodes.get(n))
+                .max_by_key(height)
+                .or_else(|| self.nodes.values().max_by_key(height));
+            self.entry_point = top.map(|n| n.id);
+            self.max_layer = top.map_or(-1, |n| n.neighbors.len() as i32 - 1);
+        }
+
+        for (nid, layer) in affected {
+            self.reconnect(nid, layer);
+        }
+        true
+    }
+
+    /// Replace a node's neighbor list at `layer`, keeping the edge count
+    fn set_neighbors(&mut self, id: u64, layer: usize, neighbors: Vec<u64>) {
+        let node = self.nodes.get_mut(&id).unwrap();
+        let old = std::mem::replace(&mut node.neighbors[layer], neighbors);
+        self.stats.total_edges += node.neighbors[layer].len();
+        self.stats.total_edges -= old.len();
+        if let Some(incoming) = &mut self.incoming {
+            for n in &old {
+                if let Some(edges) = incoming.get_mut(n) {
+                    edges.remove(&(id, layer));
+                }
+            }
+            for &n in &node.neighbors[layer] {
+                incoming.entry(n).or_default().insert((id, layer));
+            }
+        }
+    }
+
+    /// Top up a node left with fewer than half its allowed neighbors
+    ///
+    /// Searches the layer from the node's own position and links the closest
+    /// new candidates, adding the reverse edge where the candidate has room.
+    fn reconnect(&mut self, id: u64, layer: usize) {
+        let cap = if layer == 0 {
+            self.config.m_max_0
+        } else {
+            self.config.m
+        };
+        let node = &self.nodes[&id];
+        if node.neighbors[layer].len() >= cap.div_ceil(2) {
+            return;
+        }
+        let Some(ep) = node.neighbors[layer].first().copied().or(self.entry_point) else {
+            return;
+        };
+
+        let mut neighbors = node.neighbors[layer].clone();
+        let ef = self.config.ef_construction_for(layer).max(cap);
+        let found = self
+            .search_layer(&node.vector, ep, ef, layer, 0.0)
+            .into_sorted_vec();
+        let mut added = Vec::new();
+        for candidate in found {
+            if neighbors.len() >= cap {
+                break;
+            }
+            if candidate.id != id && !neighbors.contains(&candidate.id) {
+                neighbors.push(candidate.id);
+                added.push(candidate.id);
+            }
+        }
+        self.set_neighbors(id, layer, neighbors);
+
+        for other in added {
+            let back = &self.nodes[&other].neighbors[layer];
+            if back.len() < cap && !back.contains(&id) {
+                let mut back = back.clone();
+                back.push(id);
+                self.set_neighbors(other, layer, back);
+            }
+        }
+    }
+
+    /// Pick the closest replacement neighbors for `node` at `layer`
+    fn heal_neighbors(
+        &self,
+        node: &HnswNode,
+        layer: usize,
+        mut kept: Vec<u64>,
+        orphaned: &[u64],
+    ) -> Vec<u64> {
+        let m = if layer == 0 {
+            self.config.m_max_0
+        } else {
+            self.config.m
+        };
+
+        for &candidate in orphaned {
+            let reachable = self
+                .nodes
+                .get(&candidate)
+                .is_some_and(|c| layer < c.neighbors.len());
+            if candidate != node.id && reachable && !kept.contains(&candidate) {
+                kept.push(candidate);
+            }
+        }
+        if kept.len() <= m {
+            return kept;
+        }
+
+        let mut scored: Vec<(u64, f32)> = kept
+            .into_iter()
+            .map(|n| (n, self.get_distance(&node.vector, n)))
+            .collect();
+        scored.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+        scored.into_iter().take(m).map(|(n, _)| n).collect()
+    }
+
+    /// Replace a stored vector in place
+    ///
+    /// The node keeps its layers and edges when the vector moved by at most
+    /// `repair_threshold` (in index distance), which is cheap and fine for
+    /// small edits. A larger move runs [`HnswIndex::repair_node`].
+    /// Returns whether the node was repaired, or [`Error::NotFound`] for an
+    /// unknown or soft-deleted id.
+    pub fn update_vector(&mut self, id: u64, mut vector: Vec<f32>) -> Result<bool> {
+        if vector.len() != self.config.dimension {
+            return Err(Error::DimensionMismatch {
+                expected: self.config.dimension,
+                actual: vector.len(),
+            });
+        }
+        if self.config.auto_normalize {
+            normalize(&mut vector);
+        }
+
+        let moved = match self.nodes.get(&id) {
+            Some(node) if !self.tombstones.contains(&id) => self.distance(&node.vector, &vector),
+            _ => return Err(Error::NotFound(format!("vector {}", id))),
+        };
+        self.nodes.get_mut(&id).unwrap().vector = vector;
+
+        if moved > self.config.repair_threshold {
+            self.repair_node(id)?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Replace a vector by removing its node and inserting it again
+    ///
+    /// Unlike [`HnswIndex::update_vector`], the node is unlinked with the same
+    /// repair as [`HnswIndex::delete_with_healing`] and then linked from
+    /// scratch, so no edge chosen for the old vector survives. The id, top
+    /// layer and metadata are kept. Returns [`Error::NotFound`] for an unknown
+    /// or soft-deleted id.
+    pub fn update(&mut self, id: u64, mut vector: Vec<f32>) -> Result<()> {
+        if vector.len() != self.config.dimension {
+            return Err(Error::DimensionMismatch {
+                expected: self.config.dimension,
+                actual: vector.len(),
+            });
+        }
+        let (metadata, level) = match self.nodes.get(&id) {
+            Some(node) if !self.tombstones.contains(&id) => {
+                (node.metadata.clone(), node.neighbors.len() as i32 - 1)
+            }
+            _ => return Err(Error::NotFound(format!("vector {}", id))),
+        };
+        if self.config.auto_normalize {
+            normalize(&mut vector);
+        }
+
+        self.remove_node(id, true);
+        let parallel_expand = self.config.parallel_expand_threshold;
+        self.link_node(id, vector, metadata, level, parallel_expand)
+    }
+
+    /// Reselect a node's neighbors at every layer for its current vector
+    ///
+    /// Runs the insert-time search and neighbor selection for this node only
+    /// and adds reverse edges from the new neighbors. Edges other nodes held
+    /// to it are dropped unless they are among its new neighbors, since they
+    /// were chosen for its old position; nodes left under-connected are
+    /// topped up as after a delete.
+    pub fn repair_node(&mut self, id: u64) -> Result<()> {
+        let (vector, top_layer) = match self.nodes.get(&id) {
+            Some(node) => (node.vector.clone(), node.neighbors.len() as i32 - 1),
+            None => return Err(Error::NotFound(format!("vector {}", id))),
+        };
+
+        let mut curr_ep = self.descend_to(&vector, top_layer);
+        for l in (0..=top_layer.min(self.max_layer)).rev() {
+            let layer = l as usize;
+            let m = if layer == 0 {
+                self.config.m_max_0
+            } else {
+                self.config.m
+            };
+
+            // Search for one extra result since the node finds itself
+            let ef = self.config.ef_construction_for(layer) + 1;
+            let candidates: Vec<(u64, f32)> = self
+                .search_layer(&vector, curr_ep, ef, layer, 0.0)
+                .into_sorted_vec()
+                .into_iter()
+                .filter(|c| c.id != id)
+                .map(|c| (c.id, c.distance))
+                .collect();
+            let neighbor_ids =
+                self.select_neighbors((id, &vector), candidates, m, layer, (id, &vector));
+
+            self.set_neighbors(id, layer, neighbor_ids.clone());
+            self.link_back(id, &vector, &neighbor_ids, layer, m);
+            if let Some(&closest) = neighbor_ids.first() {
+                curr_ep = closest;
+            }
+        }
+
+        let mut stale: Vec<(u64, usize)> = self
+            .incoming_edges()
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .copied()
+            .collect();
+        stale.retain(|(src, layer)| !self.nodes[&id].neighbors[*layer].contains(src));
+        stale.sort_unstable();
+        for &(src, layer) in &stale {
+            let mut kept = self.nodes[&src].neighbors[layer].clone();
+            kept.retain(|&n| n != id);
+            self.set_neighbors(src, layer, kept);
+        }
+        for (src, layer) in stale {
+            self.reconnect(src, layer);
+        }
+        Ok(())
+    }
+
+    /// Mark a vector as deleted without touching the graph
+    ///
+    /// The node keeps routing searches but no longer appears in results.
+    /// Returns false if the ID does not exist or is already deleted.
+    pub fn soft_delete(&mut self, id: u64) -> bool {
+        self.nodes.contains_key(&id) && self.tombstones.insert(id)
+    }
+
+    /// Fraction of stored nodes that are soft-deleted
+    pub fn tombstone_ratio(&self) -> f32 {
+        if self.nodes.is_empty() {
+            return 0.0;
+        }
+        self.tombstones.len() as f32 / self.nodes.len() as f32
+    }
+
+    /// Check if the tombstone ratio exceeds `auto_compact_ratio`
+    pub fn needs_compaction(&self) -> bool {
+        self.config
+            .auto_compact_ratio
+            .is_some_and(|ratio| self.tombstone_ratio() > ratio)
+    }
+
+    /// Rebuild the graph from the live nodes, dropping all tombstones
+    pub fn compact(&mut self) -> Result<()> {
+        let mut live: Vec<HnswNode> = std::mem::take(&mut self.nodes)
+            .into_values()
+            .filter(|n| !self.tombstones.contains(&n.id))
+            .collect();
+        live.sort_by_key(|n| n.id);
+
+        let mut rebuilt = HnswIndex::new(self.config.clone());
+        rebuilt.query_log = self.query_log.take();
+        for node in live {
+            rebuilt.insert_with_metadata(node.id, node.vector, node.metadata)?;
+        }
+
+        *self = rebuilt;
+        Ok(())
+    }
+
+    fn generate_random_layer(&mut self) -> i32 {
+        let r: f64 = match self.config.seed {
+            Some(seed) => self
+                .rng
+                .get_or_insert_with(|| StdRng::seed_from_u64(seed))
+                .gen(),
+            None => thread_rng().gen(),
+        };
+        (-(r.ln() * self.config.ml).floor()) as i32
+    }
+
+    /// Serialize the index to bytes
+    ///
+    /// The bincode body is not self-describing, so it is preceded by a magic
+    /// header and a format version that [`HnswIndex::deserialize`] checks.
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        let mut out = INDEX_MAGIC.to_vec();
+        out.extend_from_slice(&INDEX_FORMAT_VERSION.to_le_bytes());
+        bincode::serialize_into(&mut out, self).map_err(|e| Error::Bincode(e.to_string()))?;
+        Ok(out)
+    }
+
+    /// Deserialize the index from bytes
+    ///
+    /// A loaded index is fully live: `insert` links new vectors into the
+    /// existing graph without a rebuild, and a later `serialize` captures them.
+    /// Inserting requires `&mut self`, so an index shared for serving must be
+    /// thawed from its [`ReadOnlyHnsw`] handle or guarded by a lock first.
+    ///
+    /// Bytes without the format header are read as the original headerless
+    /// layout, which keeps only the dimension, `m`, `m_max_0`,
+    /// `ef_construction` and `ml` of the config; other settings take their
+    /// defaults. An unknown format version fails with [`Error::Index`].
+    pub fn deserialize(bytes: &[u8]) -> Result<Self> {
+        let Some(rest) = bytes.strip_prefix(INDEX_MAGIC.as_slice()) else {
+            let legacy: LegacyIndex =
+                bincode::deserialize(bytes).map_err(|e| Error::Bincode(e.to_string()))?;
+            return Ok(legacy.into());
+        };
+        let (version, body) = rest.split_at(rest.len().min(4));
+        let version = version
+            .try_into()
+            .map(u32::from_le_bytes)
+            .map_err(|_| Error::Index("missing index format version".to_string()))?;
+        if version != INDEX_FORMAT_VERSION {
+            return Err(Error::Index(format!(
+                "unsupported index format version {}",
+                version
+            )));
+        }
+        bincode::deserialize(body).map_err(|e| Error::Bincode(e.to_string()))
+    }
+
+    /// Serialize the index and compress the bytes with `codec`
+    ///
+    /// The output starts with a magic header naming the codec, so
+    /// [`HnswIndex::deserialize_compressed`] needs no hint. Vectors dominate
+    /// the size, so the gain depends on how redundant they are.
+    pub fn serialize_compressed(&self, codec: Compression) -> Result<Vec<u8>> {
+        use std::io::Write;
+
+        let raw = self.serialize()?;
+        let mut out = COMPRESSED_MAGIC.to_vec();
+        match codec {
+            Compression::Gzip => {
+                out.push(CODEC_GZIP);
+                let mut gz = flate2::write::GzEncoder::new(out, flate2::Compression::default());
+                gz.write_all(&raw)
+                    .and_then(|_| gz.finish())
+                    .map_err(|e| Error::Index(format!("gzip compression failed: {}", e)))
+            }
+            Compression::Zstd { level } => {
+                out.push(CODEC_ZSTD);
+                zstd::stream::copy_encode(raw.as_slice(), &mut out, level)
+                    .map(|_| out)
+                    .map_err(|e| Error::Index(format!("zstd compression failed: {}", e)))
+            }
+        }
+    }
+
+    /// Deserialize bytes from [`HnswIndex::serialize_compressed`]
+    ///
+    /// The codec is read from the header. Bytes without the header are taken
+    /// as plain [`HnswIndex::serialize`] output.
+    pub fn deserialize_compressed(bytes: &[u8]) -> Result<Self> {
+        use std::io::Read;
+
+        let Some(rest) = bytes.strip_prefix(COMPRESSED_MAGIC.as_slice()) else {
+            return Self::deserialize(bytes);
+        };
+        let decoded = match rest.split_first() {
+            Some((&CODEC_GZIP, data)) => {
+                let mut out = Vec::new();
+                flate2::read::GzDecoder::new(data)
+                    .read_to_end(&mut out)
+                    .map(|_| out)
+            }
+            Some((&CODEC_ZSTD, data)) => zstd::decode_all(data),
+            Some((codec, _)) => {
+                return Err(Error::Decompression(format!(
+                    "unknown index codec {}",
+                    codec
+                )))
+            }
+            None => return Err(Error::Decompression("missing index codec".to_string())),
+        };
+        let raw = decoded.map_err(|e| Error::Decompression(e.to_string()))?;
+        Self::deserialize(&raw)
+    }
+
+    /// Write the serialized index to `path` as a single object
+    ///
+    /// The object is written with one `write`, so readers see either the
+    /// previous index or the new one, never a partial file.
+    pub async fn save(&self, client: &StorageClient, path: &str) -> Result<()> {
+        client.write(path, self.serialize()?).await
+    }
+
+    /// Read an index written by [`HnswIndex::save`]
+    ///
+    /// Fails with [`Error::Index`] if the stored config has no dimension.
+    pub async fn load(client: &StorageClient, path: &str) -> Result<Self> {
+        Self::deserialize_saved(&client.read(path).await?, path)
+    }
+
+    /// Deserialize a saved index read from `path`, rejecting one without a
+    /// dimension
+    pub(crate) fn deserialize_saved(bytes: &[u8], path: &str) -> Result<Self> {
+        let index = Self::deserialize(bytes)?;
+        if index.config.dimension == 0 {
+            return Err(Error::Index(format!("index at {} has dimension 0", path)));
+        }
+        Ok(index)
+    }
+
+    /// Serialize the graph structure without the vectors
+    ///
+    /// For deployments that already keep vectors in Parquet. Reload with
+    /// [`HnswIndex::load_graph_with_vectors`].
+    pub fn serialize_graph_only(&self) -> Result<Vec<u8>> {
+        let mut nodes: Vec<GraphNode> = self
+            .nodes
+            .values()
+            .map(|n| GraphNode {
+                id: n.id,
+                neighbors: n.neighbors.clone(),
+                metadata: n.metadata.clone(),
+            })
+            .collect();
+        nodes.sort_by_key(|n| n.id);
+
+        let snapshot = GraphSnapshot {
+            config: self.config.clone(),
+            nodes,
+            entry_point: self.entry_point,
+            max_layer: self.max_layer,
+            tombstones: self.tombstones.clone(),
+        };
+        bincode::serialize(&snapshot).map_err(|e| Error::Bincode(e.to_string()))
+    }
+
+    /// Rebuild an index from a graph-only snapshot and a source of vectors
+    ///
+    /// `vectors` must contain every ID in the graph (extra IDs are ignored),
+    /// e.g. the output of [`crate::storage::extract_vectors`] for the partition
+    /// the graph was built from.
+    pub fn load_graph_with_vectors<I>(graph_bytes: &[u8], vectors: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (u64, Vec<f32>)>,
+    {
+        let snapshot: GraphSnapshot =
+            bincode::deserialize(graph_bytes).map_err(|e| Error::Bincode(e.to_string()))?;
+        let mut vectors: HashMap<u64, Vec<f32>> = vectors.into_iter().collect();
+
+        let mut nodes = HashMap::with_capacity(snapshot.nodes.len());
+        for node in snapshot.nodes {
+            let mut vector = vectors
+                .remove(&node.id)
+                .ok_or_else(|| Error::NotFound(format!("vector for node {}", node.id)))?;
+            if vector.len() != snapshot.config.dimension {
+                return Err(Error::DimensionMismatch {
+                    expected: snapshot.config.dimension,
+                    actual: vector.len(),
+                });
+            }
+            // Stored vectors were normalized at insert; the source may be raw
+            if snapshot.config.auto_normalize {
+                normalize(&mut vector);
+            }
+            nodes.insert(
+                node.id,
+                HnswNode {
+                    id: node.id,
+                    vector,
+                    neighbors: node.neighbors,
+                    metadata: node.metadata,
+                },
+            );
+        }
+
+        let mut index = Self {
+            config: snapshot.config,
+            nodes,
+            entry_point: snapshot.entry_point,
+            max_layer: snapshot.max_layer,
+            tombstones: snapshot.tombstones,
+            stats: GraphStats::default(),
+            query_log: None,
+            rng: None,
+            incoming: None,
+        };
+        index.stats = index.diagnostics();
+        Ok(index)
+    }
+
+    /// Freeze the index into a read-only handle for serving
+    pub fn freeze(self) -> ReadOnlyHnsw {
+        ReadOnlyHnsw { inner: self }
+    }
+}
+
+/// Graph node without its vector, for graph-only snapshots
+#[derive(Serialize, Deserialize)]
+struct GraphNode {
+    id: u64,
+    neighbors: Vec<Vec<u64>>,
+    metadata: Option<String>,
+}
+
+/// Serialized form of [`HnswIndex::serialize_graph_only`]
+#[derive(Serialize, Deserialize)]
+struct GraphSnapshot {
+    config: HnswConfig,
+    nodes: Vec<GraphNode>,
+    entry_point: Option<u64>,
+    max_layer: i32,
+    tombstones: HashSet<u64>,
+}
+
+/// Headerless index layout written before [`HnswIndex::serialize`] had a
+/// format version
+#[derive(Deserialize)]
+struct LegacyIndex {
+    config: LegacyConfig,
+    nodes: HashMap<u64, LegacyNode>,
+    entry_point: Option<u64>,
+    max_layer: i32,
+}
+
+#[derive(Deserialize)]
+struct LegacyConfig {
+    dimension: usize,
+    m: usize,
+    m_max_0: usize,
+    ef_construction: usize,
+    ml: f64,
+}
+
+#[derive(Deserialize)]
+struct LegacyNode {
+    id: u64,
+    vector: Vec<f32>,
+    neighbors: Vec<Vec<u64>>,
+}
+
+impl From<LegacyIndex> for HnswIndex {
+    fn from(legacy: LegacyIndex) -> Self {
+        let mut index = HnswIndex::new(HnswConfig {
+            dimension: legacy.config.dimension,
+            m: legacy.config.m,
+            m_max_0: legacy.config.m_max_0,
+            ef_construction: legacy.config.ef_construction,
+            ml: legacy.config.ml,
+            ..Default::default()
+        });
+        index.nodes = legacy
+            .nodes
+            .into_iter()
+            .map(|(id, node)| {
+                let node = HnswNode {
+                    id: node.id,
+                    vector: node.vector,
+                    neighbors: node.neighbors,
+                    metadata: None,
+                };
+                (id, node)
+            })
+            .collect();
+        index.entry_point = legacy.entry_point;
+        index.max_layer = legacy.max_layer;
+        index.stats = index.diagnostics();
+        index
+    }
+}
+
+/// Read-only HNSW index handle
+///
+/// Exposes only query methods, so a frozen index cannot be mutated and can be
+/// shared across threads via `Arc` without locking.
+#[derive(Debug)]
+pub struct ReadOnlyHnsw {
+    inner: HnswIndex,
+}
+
+impl ReadOnlyHnsw {
+    /// Search for the top K most similar vectors
+    pub fn search(&self, query: &[f32], k: usize, ef: usize) -> Result<Vec<SearchResult>> {
+        self.inner.search(query, k, ef)
+    }
+
+    /// Search for the top K most similar vectors, returning stored metadata inline
+    pub fn search_with_metadata(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: usize,
+    ) -> Result<Vec<(SearchResult, Option<String>)>> {
+        self.inner.search_with_metadata(query, k, ef)
+    }
+
+    /// Get the number of vectors in the index
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Check if the index is empty
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Get the index configuration
+    pub fn config(&self) -> &HnswConfig {
+        &self.inner.config
+    }
+
+    /// Serialize the index to bytes
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        self.inner.serialize()
+    }
+
+    /// Convert back into a mutable index
+    pub fn thaw(self) -> HnswIndex {
+        self.inner
+    }
+}
+
+/// Thread-safe HNSW index for mixed read and write traffic
+///
+/// Wraps the index in an `RwLock`: searches share the read lock and run in
+/// parallel, while inserts and other mutations take it exclusively. Unlike
+/// [`ReadOnlyHnsw`], the index stays writable.
+#[derive(Debug)]
+pub struct ConcurrentHnsw {
+    inner: RwLock<HnswIndex>,
+}
+
+impl ConcurrentHnsw {
+    /// Wrap an index
+    pub fn new(index: HnswIndex) -> Self {
+        Self {
+            inner: RwLock::new(index),
+        }
+    }
+
+    /// Search for the top K most similar vectors under the shared lock
+    pub fn search(&self, query: &[f32], k: usize, ef: usize) -> Result<Vec<SearchResult>> {
+        self.read().map_err(|_| poisoned())?.search(query, k, ef)
+    }
+
+    /// Insert a vector under the exclusive lock
+    pub fn insert(&self, id: u64, vector: Vec<f32>) -> Result<()> {
+        self.write().map_err(|_| poisoned())?.insert(id, vector)
+    }
+
+    /// Take the shared lock for other read-only calls
+    ///
+    /// Fails like [`RwLock::read`] if a writer panicked while holding the lock.
+    pub fn read(&self) -> LockResult<RwLockReadGuard<'_, HnswIndex>> {
+        self.inner.read()
+    }
+
+    /// Take the exclusive lock for other mutations
+    pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, HnswIndex>> {
+        self.inner.write()
+    }
+
+    /// Unwrap the index
+    pub fn into_inner(self) -> Result<HnswIndex> {
+        self.inner.into_inner().map_err(|_| poisoned())
+    }
+}
+
+impl From<HnswIndex> for ConcurrentHnsw {
+    fn from(index: HnswIndex) -> Self {
+        Self::new(index)
+    }
+}
+
+fn poisoned() -> Error {
+    Error::Index("index lock poisoned by a panicked writer".to_string())
+}
+
+impl Index for HnswIndex {
+    fn insert(&mut self, id: u64, vector: Vec<f32>) -> Result<()> {
+        HnswIndex::insert(self, id, vector)
+    }
+
+    /// Searches with the configured `default_ef`
+    fn search(&self, query: &[f32], k: usize) -> Result<Vec<SearchResult>> {
+        HnswIndex::search(self, query, k, self.config.default_ef)
+    }
+
+    fn len(&self) -> usize {
+        HnswIndex::len(self)
+    }
+
+    fn dimension(&self) -> usize {
+        self.config.dimension
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_batch_cancel() {
+        let mut index = HnswIndex::new(HnswConfig {
+            dimension: 2,
+            ..Default::default()
+        });
+        let stop = AtomicBool::new(false);
+
+        // Trip the flag while the 40th item is being produced
+        let items = (0..100u64).map(|i| {
+            if i == 40 {
+                stop.store(true, AtomicOrdering::Relaxed);
+            }
+            (i, vec![1.0, i as f32])
+        });
+        let err = index.insert_batch(items, Some(&stop)).unwrap_err();
+        assert!(matches!(err, Error::Cancelled { completed: 40 }));
+        assert_eq!(index.nodes.len(), 40);
+        assert!(index.nodes.keys().all(|&id| id < 40));
+
+        let items = (100..110u64).map(|i| (i, vec![1.0, i as f32]));
+        assert_eq!(index.insert_batch(items, None).unwrap(), 10);
+        assert_eq!(index.nodes.len(), 50);
+    }
+
+    #[test]
+    fn test_insert_batch_matches_sequential() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let items: Vec<(u64, Vec<f32>)> = (0..1000u64)
+            .map(|i| (i, (0..16).map(|_| rng.gen_range(-1.0..1.0)).collect()))
+            .collect();
+        // Seeded, so both builds draw the same layers
+        let config = HnswConfig {
+            dimension: 16,
+            seed: Some(11),
+            ..Default::default()
+        };
+
+        let mut sequential = HnswIndex::new(config.clone());
+        for (id, v) in &items {
+            sequential.insert(*id, v.clone()).unwrap();
+        }
+
+        let mut batched = HnswIndex::new(config);
+        let progress = std::cell::RefCell::new(Vec::new());
+        let record = |done: usize| progress.borrow_mut().push(done);
+        batched
+            .insert_batch_with_progress(&items, Some(&record))
+            .unwrap();
+        assert_eq!(*progress.borrow(), (1..=1000).collect::<Vec<_>>());
+        assert_eq!(batched.config.parallel_expand_threshold, None);
+        assert!(batched.max_layer > 0);
+
+        for (_, query) in items.iter().step_by(50) {
+            let expected = sequential.search(query, 10, 50).unwrap();
+            let actual = batched.search(query, 10, 50).unwrap();
+            let ids = |r: &[SearchResult]| r.iter().map(|r| r.id).collect::<Vec<_>>();
+            assert_eq!(ids(&actual), ids(&expected));
+        }
+
+        // Validation happens before anything is inserted
+        let mut bad = items[..10].to_vec();
+        bad[7].1.pop();
+        let mut index = HnswIndex::new(HnswConfig {
+            dimension: 0,
+            ..Default::default()
+        });
+        assert!(matches!(
+            index.insert_batch_with_progress(&bad, None),
+            Err(Error::DimensionMismatch {
+                expected: 16,
+                actual: 15
+            })
+        ));
+        assert!(index.nodes.is_empty());
+    }
+
+    #[test]
+    fn test_recommended_config_scales() {
+        let small = HnswConfig::recommended(1_000, 8);
+        let large = HnswConfig::recommended(1_000_000, 1536);
+
+        assert!(large.m > small.m);
+        assert!(large.ef_construction > small.ef_construction);
+        assert_eq!(large.dimension, 1536);
+        small.validate().unwrap();
+        large.validate().unwrap();
+        HnswConfig::default().validate().unwrap();
+
+        let bad = HnswConfig {
+            m: 16,
+            m_max_0: 8,
+            ..Default::default()
+        };
+        assert!(matches!(bad.validate(), Err(Error::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_hnsw_basic() {
+        let config = HnswConfig {
+            dimension: 3,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+
+        index.insert(1, vec![1.0, 0.0, 0.0]).unwrap();
+        index.insert(2, vec![0.0, 1.0, 0.0]).unwrap();
+        index.insert(3, vec![0.0, 0.0, 1.0]).unwrap();
+
+        let results = index.search(&[1.0, 0.1, 0.1], 2, 10).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].id, 1);
+    }
+
+    #[test]
+    fn test_hnsw_serialization() {
+        let config = HnswConfig {
+            dimension: 3,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        index.insert(1, vec![1.0, 0.0, 0.0]).unwrap();
+
+        let bytes = index.serialize().unwrap();
+        let loaded = HnswIndex::deserialize(&bytes).unwrap();
+
+        let results = loaded.search(&[1.0, 0.0, 0.0], 1, 10).unwrap();
+        assert_eq!(results[0].id, 1);
+
+        let mut future = bytes.clone();
+        future[4..8].copy_from_slice(&(INDEX_FORMAT_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            HnswIndex::deserialize(&future),
+            Err(Error::Index(_))
+        ));
+        assert!(HnswIndex::deserialize(&bytes[..6]).is_err());
+    }
+
+    #[test]
+    fn test_hnsw_deserialize_baseline_format() {
+        use crate::vector::brute_force_topk;
+
+        // Written by the original headerless layout: 12 vectors of dimension
+        // 4, ef_construction 32, other settings at their defaults of the time
+        let bytes = include_bytes!("testdata/baseline_hnsw.bin");
+        let mut index = HnswIndex::deserialize(bytes).unwrap();
+        assert_eq!(index.len(), 12);
+        assert_eq!(index.config.dimension, 4);
+        assert_eq!(index.config.ef_construction, 32);
+        assert_eq!(index.config.metric, DistanceMetric::Cosine);
+        assert_eq!(index.live_stats(), index.diagnostics());
+
+        let vector = |i: u64| {
+            let a = i as f32 * 0.2;
+            vec![a.cos(), a.sin(), (i % 4) as f32 * 0.1, 1.0]
+        };
+        let vectors: Vec<(u64, Vec<f32>)> = (0..12).map(|i| (i, vector(i))).collect();
+        for (id, query) in &vectors {
+            let expected: Vec<u64> = brute_force_topk(query, &vectors, 3)
+                .into_iter()
+                .map(|r| r.id)
+                .collect();
+            let results: Vec<u64> = index
+                .search(query, 3, 20)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.id)
+                .collect();
+            assert_eq!(results[0], *id);
+            assert_eq!(results, expected);
+        }
+
+        // Migrated indexes stay live and save in the current format
+        index.insert(12, vector(12)).unwrap();
+        let bytes = index.serialize().unwrap();
+        assert!(bytes.starts_with(INDEX_MAGIC));
+        assert_eq!(HnswIndex::deserialize(&bytes).unwrap().len(), 13);
+    }
+
+    #[test]
+    fn test_hnsw_infer_dimension() {
+        let config = HnswConfig {
+            dimension: 0,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        assert!(index.search(&[1.0, 0.0], 1, 10).unwrap().is_empty());
+
+        index.insert(1, vec![1.0, 0.0]).unwrap();
+        assert_eq!(index.config.dimension, 2);
+
+        let result = index.insert(2, vec![1.0, 0.0, 0.0]);
+        assert!(matches!(
+            result,
+            Err(Error::DimensionMismatch {
+                expected: 2,
+                actual: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn test_hnsw_stable_sums() {
+        let config = HnswConfig {
+            dimension: 3,
+            stable_sums: true,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        for i in 0..50u64 {
+            let x = i as f32 / 50.0;
+            index.insert(i, vec![1.0 - x, x, 0.5]).unwrap();
+        }
+
+        let results = index.search(&[1.0, 0.0, 0.5], 1, 50).unwrap();
+        assert_eq!(results[0].id, 0);
+    }
+
+    #[test]
+    fn test_hnsw_search_streaming() {
+        let config = HnswConfig {
+            dimension: 3,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        for i in 0..100u64 {
+            let x = i as f32 / 100.0;
+            index
+                .insert(i, vec![1.0 - x, x, (i % 7) as f32 / 7.0])
+                .unwrap();
+        }
+
+        let query = [0.7, 0.3, 0.2];
+        let mut streamed = Vec::new();
+        index
+            .search_streaming(&query, 64, |r| streamed.push(r))
+            .unwrap();
+
+        assert_eq!(streamed.len(), 64);
+        assert!(streamed.windows(2).all(|w| w[0].score >= w[1].score));
+        assert_eq!(streamed, index.search(&query, 64, 64).unwrap());
+    }
+
+    #[test]
+    fn test_hnsw_search_with_metadata() {
+        let config = HnswConfig {
+            dimension: 3,
+            store_metadata: true,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        index
+            .insert_with_metadata(1, vec![1.0, 0.0, 0.0], Some("{\"tag\":\"a\"}".into()))
+            .unwrap();
+        index
+            .insert_with_metadata(2, vec![0.0, 1.0, 0.0], Some("{\"tag\":\"b\"}".into()))
+            .unwrap();
+        index.insert(3, vec![0.0, 0.0, 1.0]).unwrap();
+
+        let results = index.search_with_metadata(&[1.0, 0.1, 0.0], 3, 10).unwrap();
+        assert_eq!(results[0].0.id, 1);
+        assert_eq!(results[0].1.as_deref(), Some("{\"tag\":\"a\"}"));
+        assert_eq!(results[1].0.id, 2);
+        assert_eq!(results[1].1.as_deref(), Some("{\"tag\":\"b\"}"));
+        assert_eq!(results[2].1, None);
+
+        // Metadata is dropped when the option is off
+        let mut plain = HnswIndex::new(HnswConfig {
+            dimension: 3,
+            ..Default::default()
+        });
+        plain
+            .insert_with_metadata(1, vec![1.0, 0.0, 0.0], Some("x".into()))
+            .unwrap();
+        let results = plain.search_with_metadata(&[1.0, 0.0, 0.0], 1, 10).unwrap();
+        assert_eq!(results[0].1, None);
+    }
+
+    #[test]
+    fn test_hnsw_assume_normalized() {
+        let config = HnswConfig {
+            dimension: 3,
+            assume_normalized: true,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        let mut vectors = Vec::new();
+        for i in 0..40u64 {
+            let x = i as f32 / 40.0;
+            let mut v = vec![1.0 - x, x, 0.3];
+            crate::vector::normalize(&mut v);
+            index.insert(i, v.clone()).unwrap();
+            vectors.push(v);
+        }
+
+        let mut query = vec![0.6, 0.4, 0.3];
+        crate::vector::normalize(&mut query);
+        let results = index.search(&query, 5, 40).unwrap();
+        for r in results {
+            let direct = cosine_similarity(&query, &vectors[r.id as usize]);
+            assert!((r.score - direct).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_hnsw_soft_delete_auto_compact() {
+        let config = HnswConfig {
+            dimension: 3,
+            auto_compact_ratio: Some(0.25),
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        for i in 0..20u64 {
+            let x = i as f32 / 20.0;
+            index.insert(i, vec![1.0 - x, x, 0.5]).unwrap();
+        }
+
+        for id in 0..5 {
+            assert!(index.soft_delete(id));
+        }
+        assert!(!index.soft_delete(0));
+        assert!(!index.soft_delete(100));
+        assert!(!index.needs_compaction());
+
+        let results = index.search(&[1.0, 0.0, 0.5], 20, 50).unwrap();
+        assert_eq!(results.len(), 15);
+        assert!(results.iter().all(|r| r.id >= 5));
+
+        assert!(index.soft_delete(5));
+        assert!(index.needs_compaction());
+
+        // The next insert rebuilds the graph without the tombstones
+        index.insert(20, vec![0.0, 1.0, 0.5]).unwrap();
+        assert!(!index.needs_compaction());
+        assert_eq!(index.tombstone_ratio(), 0.0);
+        assert_eq!(index.nodes.len(), 15);
+
+        let results = index.search(&[1.0, 0.0, 0.5], 1, 50).unwrap();
+        assert_eq!(results[0].id, 6);
+    }
+
+    #[test]
+    fn test_hnsw_soft_delete_still_returns_k() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut index = HnswIndex::new(HnswConfig {
+            dimension: 8,
+            seed: Some(5),
+            ..Default::default()
+        });
+        for i in 0..200u64 {
+            let v: Vec<f32> = (0..8).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            index.insert(i, v).unwrap();
+        }
+
+        let query = index.nodes[&0].vector.clone();
+        let top = index.search(&query, 10, 10).unwrap();
+        for r in top.iter().step_by(2) {
+            assert!(index.soft_delete(r.id));
+        }
+
+        // ef equals k, so the deleted nodes must not take up result slots
+        let results = index.search(&query, 10, 10).unwrap();
+        assert_eq!(results.len(), 10);
+        assert!(results.iter().all(|r| !index.tombstones.contains(&r.id)));
+        assert_eq!(results[0].id, top[1].id);
+
+        // Every node deleted: nothing to return, but no panic
+        let ids: Vec<u64> = index.nodes.keys().copied().collect();
+        for id in ids {
+            index.soft_delete(id);
+        }
+        assert!(index.search(&query, 10, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_hnsw_auto_normalize() {
+        let config = HnswConfig {
+            dimension: 3,
+            auto_normalize: true,
+            assume_normalized: true,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        index.insert(1, vec![3.0, 4.0, 0.0]).unwrap();
+        index.insert(2, vec![0.0, 10.0, 0.0]).unwrap();
+        index.insert(3, vec![0.0, 0.0, 0.5]).unwrap();
+
+        let stored = &index.nodes[&1].vector;
+        let norm: f32 = stored.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-6);
+
+        let results = index.search(&[6.0, 8.0, 0.0], 1, 10).unwrap();
+        assert_eq!(results[0].id, 1);
+        assert!((results[0].score - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_hnsw_search_with_stats() {
+        let mut index = HnswIndex::new(HnswConfig {
+            dimension: 8,
+            m: 4,
+            m_max_0: 8,
+            seed: Some(5),
+            ..Default::default()
+        });
+        let query = [0.3f32; 8];
+        let (results, stats) = index.search_with_stats(&query, 5, 20).unwrap();
+        assert!(results.is_empty());
+        assert_eq!(stats, SearchStats::default());
+
+        for i in 0..500u64 {
+            let v = (0..8).map(|d| ((i * 8 + d) as f32 * 0.61).sin()).collect();
+            index.insert(i, v).unwrap();
+        }
+        assert!(index.max_layer > 0);
+
+        let (results, stats) = index.search_with_stats(&query, 5, 20).unwrap();
+        assert_eq!(results, index.search(&query, 5, 20).unwrap());
+        assert!(stats.distance_computations > 0);
+        assert!(stats.nodes_visited > 0);
+        assert!(stats.distance_computations >= stats.nodes_visited);
+        assert!(stats.distance_computations <= index.nodes.len() * (index.max_layer as usize + 1));
+
+        // A wider search does more work
+        let (_, wide) = index.search_with_stats(&query, 5, 200).unwrap();
+        assert!(wide.nodes_visited > stats.nodes_visited);
+
+        let hops: usize = (0..20)
+            .map(|i| {
+                let q: Vec<f32> = (0..8).map(|d| ((i * 13 + d) as f32).cos()).collect();
+                index.search_with_stats(&q, 1, 10).unwrap().1.hops
+            })
+            .sum();
+        assert!(hops > 0);
+    }
+
+    #[test]
+    fn test_hnsw_compressed_roundtrip() {
+        // Few distinct vectors, so the bincode output is highly redundant
+        let mut index = HnswIndex::new(HnswConfig {
+            dimension: 32,
+            ..Default::default()
+        });
+        for i in 0..300u64 {
+            let mut v = vec![0.0; 32];
+            v[(i % 8) as usize] = 1.0;
+            v[8 + (i % 5) as usize] = 0.5;
+            index.insert(i, v).unwrap();
+        }
+        let raw = index.serialize().unwrap();
+        let query = index.nodes[&7].vector.clone();
+        let expected = index.search(&query, 5, 50).unwrap();
+
+        for codec in [Compression::Zstd { level: 3 }, Compression::Gzip] {
+            let packed = index.serialize_compressed(codec).unwrap();
+            assert!(
+                packed.len() < raw.len(),
+                "{:?}: {} >= {}",
+                codec,
+                packed.len(),
+                raw.len()
+            );
+            let loaded = HnswIndex::deserialize_compressed(&packed).unwrap();
+            assert_eq!(loaded.nodes.len(), 300);
+            assert_eq!(loaded.search(&query, 5, 50).unwrap(), expected);
+        }
+
+        // Uncompressed bytes are still accepted
+        assert_eq!(
+            HnswIndex::deserialize_compressed(&raw).unwrap().nodes.len(),
+            300
+        );
+        assert!(matches!(
+            HnswIndex::deserialize_compressed(b"VXHC\x09junk"),
+            Err(Error::Decompression(_))
+        ));
+        assert!(matches!(
+            HnswIndex::deserialize_compressed(b"VXHC\x02junk"),
+            Err(Error::Decompression(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_hnsw_save_load() {
+        let client = StorageClient::memory().unwrap();
+        let mut index = HnswIndex::new(HnswConfig {
+            dimension: 3,
+            ..Default::default()
+        });
+        for i in 0..50u64 {
+            let a = i as f32 * 0.1;
+            index.insert(i, vec![a.cos(), a.sin(), 0.5]).unwrap();
+        }
+        index.save(&client, "index/v1.hnsw").await.unwrap();
+
+        let loaded = HnswIndex::load(&client, "index/v1.hnsw").await.unwrap();
+        assert_eq!(loaded.nodes.len(), 50);
+        let query = [1.0, 0.2, 0.5];
+        assert_eq!(
+            loaded.search(&query, 5, 50).unwrap(),
+            index.search(&query, 5, 50).unwrap()
+        );
+
+        let empty = HnswIndex::new(HnswConfig {
+            dimension: 0,
+            ..Default::default()
+        });
+        empty.save(&client, "index/empty.hnsw").await.unwrap();
+        assert!(matches!(
+            HnswIndex::load(&client, "index/empty.hnsw").await,
+            Err(Error::Index(_))
+        ));
+        assert!(HnswIndex::load(&client, "index/missing.hnsw")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hnsw_search_threshold_to_parquet() {
+        use crate::storage::ParquetReader;
+
+        let config = HnswConfig {
+            dimension: 3,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        for i in 0..30u64 {
+            let x = i as f32 / 30.0;
+            index.insert(i, vec![1.0 - x, x, 0.0]).unwrap();
+        }
+
+        let client = StorageClient::memory().unwrap();
+        let query = [1.0, 0.0, 0.0];
+        let threshold = 0.9;
+        let written = index
+            .search_threshold_to_parquet(&query, threshold, 30, &client, "exports/q.parquet")
+            .await
+            .unwrap();
+
+        let expected: Vec<SearchResult> = index
+            .search(&query, 30, 30)
+            .unwrap()
+            .into_iter()
+            .filter(|r| r.score >= threshold)
+            .collect();
+        assert_eq!(written, expected.len());
+        assert!(written > 0 && written < 30);
+
+        let batches = ParquetReader::new(&client)
+            .read_all("exports/q.parquet")
+            .await
+            .unwrap();
+        let mut actual = Vec::new();
+        for batch in &batches {
+            let ids = batch
+                .column_by_name("id")
+                .unwrap()
+                .as_any()
+                .downcast_ref::<UInt64Array>()
+                .unwrap();
+            let scores = batch
+                .column_by_name("score")
+                .unwrap()
+                .as_any()
+                .downcast_ref::<Float32Array>()
+                .unwrap();
+            for row in 0..batch.num_rows() {
+                actual.push(SearchResult::new(ids.value(row), scores.value(row)));
+            }
+        }
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_hnsw_search_radius() {
+        let config = HnswConfig {
+            dimension: 2,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        // Unit vectors at 0, 1, 2, ... 89 degrees
+        for deg in 0..90u64 {
+            let rad = (deg as f32).to_radians();
+            index.insert(deg, vec![rad.cos(), rad.sin()]).unwrap();
+        }
+
+        // cos(10 deg) ~ 0.9848, so radius 1 - 0.98 keeps 0..=11 degrees
+        let radius = 1.0 - 0.98;
+        let results = index.search_radius(&[1.0, 0.0], radius, 100, 4).unwrap();
+        let mut ids: Vec<u64> = results.iter().map(|r| r.id).collect();
+        assert!(results.windows(2).all(|w| w[0].score >= w[1].score));
+        ids.sort();
+        assert_eq!(ids, (0..=11).collect::<Vec<u64>>());
+
+        let capped = index.search_radius(&[1.0, 0.0], radius, 3, 4).unwrap();
+        let capped_ids: Vec<u64> = capped.iter().map(|r| r.id).collect();
+        assert_eq!(capped_ids, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_hnsw_search_range() {
+        let config = HnswConfig {
+            dimension: 2,
+            metric: DistanceMetric::L2,
+            m: 4,
+            m_max_0: 8,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        // 40 points on a small circle around the origin, 40 far away
+        for i in 0..40u64 {
+            let a = i as f32 * 0.157;
+            index.insert(i, vec![a.cos(), a.sin()]).unwrap();
+            index.insert(100 + i, vec![50.0 + a.cos(), 50.0]).unwrap();
+        }
+
+        // ef 1 would stop a top-k search after one candidate
+        let results = index.search_range(&[0.0, 0.0], 1.5, 1).unwrap();
+        let mut ids: Vec<u64> = results.iter().map(|r| r.id).collect();
+        assert!(results.windows(2).all(|w| w[0].score <= w[1].score));
+        ids.sort();
+        assert_eq!(ids, (0..40).collect::<Vec<u64>>());
+
+        assert!(index.search_range(&[0.0, 0.0], 0.5, 8).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_hnsw_per_layer_ef_construction() {
+        use crate::vector::brute_force_topk;
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let dim = 16;
+        let items: Vec<(u64, Vec<f32>)> = (0..500u64)
+            .map(|i| (i, (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect()))
+            .collect();
+        let queries: Vec<Vec<f32>> = (0..20)
+            .map(|_| (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect();
+
+        let recall = |config: HnswConfig| {
+            let mut index = HnswIndex::new(config);
+            for (id, v) in &items {
+                index.insert(*id, v.clone()).unwrap();
+            }
+            let mut hits = 0;
+            for q in &queries {
+                let exact: HashSet<u64> = brute_force_topk(q, &items, 10)
+                    .into_iter()
+                    .map(|r| r.id)
+                    .collect();
+                hits += index
+                    .search(q, 10, 64)
+                    .unwrap()
+                    .iter()
+                    .filter(|r| exact.contains(&r.id))
+                    .count();
+            }
+            hits as f32 / (queries.len() * 10) as f32
+        };
+
+        // Same layer assignment for both builds, so only ef differs
+        let uniform = recall(HnswConfig {
+            dimension: dim,
+            ef_construction: 100,
+            seed: Some(7),
+            ..Default::default()
+        });
+        let per_layer = recall(HnswConfig {
+            dimension: dim,
+            ef_construction: 16,
+            ef_construction_per_layer: vec![100],
+            seed: Some(7),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            HnswConfig {
+                ef_construction_per_layer: vec![100],
+                ..Default::default()
+            }
+            .ef_construction_for(3),
+            200
+        );
+        assert!(per_layer >= 0.9, "per-layer recall {per_layer}");
+        assert!(per_layer >= uniform - 0.05, "{per_layer} vs {uniform}");
+    }
+
+    #[test]
+    fn test_hnsw_query_transform() {
+        let build = |transform: Option<Matrix>| {
+            let mut index = HnswIndex::new(HnswConfig {
+                dimension: 2,
+                query_transform: transform,
+                ..Default::default()
+            });
+            index.insert(1, vec![1.0, 0.0]).unwrap();
+            index.insert(2, vec![0.0, 1.0]).unwrap();
+            index.insert(3, vec![0.7, 0.7]).unwrap();
+            index
+        };
+
+        let plain = build(None);
+        let identity = build(Some(Matrix::identity(2)));
+        let query = [1.0, 0.2];
+        assert_eq!(
+            identity.search(&query, 3, 10).unwrap(),
+            plain.search(&query, 3, 10).unwrap()
+        );
+
+        // Swapping the axes maps the query onto the other document
+        let swap = build(Some(Matrix::new(2, 2, vec![0.0, 1.0, 1.0, 0.0]).unwrap()));
+        assert_eq!(plain.search(&query, 1, 10).unwrap()[0].id, 1);
+        assert_eq!(swap.search(&query, 1, 10).unwrap()[0].id, 2);
+
+        // A 3 -> 2 projection takes 3-dim queries
+        let project = build(Some(
+            Matrix::new(2, 3, vec![1.0, 0.0, 0.0, 0.0, 0.0, 1.0]).unwrap(),
+        ));
+        assert_eq!(project.search(&[0.0, 5.0, 1.0], 1, 10).unwrap()[0].id, 2);
+        assert!(project.search(&[1.0, 0.0], 1, 10).is_err());
+
+        let bytes = project.serialize().unwrap();
+        let loaded = HnswIndex::deserialize(&bytes).unwrap();
+        assert_eq!(
+            loaded.config.query_transform,
+            project.config.query_transform
+        );
+    }
+
+    #[test]
+    fn test_hnsw_search_with_margin() {
+        let config = HnswConfig {
+            dimension: 3,
+            ..Default::default()
+        };
+
+        // One vector matches the query, the rest point elsewhere
+        let mut dominant = HnswIndex::new(config.clone());
+        dominant.insert(0, vec![1.0, 0.0, 0.0]).unwrap();
+        for i in 1..10u64 {
+            dominant.insert(i, vec![0.1, 1.0, i as f32 / 10.0]).unwrap();
+        }
+        let (results, margin) = dominant
+            .search_with_margin(&[1.0, 0.0, 0.0], 5, 20)
+            .unwrap();
+        assert_eq!(results[0].id, 0);
+        assert!(margin > 0.5, "margin {margin}");
+
+        // All vectors are nearly identical
+        let mut uniform = HnswIndex::new(config);
+        for i in 0..10u64 {
+            uniform.insert(i, vec![1.0, 0.01 * i as f32, 0.0]).unwrap();
+        }
+        let (_, margin) = uniform.search_with_margin(&[1.0, 0.0, 0.0], 5, 20).unwrap();
+        assert!(margin < 0.01, "margin {margin}");
+
+        let (_, margin) = uniform.search_with_margin(&[1.0, 0.0, 0.0], 1, 20).unwrap();
+        assert_eq!(margin, 0.0);
+    }
+
+    #[test]
+    fn test_hnsw_insert_into_loaded_index() {
+        let config = HnswConfig {
+            dimension: 3,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        for i in 0..50u64 {
+            let x = i as f32 / 50.0;
+            index.insert(i, vec![x, 1.0 - x, 0.0]).unwrap();
+        }
+
+        let mut loaded = HnswIndex::deserialize(&index.serialize().unwrap()).unwrap();
+        loaded.insert(100, vec![0.3, 0.7, 0.2]).unwrap();
+        loaded.insert(101, vec![0.8, 0.2, 0.2]).unwrap();
+
+        assert_eq!(loaded.search(&[0.3, 0.7, 0.2], 1, 20).unwrap()[0].id, 100);
+        assert_eq!(loaded.search(&[0.8, 0.2, 0.2], 1, 20).unwrap()[0].id, 101);
+        let results = loaded.search(&[0.5, 0.5, 0.0], 1, 20).unwrap();
+        assert!(results[0].id < 50);
+
+        let reloaded = HnswIndex::deserialize(&loaded.serialize().unwrap()).unwrap();
+        assert_eq!(reloaded.nodes.len(), 52);
+        assert_eq!(reloaded.search(&[0.3, 0.7, 0.2], 1, 20).unwrap()[0].id, 100);
+        assert_eq!(reloaded.search(&[0.8, 0.2, 0.2], 1, 20).unwrap()[0].id, 101);
+    }
+
+    #[test]
+    fn test_concurrent_hnsw_parallel_search() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<HnswIndex>();
+        assert_send_sync::<ConcurrentHnsw>();
+
+        let mut index = HnswIndex::new(HnswConfig {
+            dimension: 3,
+            ..Default::default()
+        });
+        for deg in 0..180u64 {
+            let rad = (deg as f32 * 2.0).to_radians();
+            index.insert(deg, vec![rad.cos(), rad.sin(), 0.0]).unwrap();
+        }
+        let shared = Arc::new(ConcurrentHnsw::new(index));
+
+        let readers: Vec<_> = (0..8u64)
+            .map(|t| {
+                let shared = Arc::clone(&shared);
+                std::thread::spawn(move || {
+                    for i in 0..50u64 {
+                        let id = (t * 50 + i) % 180;
+                        let rad = (id as f32 * 2.0).to_radians();
+                        let results = shared.search(&[rad.cos(), rad.sin(), 0.0], 1, 32).unwrap();
+                        assert_eq!(results[0].id, id);
+                    }
+                })
+            })
+            .collect();
+        // A writer interleaves with the readers, away from their queries
+        for id in 1000..1020u64 {
+            shared
+                .insert(id, vec![(id - 1000) as f32 * 0.01, 0.0, 1.0])
+                .unwrap();
+        }
+        for reader in readers {
+            reader.join().unwrap();
+        }
+
+        let index = Arc::try_unwrap(shared).unwrap().into_inner().unwrap();
+        assert_eq!(index.len(), 200);
+    }
+
+    #[test]
+    fn test_hnsw_freeze() {
+        let config = HnswConfig {
+            dimension: 3,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        index.insert(1, vec![1.0, 0.0, 0.0]).unwrap();
+        index.insert(2, vec![0.0, 1.0, 0.0]).unwrap();
+        index.insert(3, vec![0.0, 0.0, 1.0]).unwrap();
+
+        let query = [1.0, 0.1, 0.1];
+        let expected = index.search(&query, 3, 10).unwrap();
+
+        let frozen = std::sync::Arc::new(index.freeze());
+        assert_eq!(frozen.len(), 3);
+        assert_eq!(frozen.config().dimension, 3);
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let frozen = std::sync::Arc::clone(&frozen);
+                std::thread::spawn(move || frozen.search(&query, 3, 10).unwrap())
+            })
+            .collect();
+
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_hnsw_single_node() {
+        let config = HnswConfig {
+            dimension: 3,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        index.insert(7, vec![1.0, 1.0, 0.0]).unwrap();
+        assert_eq!(index.entry_point, Some(7));
+
+        let query = [1.0, 0.0, 0.0];
+        let expected = 1.0 / 2.0f32.sqrt();
+        for (k, ef) in [(1, 1), (5, 10), (5, 0)] {
+            let results = index.search(&query, k, ef).unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].id, 7);
+            assert!((results[0].score - expected).abs() < 1e-6);
+        }
+        assert!(index.search(&query, 0, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_hnsw_graph_only_roundtrip() {
+        use crate::storage::{extract_vectors, ParquetWriter};
+
+        let config = HnswConfig {
+            dimension: 4,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        let mut ids = Vec::new();
+        let mut vectors = Vec::new();
+        for i in 0..30u64 {
+            let v: Vec<f32> = (0..4).map(|j| ((i * 13 + j * 5) as f32).cos()).collect();
+            index.insert(i, v.clone()).unwrap();
+            ids.push(i);
+            vectors.push(v);
+        }
+
+        let graph = index.serialize_graph_only().unwrap();
+        assert!(graph.len() < index.serialize().unwrap().len());
+
+        let client = StorageClient::memory().unwrap();
+        let batch = ParquetWriter::new(&client, 4)
+            .create_batch(&ids, &vectors, &vec![None; ids.len()])
+            .unwrap();
+        let loaded =
+            HnswIndex::load_graph_with_vectors(&graph, extract_vectors(&batch).unwrap()).unwrap();
+
+        let query = [0.3, -0.2, 0.9, 0.1];
+        assert_eq!(
+            loaded.search(&query, 5, 20).unwrap(),
+            index.search(&query, 5, 20).unwrap()
+        );
+
+        let partial = extract_vectors(&batch).unwrap().into_iter().skip(1);
+        assert!(matches!(
+            HnswIndex::load_graph_with_vectors(&graph, partial),
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_hnsw_search_filtered_by_metadata_index() {
+        use crate::storage::MetadataIndex;
+
+        let config = HnswConfig {
+            dimension: 2,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        let mut tags = MetadataIndex::new();
+        for i in 0..20u64 {
+            let angle = i as f32 * 0.05;
+            index.insert(i, vec![angle.cos(), angle.sin()]).unwrap();
+            tags.insert(if i % 2 == 0 { "even" } else { "odd" }, i);
+        }
+
+        let query = [1.0, 0.0];
+        let odd = index
+            .search_filtered(&query, 3, 50, &tags.ids_for("odd"))
+            .unwrap();
+        assert_eq!(odd.iter().map(|r| r.id).collect::<Vec<_>>(), vec![1, 3, 5]);
+
+        let not_odd = index
+            .search_excluding(&query, 3, 50, &tags.ids_for("odd"))
+            .unwrap();
+        assert_eq!(
+            not_odd.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![0, 2, 4]
+        );
+    }
+
+    #[test]
+    fn test_hnsw_promoted_entry_point_is_linked() {
+        let config = HnswConfig {
+            dimension: 3,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        for i in 0..50u64 {
+            let a = i as f32 * 0.1;
+            index.insert(i, vec![a.cos(), a.sin(), 0.2]).unwrap();
+        }
+
+        // Force a node well above the current top layer
+        let old_max = index.max_layer;
+        let level = old_max + 3;
+        index
+            .link_node(999, vec![0.0, 0.0, 1.0], None, level, None)
+            .unwrap();
+        assert_eq!(index.entry_point, Some(999));
+        assert_eq!(index.max_layer, level);
+
+        let node = &index.nodes[&999];
+        assert_eq!(node.neighbors.len(), level as usize + 1);
+        for l in 0..=old_max as usize {
+            assert!(!node.neighbors[l].is_empty(), "no neighbors at layer {}", l);
+        }
+        // Reachable back from the graph at layer 0
+        assert!(index
+            .nodes
+            .values()
+            .any(|n| n.id != 999 && n.neighbors[0].contains(&999)));
+
+        assert_eq!(index.search(&[0.0, 0.0, 1.0], 1, 10).unwrap()[0].id, 999);
+        for i in [0u64, 17, 42] {
+            let a = i as f32 * 0.1;
+            let results = index.search(&[a.cos(), a.sin(), 0.2], 1, 50).unwrap();
+            assert_eq!(results[0].id, i);
+        }
+    }
+
+    #[test]
+    fn test_hnsw_search_default_persisted() {
+        let config = HnswConfig {
+            dimension: 2,
+            default_k: 3,
+            default_ef: 5,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        for i in 0..10u64 {
+            index.insert(i, vec![1.0, i as f32]).unwrap();
+        }
+
+        let loaded = HnswIndex::deserialize(&index.serialize().unwrap()).unwrap();
+        assert_eq!(loaded.config.default_k, 3);
+        assert_eq!(loaded.config.default_ef, 5);
+
+        let query = [1.0, 4.2];
+        let results = loaded.search_default(&query).unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results, loaded.search(&query, 3, 5).unwrap());
+    }
+
+    #[test]
+    fn test_hnsw_live_stats_match_diagnostics() {
+        let config = HnswConfig {
+            dimension: 4,
+            // Small fan-out so pruning kicks in often
+            m: 4,
+            m_max_0: 4,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        for i in 0..200u64 {
+            let v: Vec<f32> = (0..4).map(|j| ((i * 7 + j * 3) as f32).sin()).collect();
+            index.insert(i, v).unwrap();
+        }
+        // Re-inserting an ID replaces its node
+        index.insert(5, vec![1.0, 0.0, 0.0, 0.0]).unwrap();
+        for id in [3, 50, 120] {
+            index.soft_delete(id);
+        }
+
+        let stats = index.live_stats();
+        assert_eq!(stats, index.diagnostics());
+        assert_eq!(stats.nodes_per_layer[0], 200);
+        assert_eq!(stats.tombstones, 3);
+        assert!(stats.total_edges > 0);
+
+        index.compact().unwrap();
+        assert_eq!(index.live_stats(), index.diagnostics());
+        assert_eq!(index.live_stats().nodes_per_layer[0], 197);
+    }
+
+    #[test]
+    fn test_hnsw_parallel_expand_unchanged() {
+        let config = HnswConfig {
+            dimension: 32,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        for i in 0..300u64 {
+            let v: Vec<f32> = (0..32).map(|j| ((i * 31 + j * 7) as f32).sin()).collect();
+            index.insert(i, v).unwrap();
+        }
+
+        let queries: Vec<Vec<f32>> = (0..5)
+            .map(|q| (0..32).map(|j| ((q * 13 + j) as f32).cos()).collect())
+            .collect();
+        let sequential: Vec<_> = queries
+            .iter()
+            .map(|q| index.search(q, 10, 128).unwrap())
             .collect();
 
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
-        results.truncate(k);
+        index.set_parallel_expand_threshold(Some(1));
+        for (q, expected) in queries.iter().zip(&sequential) {
+            assert_eq!(&index.search(q, 10, 128).unwrap(), expected);
+        }
+    }
 
-        Ok(results)
+    #[test]
+    fn test_hnsw_huge_k_returns_all_nodes() {
+        let config = HnswConfig {
+            dimension: 2,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        for i in 0..100u64 {
+            let a = i as f32 * 0.03;
+            index.insert(i, vec![a.cos(), a.sin()]).unwrap();
+        }
+        index.soft_delete(7);
+
+        let results = index.search(&[1.0, 0.0], 1_000_000, 10).unwrap();
+        assert_eq!(results.len(), 99);
+        assert!(results.iter().all(|r| r.id != 7));
+        assert!(results.windows(2).all(|w| w[0].score >= w[1].score));
+
+        let results = index.search(&[1.0, 0.0], 5, usize::MAX).unwrap();
+        assert_eq!(results.len(), 5);
     }
 
-    fn generate_random_layer(&self) -> i32 {
-        let mut rng = thread_rng();
-        let r: f64 = rng.gen();
-        (-(r.ln() * self.config.ml).floor()) as i32
+    #[test]
+    fn test_hnsw_delete_healing_preserves_recall() {
+        use crate::vector::brute_force_topk;
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let dim = 16;
+        let mut rng = StdRng::seed_from_u64(7);
+        let vectors: Vec<(u64, Vec<f32>)> = (0..1000u64)
+            .map(|i| (i, (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect()))
+            .collect();
+
+        let config = HnswConfig {
+            dimension: dim,
+            m: 6,
+            m_max_0: 6,
+            ef_construction: 64,
+            seed: Some(7),
+            ..Default::default()
+        };
+        let mut simple = HnswIndex::new(config);
+        for (id, v) in &vectors {
+            simple.insert(*id, v.clone()).unwrap();
+        }
+        let mut healed = HnswIndex::deserialize(&simple.serialize().unwrap()).unwrap();
+
+        // Delete 70% of the nodes
+        let survivors: Vec<(u64, Vec<f32>)> = vectors
+            .iter()
+            .filter(|(id, _)| id % 10 < 3)
+            .cloned()
+            .collect();
+        for (id, _) in vectors.iter().filter(|(id, _)| id % 10 >= 3) {
+            assert!(simple.delete(*id).unwrap());
+            assert!(healed.delete_with_healing(*id).unwrap());
+        }
+        assert!(!simple.delete(3).unwrap());
+        assert_eq!(simple.live_stats(), simple.diagnostics());
+        assert_eq!(healed.live_stats(), healed.diagnostics());
+        // The reverse edge index tracked every change
+        for index in [&mut simple, &mut healed] {
+            let mut tracked = index.incoming.take().unwrap();
+            tracked.retain(|_, edges| !edges.is_empty());
+            assert_eq!(&tracked, index.incoming_edges());
+        }
+
+        let recall = |index: &HnswIndex| {
+            let mut hits = 0;
+            for (_, query) in vectors.iter().take(100) {
+                let truth: HashSet<u64> = brute_force_topk(query, &survivors, 10)
+                    .into_iter()
+                    .map(|r| r.id)
+                    .collect();
+                hits += index
+                    .search(query, 10, 10)
+                    .unwrap()
+                    .iter()
+                    .filter(|r| truth.contains(&r.id))
+                    .count();
+            }
+            hits as f32 / 1000.0
+        };
+
+        let (simple_recall, healed_recall) = (recall(&simple), recall(&healed));
+        assert!(
+            healed_recall > simple_recall,
+            "healed {} vs simple {}",
+            healed_recall,
+            simple_recall
+        );
     }
 
-    /// Serialize the index to bytes
-    pub fn serialize(&self) -> Result<Vec<u8>> {
-        bincode::serialize(self).map_err(|e| Error::Bincode(e.to_string()))
+    #[test]
+    fn test_hnsw_reinsert_then_delete_old_neighbors() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let dim = 8;
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut vector = || -> Vec<f32> { (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect() };
+        let mut index = HnswIndex::new(HnswConfig {
+            dimension: dim,
+            m: 4,
+            m_max_0: 8,
+            seed: Some(5),
+            ..Default::default()
+        });
+        for id in 0..200u64 {
+            index.insert(id, vector()).unwrap();
+        }
+        // Builds the reverse edge index
+        assert!(index.delete(199).unwrap());
+
+        for id in 0..100u64 {
+            // Deleted as an earlier id's neighbor
+            let Some(node) = index.nodes.get(&id) else {
+                continue;
+            };
+            let old: Vec<u64> = node.neighbors.concat();
+            index.insert(id, vector()).unwrap();
+            assert!(index.nodes[&id].neighbors.iter().all(|l| !l.contains(&id)));
+            if id % 4 == 0 {
+                for neighbor in old.into_iter().filter(|&n| n != id) {
+                    index.delete(neighbor).unwrap();
+                }
+            }
+        }
+        assert_eq!(index.live_stats(), index.diagnostics());
+        let mut tracked = index.incoming.take().unwrap();
+        tracked.retain(|_, edges| !edges.is_empty());
+        assert_eq!(&tracked, index.incoming_edges());
     }
 
-    /// Deserialize the index from bytes
-    pub fn deserialize(bytes: &[u8]) -> Result<Self> {
-        bincode::deserialize(bytes).map_err(|e| Error::Bincode(e.to_string()))
+    #[test]
+    fn test_hnsw_delete_entry_point() {
+        use crate::vector::brute_force_topk;
+
+        let config = HnswConfig {
+            dimension: 2,
+            m: 4,
+            m_max_0: 8,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        let mut vectors = Vec::new();
+        for i in 0..100u64 {
+            let a = i as f32 * 0.06;
+            let v = vec![a.cos(), a.sin()];
+            index.insert(i, v.clone()).unwrap();
+            vectors.push((i, v));
+        }
+
+        let entry = index.entry_point.unwrap();
+        assert!(index.delete(entry).unwrap());
+        assert!(!index.delete(entry).unwrap());
+        vectors.retain(|(id, _)| *id != entry);
+
+        let new_entry = index.entry_point.unwrap();
+        assert_ne!(new_entry, entry);
+        assert_eq!(
+            index.nodes[&new_entry].neighbors.len() as i32 - 1,
+            index.max_layer
+        );
+        assert_eq!(
+            index
+                .nodes
+                .values()
+                .map(|n| n.neighbors.len() as i32 - 1)
+                .max(),
+            Some(index.max_layer)
+        );
+        assert!(index
+            .nodes
+            .values()
+            .all(|n| n.neighbors.iter().all(|l| !l.contains(&entry))));
+        assert_eq!(index.live_stats(), index.diagnostics());
+
+        for (_, query) in vectors.iter().step_by(10) {
+            let expected: Vec<u64> = brute_force_topk(query, &vectors, 5)
+                .into_iter()
+                .map(|r| r.id)
+                .collect();
+            let results = index.search(query, 5, 50).unwrap();
+            assert!(results.iter().all(|r| r.id != entry));
+            let hits = results.iter().filter(|r| expected.contains(&r.id)).count();
+            assert!(hits >= 4, "{:?} vs {:?}", results, expected);
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_hnsw_exploration_escapes_local_minimum() {
+        let config = HnswConfig {
+            dimension: 2,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+
+        // The entry point's closest neighbor is a dead end; the true nearest
+        // neighbor (3) is only reachable through the farther path node (2)
+        let at = |deg: f32| vec![deg.to_radians().cos(), deg.to_radians().sin()];
+        let graph = [
+            (0, at(90.0), vec![1, 2]),
+            (1, at(40.0), vec![0]),
+            (2, at(80.0), vec![0, 3]),
+            (3, at(0.0), vec![2]),
+        ];
+        for (id, vector, neighbors) in graph {
+            index.put_node(HnswNode {
+                id,
+                vector,
+                neighbors: vec![neighbors],
+                metadata: None,
+            });
+        }
+        index.entry_point = Some(0);
+        index.max_layer = 0;
+
+        let hits = |index: &HnswIndex| {
+            (0..200)
+                .filter(|_| index.search(&[1.0, 0.0], 1, 1).unwrap()[0].id == 3)
+                .count()
+        };
+        assert_eq!(hits(&index), 0);
+
+        index.set_exploration(0.3).unwrap();
+        assert!(hits(&index) > 0);
+        assert!(index.set_exploration(1.5).is_err());
+    }
 
     #[test]
-    fn test_hnsw_basic() {
+    fn test_hnsw_select_heuristic_improves_recall() {
+        use crate::vector::brute_force_topk;
+        use rand::{rngs::StdRng, SeedableRng};
+
+        // Tight, well separated clusters: closest-only selection links every
+        // node within its own cluster and leaves few bridges between them
+        let dim = 8;
+        let mut rng = StdRng::seed_from_u64(3);
+        let centers: Vec<Vec<f32>> = (0..40)
+            .map(|_| (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect();
+        let vectors: Vec<(u64, Vec<f32>)> = (0..2000u64)
+            .map(|i| {
+                let center = &centers[i as usize % centers.len()];
+                let v = center
+                    .iter()
+                    .map(|c| c + rng.gen_range(-0.05..0.05))
+                    .collect();
+                (i, v)
+            })
+            .collect();
+
+        let queries: Vec<Vec<f32>> = (0..200)
+            .map(|_| {
+                let center = &centers[rng.gen_range(0..centers.len())];
+                center
+                    .iter()
+                    .map(|c| c + rng.gen_range(-0.05..0.05))
+                    .collect()
+            })
+            .collect();
+
+        let recall = |select_heuristic: bool, extend_candidates: bool| {
+            let mut index = HnswIndex::new(HnswConfig {
+                dimension: dim,
+                m: 4,
+                m_max_0: 8,
+                ef_construction: 32,
+                select_heuristic,
+                extend_candidates,
+                seed: Some(3),
+                ..Default::default()
+            });
+            for (id, v) in &vectors {
+                index.insert(*id, v.clone()).unwrap();
+            }
+            let mut hits = 0;
+            for query in &queries {
+                let truth: HashSet<u64> = brute_force_topk(query, &vectors, 10)
+                    .into_iter()
+                    .map(|r| r.id)
+                    .collect();
+                hits += index
+                    .search(query, 10, 10)
+                    .unwrap()
+                    .iter()
+                    .filter(|r| truth.contains(&r.id))
+                    .count();
+            }
+            hits as f32 / 2000.0
+        };
+
+        let simple = recall(false, false);
+        let heuristic = recall(true, false);
+        let extended = recall(true, true);
+        assert!(
+            heuristic > simple + 0.3,
+            "heuristic {} vs simple {}",
+            heuristic,
+            simple
+        );
+        assert!(
+            extended > simple + 0.3,
+            "extended {} vs simple {}",
+            extended,
+            simple
+        );
+    }
+
+    #[test]
+    fn test_hnsw_update_vector() {
         let config = HnswConfig {
-            dimension: 3,
+            dimension: 2,
+            m: 4,
+            m_max_0: 8,
+            seed: Some(3),
             ..Default::default()
         };
         let mut index = HnswIndex::new(config);
+        for i in 0..100u64 {
+            let a = i as f32 * 0.06;
+            index.insert(i, vec![a.cos(), a.sin()]).unwrap();
+        }
+        let angle = |deg: f32| vec![deg.to_radians().cos(), deg.to_radians().sin()];
 
-        index.insert(1, vec![1.0, 0.0, 0.0]).unwrap();
-        index.insert(2, vec![0.0, 1.0, 0.0]).unwrap();
-        index.insert(3, vec![0.0, 0.0, 1.0]).unwrap();
+        // Node 10 sits at ~34 degrees; a nudge keeps its edges
+        let edges = index.nodes[&10].neighbors.clone();
+        assert!(!index.update_vector(10, angle(34.5)).unwrap());
+        assert_eq!(index.nodes[&10].neighbors, edges);
+        let results = index.search(&angle(34.5), 1, 50).unwrap();
+        assert_eq!(results[0].id, 10);
+        assert!(results[0].score > 0.9999);
 
-        let results = index.search(&[1.0, 0.1, 0.1], 2, 10).unwrap();
-        assert_eq!(results.len(), 2);
-        assert_eq!(results[0].id, 1);
+        // Moving node 10 to the far side of the circle rebuilds its edges
+        // around node 52 (~179 degrees)
+        assert!(index.update_vector(10, angle(180.5)).unwrap());
+        assert_ne!(index.nodes[&10].neighbors, edges);
+        assert!(index.nodes[&10].neighbors[0].contains(&52));
+        assert!(index.nodes[&52].neighbors[0].contains(&10));
+        // Nodes near the old position no longer link to it
+        assert!((5..16u64)
+            .filter(|&n| n != 10)
+            .all(|n| index.nodes[&n].neighbors.iter().all(|l| !l.contains(&10))));
+        let results = index.search(&angle(180.5), 1, 50).unwrap();
+        assert_eq!(results[0].id, 10);
+        assert!(index
+            .search(&angle(34.5), 3, 50)
+            .unwrap()
+            .iter()
+            .all(|r| r.id != 10));
+        assert_eq!(index.live_stats(), index.diagnostics());
+
+        assert!(index.update_vector(1000, angle(0.0)).is_err());
+        assert!(index.update_vector(10, vec![1.0]).is_err());
+        assert!(index.soft_delete(10));
+        assert!(matches!(
+            index.update_vector(10, angle(34.5)),
+            Err(Error::NotFound(_))
+        ));
     }
 
     #[test]
-    fn test_hnsw_serialization() {
+    fn test_hnsw_build_matches_sequential_inserts() {
+        use crate::eval::{ground_truth, mean_recall_at_k};
+        use rand::rngs::StdRng;
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let items: Vec<(u64, Vec<f32>)> = (0..1000u64)
+            .map(|i| (i, (0..16).map(|_| rng.gen_range(-1.0..1.0)).collect()))
+            .collect();
+        let config = HnswConfig {
+            dimension: 16,
+            m: 8,
+            m_max_0: 16,
+            ef_construction: 64,
+            seed: Some(11),
+            ..Default::default()
+        };
+
+        let built = HnswIndex::build(config.clone(), items.clone()).unwrap();
+        let mut sequential = HnswIndex::new(config.clone());
+        for (id, v) in &items {
+            sequential.insert(*id, v.clone()).unwrap();
+        }
+        assert_eq!(built.len(), items.len());
+
+        let queries: Vec<Vec<f32>> = (0..50)
+            .map(|_| (0..16).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect();
+        let results = |index: &HnswIndex| -> Vec<Vec<SearchResult>> {
+            queries
+                .iter()
+                .map(|q| index.search(q, 10, 64).unwrap())
+                .collect()
+        };
+        let exact: Vec<Vec<SearchResult>> = queries
+            .iter()
+            .map(|q| ground_truth(q, &items, 10))
+            .collect();
+        let built_results = results(&built);
+        let built_recall = mean_recall_at_k(&built_results, &exact, 10);
+        let sequential_recall = mean_recall_at_k(&results(&sequential), &exact, 10);
+        assert!(built_recall > 0.9, "build recall {}", built_recall);
+        assert!(
+            built_recall >= sequential_recall - 0.03,
+            "build recall {} vs sequential {}",
+            built_recall,
+            sequential_recall
+        );
+        assert!(mean_recall_at_k(&built_results, &results(&sequential), 10) > 0.9);
+
+        // Seeded builds are reproducible
+        let again = HnswIndex::build(config.clone(), items.clone()).unwrap();
+        assert_eq!(results(&again), built_results);
+
+        assert!(HnswIndex::build(config.clone(), Vec::new())
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            HnswIndex::build(config.clone(), vec![(1, vec![0.0; 3])]),
+            Err(Error::DimensionMismatch { .. })
+        ));
+        assert!(matches!(
+            HnswIndex::build(config, vec![(1, vec![0.0; 16]), (1, vec![1.0; 16])]),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_hnsw_seeded_build_is_reproducible() {
+        let vectors: Vec<(u64, Vec<f32>)> = (0..300u64)
+            .map(|i| (i, (0..8).map(|d| ((i * 8 + d) as f32).sin()).collect()))
+            .collect();
+        let build = |seed| {
+            let mut index = HnswIndex::new(HnswConfig {
+                dimension: 8,
+                m: 4,
+                m_max_0: 8,
+                seed,
+                ..Default::default()
+            });
+            for (id, v) in &vectors {
+                index.insert(*id, v.clone()).unwrap();
+            }
+            index
+        };
+        let neighbors = |index: &HnswIndex| {
+            let mut all: Vec<(u64, Vec<Vec<u64>>)> = index
+                .nodes
+                .values()
+                .map(|n| (n.id, n.neighbors.clone()))
+                .collect();
+            all.sort();
+            all
+        };
+
+        let a = build(Some(42));
+        let b = build(Some(42));
+        assert_eq!(a.max_layer, b.max_layer);
+        assert_eq!(a.entry_point, b.entry_point);
+        assert_eq!(neighbors(&a), neighbors(&b));
+        assert_ne!(neighbors(&a), neighbors(&build(Some(43))));
+
+        // A deserialized index restarts the layer sequence from the seed
+        let mut reloaded = HnswIndex::deserialize(&a.serialize().unwrap()).unwrap();
+        let mut fresh = HnswIndex::new(a.config.clone());
+        let layers = |index: &mut HnswIndex| -> Vec<i32> {
+            (0..20).map(|_| index.generate_random_layer()).collect()
+        };
+        assert_eq!(layers(&mut reloaded), layers(&mut fresh));
+    }
+
+    #[test]
+    fn test_hnsw_merge() {
         let config = HnswConfig {
+            dimension: 2,
+            ..Default::default()
+        };
+        let angle = |deg: f32| vec![deg.to_radians().cos(), deg.to_radians().sin()];
+        // Shard a covers 0..100 degrees, shard b 180..280
+        let mut a = HnswIndex::new(config.clone());
+        let mut b = HnswIndex::new(config.clone());
+        for i in 0..50u64 {
+            a.insert(i, angle(i as f32 * 2.0)).unwrap();
+            b.insert(100 + i, angle(180.0 + i as f32 * 2.0)).unwrap();
+        }
+
+        let clash = || {
+            let mut index = HnswIndex::new(config.clone());
+            index.insert(7, angle(300.0)).unwrap();
+            index
+        };
+        assert!(matches!(a.merge(clash(), false), Err(Error::Index(_))));
+        assert_eq!(a.len(), 50);
+
+        a.merge(b, false).unwrap();
+        assert_eq!(a.len(), 100);
+        assert_eq!(a.search(&angle(10.0), 1, 50).unwrap()[0].id, 5);
+        assert_eq!(a.search(&angle(190.0), 1, 50).unwrap()[0].id, 105);
+        assert_eq!(a.live_stats(), a.diagnostics());
+
+        a.merge(clash(), true).unwrap();
+        assert_eq!(a.len(), 100);
+        assert_eq!(a.search(&angle(300.0), 1, 50).unwrap()[0].id, 7);
+
+        let l2 = HnswIndex::new(HnswConfig {
+            metric: DistanceMetric::L2,
+            ..config
+        });
+        assert!(a.merge(l2, false).is_err());
+        let wide = HnswIndex::new(HnswConfig {
             dimension: 3,
             ..Default::default()
+        });
+        // An empty index of another dimension has nothing to merge
+        a.merge(wide, false).unwrap();
+    }
+
+    #[test]
+    fn test_hnsw_stats() {
+        let mut index = HnswIndex::new(HnswConfig {
+            dimension: 8,
+            m: 4,
+            m_max_0: 8,
+            ..Default::default()
+        });
+        assert!(index.is_empty());
+        assert_eq!(index.stats().entry_point, None);
+
+        for i in 0..50u64 {
+            let v: Vec<f32> = (0..8).map(|d| ((i * 8 + d) as f32).sin()).collect();
+            index.insert(i, v).unwrap();
+        }
+        let stats = index.stats();
+        assert_eq!(index.len(), 50);
+        assert_eq!(stats.num_nodes, 50);
+        assert_eq!(
+            stats.max_layer,
+            index.live_stats().nodes_per_layer.len() - 1
+        );
+        assert!(stats.entry_point.is_some());
+        assert!(stats.avg_degree_layer0 > 1.0 && stats.avg_degree_layer0 <= 8.0);
+        // At least the raw vectors and layer-0 edges
+        let floor = 50 * 8 * 4 + (stats.avg_degree_layer0 * 50.0) as usize * 8;
+        assert!(stats.memory_estimate_bytes > floor);
+
+        index.soft_delete(3);
+        assert_eq!(index.len(), 49);
+        assert_eq!(index.stats().num_nodes, 50);
+    }
+
+    #[test]
+    fn test_hnsw_update_relinks_node() {
+        let config = HnswConfig {
+            dimension: 2,
+            m: 4,
+            m_max_0: 8,
+            ..Default::default()
         };
         let mut index = HnswIndex::new(config);
-        index.insert(1, vec![1.0, 0.0, 0.0]).unwrap();
+        for i in 0..100u64 {
+            let a = i as f32 * 0.06;
+            index.insert(i, vec![a.cos(), a.sin()]).unwrap();
+        }
+        let angle = |deg: f32| vec![deg.to_radians().cos(), deg.to_radians().sin()];
+        let layers = index.nodes[&10].neighbors.len();
 
-        let bytes = index.serialize().unwrap();
-        let loaded = HnswIndex::deserialize(&bytes).unwrap();
+        index.update(10, angle(180.5)).unwrap();
+        assert_eq!(index.nodes.len(), 100);
+        assert_eq!(index.nodes[&10].neighbors.len(), layers);
+        let results = index.search(&angle(180.5), 1, 50).unwrap();
+        assert_eq!(results[0].id, 10);
+        assert!(results[0].score > 0.9999);
+        assert!(index
+            .search(&angle(34.5), 3, 50)
+            .unwrap()
+            .iter()
+            .all(|r| r.id != 10));
+        assert_eq!(index.live_stats(), index.diagnostics());
 
-        let results = loaded.search(&[1.0, 0.0, 0.0], 1, 10).unwrap();
-        assert_eq!(results[0].id, 1);
+        assert!(matches!(
+            index.update(1000, angle(0.0)),
+            Err(Error::NotFound(_))
+        ));
+        assert!(matches!(
+            index.update(10, vec![1.0]),
+            Err(Error::DimensionMismatch { .. })
+        ));
+        index.soft_delete(11);
+        assert!(matches!(
+            index.update(11, angle(0.0)),
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_hnsw_l2_metric() {
+        use crate::vector::brute_force_topk_metric;
+        use rand::{rngs::StdRng, SeedableRng};
+
+        // Varying magnitudes, so cosine and L2 neighbors differ
+        let mut rng = StdRng::seed_from_u64(5);
+        let vectors: Vec<(u64, Vec<f32>)> = (0..500u64)
+            .map(|i| (i, (0..4).map(|_| rng.gen_range(-5.0..5.0)).collect()))
+            .collect();
+
+        let config = HnswConfig {
+            dimension: 4,
+            metric: DistanceMetric::L2,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        for (id, v) in &vectors {
+            index.insert(*id, v.clone()).unwrap();
+        }
+        let index = HnswIndex::deserialize(&index.serialize().unwrap()).unwrap();
+        assert_eq!(index.config.metric, DistanceMetric::L2);
+
+        let mut differs_from_cosine = false;
+        for _ in 0..20 {
+            let query: Vec<f32> = (0..4).map(|_| rng.gen_range(-5.0..5.0)).collect();
+            let expected = brute_force_topk_metric(&query, &vectors, 1, DistanceMetric::L2);
+            let results = index.search(&query, 3, 100).unwrap();
+            assert_eq!(results[0].id, expected[0].id);
+            assert!((results[0].score - expected[0].score).abs() < 1e-4);
+            assert!(results.windows(2).all(|w| w[0].score <= w[1].score));
+
+            let cosine = brute_force_topk_metric(&query, &vectors, 1, DistanceMetric::Cosine);
+            differs_from_cosine |= cosine[0].id != expected[0].id;
+        }
+        assert!(differs_from_cosine);
+    }
+
+    #[test]
+    fn test_hnsw_search_boolean() {
+        let config = HnswConfig {
+            dimension: 2,
+            ..Default::default()
+        };
+        let mut index = HnswIndex::new(config);
+        let angle = |deg: f32| vec![deg.to_radians().cos(), deg.to_radians().sin()];
+        // Every 10 degrees over a quarter circle
+        for i in 0..10u64 {
+            index.insert(i, angle(i as f32 * 10.0)).unwrap();
+        }
+
+        // Each query finds its 4 nearest: A {0,1,2,3}, B {3,4,5,6}
+        let queries = vec![angle(14.0), angle(46.0)];
+        let and = index.search_boolean(&queries, BoolOp::And, 10, 4).unwrap();
+        assert_eq!(and.len(), 1);
+        assert_eq!(and[0].id, 3);
+        let expected = 16f32.to_radians().cos();
+        assert!((and[0].score - expected).abs() < 1e-5);
+
+        let or = index.search_boolean(&queries, BoolOp::Or, 10, 4).unwrap();
+        let ids: HashSet<u64> = or.iter().map(|r| r.id).collect();
+        assert_eq!(ids, (0..=6).collect());
+        for r in &or {
+            let best = queries
+                .iter()
+                .map(|q| cosine_similarity(q, &index.nodes[&r.id].vector))
+                .fold(f32::MIN, f32::max);
+            assert!((r.score - best).abs() < 1e-5);
+        }
+        assert!(or.windows(2).all(|w| w[0].score >= w[1].score));
+
+        assert_eq!(
+            index
+                .search_boolean(&queries, BoolOp::Or, 2, 4)
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(index
+            .search_boolean(&[], BoolOp::And, 5, 4)
+            .unwrap()
+            .is_empty());
     }
 }
@@ -0,0 +1,296 @@
+//! IVF (Inverted File) index implementation
+//!
+//! Vectors are partitioned by k-means into `n_lists` clusters, each with a
+//! posting list of its members. A search ranks the centroids against the
+//! query and scans only the `n_probe` closest lists, trading recall for a
+//! scan of roughly `n_probe / n_lists` of the data.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::Index;
+use crate::vector::{cluster, cosine_similarity, DistanceMetric, SearchResult};
+use crate::{Error, Result};
+
+/// Maximum k-means iterations during training
+const KMEANS_ITERATIONS: usize = 25;
+
+/// Configuration for IVF index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IvfConfig {
+    /// Vector dimension
+    pub dimension: usize,
+    /// Number of clusters (posting lists)
+    pub n_lists: usize,
+    /// Number of closest lists scanned by [`IvfIndex::search_default`]
+    pub n_probe: usize,
+}
+
+impl Default for IvfConfig {
+    fn default() -> Self {
+        Self {
+            dimension: 128,
+            n_lists: 100,
+            n_probe: 8,
+        }
+    }
+}
+
+/// IVF index over cosine similarity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IvfIndex {
+    config: IvfConfig,
+    centroids: Vec<Vec<f32>>,
+    lists: Vec<Vec<(u64, Vec<f32>)>>,
+    /// List holding each ID
+    assignments: HashMap<u64, usize>,
+}
+
+impl IvfIndex {
+    /// Create a new untrained index
+    pub fn new(config: IvfConfig) -> Self {
+        Self {
+            config,
+            centroids: Vec::new(),
+            lists: Vec::new(),
+            assignments: HashMap::new(),
+        }
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &IvfConfig {
+        &self.config
+    }
+
+    /// Check if centroids have been trained
+    pub fn is_trained(&self) -> bool {
+        !self.centroids.is_empty()
+    }
+
+    /// Number of vectors in the index
+    pub fn len(&self) -> usize {
+        self.assignments.len()
+    }
+
+    /// Check if the index is empty
+    pub fn is_empty(&self) -> bool {
+        self.assignments.is_empty()
+    }
+
+    /// Learn `n_lists` centroids from a training sample with k-means
+    ///
+    /// Uses spherical k-means ([`cluster::kmeans`] with cosine assignment),
+    /// which is seeded deterministically. Vectors already in the index are
+    /// reassigned to the new lists.
+    pub fn train(&mut self, vectors: &[Vec<f32>], n_lists: usize) -> Result<()> {
+        if n_lists == 0 || n_lists > vectors.len() {
+            return Err(Error::InvalidConfig(format!(
+                "n_lists must be between 1 and the {} training vectors, got {}",
+                vectors.len(),
+                n_lists
+            )));
+        }
+        for v in vectors {
+            self.check_dimension(v)?;
+        }
+
+        let (centroids, _) =
+            cluster::kmeans(vectors, n_lists, KMEANS_ITERATIONS, DistanceMetric::Cosine);
+
+        let existing: Vec<(u64, Vec<f32>)> = self.lists.drain(..).flatten().collect();
+        self.centroids = centroids;
+        self.lists = vec![Vec::new(); n_lists];
+        self.assignments.clear();
+        self.config.n_lists = n_lists;
+        for (id, vector) in existing {
+            self.insert(id, vector)?;
+        }
+        Ok(())
+    }
+
+    /// Insert a vector into its nearest centroid's list
+    ///
+    /// Replaces any vector stored under the same ID.
+    pub fn insert(&mut self, id: u64, vector: Vec<f32>) -> Result<()> {
+        self.check_dimension(&vector)?;
+        if !self.is_trained() {
+            return Err(Error::Index(
+                "IVF index must be trained before insert".to_string(),
+            ));
+        }
+
+        if let Some(old) = self.assignments.remove(&id) {
+            self.lists[old].retain(|(other, _)| *other != id);
+        }
+        let list = nearest_centroid(&self.centroids, &vector);
+        self.lists[list].push((id, vector));
+        self.assignments.insert(id, list);
+        Ok(())
+    }
+
+    /// Search the `n_probe` closest lists for the K most similar vectors
+    pub fn search(&self, query: &[f32], k: usize, n_probe: usize) -> Result<Vec<SearchResult>> {
+        self.check_dimension(query)?;
+
+        let mut ranked: Vec<(usize, f32)> = self
+            .centroids
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (i, cosine_similarity(query, c)))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let mut results: Vec<SearchResult> = ranked
+            .iter()
+            .take(n_probe)
+            .flat_map(|&(list, _)| &self.lists[list])
+            .map(|(id, v)| SearchResult::new(*id, cosine_similarity(query, v)))
+            .collect();
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        results.truncate(k);
+        Ok(results)
+    }
+
+    /// Search with the configured `n_probe`
+    pub fn search_default(&self, query: &[f32], k: usize) -> Result<Vec<SearchResult>> {
+        self.search(query, k, self.config.n_probe)
+    }
+
+    /// Serialize the index to bytes
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| Error::Bincode(e.to_string()))
+    }
+
+    /// Deserialize the index from bytes
+    pub fn deserialize(bytes: &[u8]) -> Result<Self> {
+        bincode::deserialize(bytes).map_err(|e| Error::Bincode(e.to_string()))
+    }
+
+    fn check_dimension(&self, vector: &[f32]) -> Result<()> {
+        if vector.len() != self.config.dimension {
+            return Err(Error::DimensionMismatch {
+                expected: self.config.dimension,
+                actual: vector.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn nearest_centroid(centroids: &[Vec<f32>], vector: &[f32]) -> usize {
+    centroids
+        .iter()
+        .map(|c| cosine_similarity(vector, c))
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(0, |(i, _)| i)
+}
+
+impl Index for IvfIndex {
+    fn insert(&mut self, id: u64, vector: Vec<f32>) -> Result<()> {
+        IvfIndex::insert(self, id, vector)
+    }
+
+    /// Searches the configured `n_probe` lists
+    fn search(&self, query: &[f32], k: usize) -> Result<Vec<SearchResult>> {
+        self.search_default(query, k)
+    }
+
+    fn len(&self) -> usize {
+        IvfIndex::len(self)
+    }
+
+    fn dimension(&self) -> usize {
+        self.config.dimension
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::brute_force_topk;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_ivf_recall() {
+        let dim = 16;
+        let mut rng = StdRng::seed_from_u64(11);
+        let centers: Vec<Vec<f32>> = (0..50)
+            .map(|_| (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect();
+        let data: Vec<(u64, Vec<f32>)> = (0..5000u64)
+            .map(|i| {
+                let center = &centers[i as usize % centers.len()];
+                let v = center
+                    .iter()
+                    .map(|c| c + rng.gen_range(-0.3..0.3))
+                    .collect();
+                (i, v)
+            })
+            .collect();
+
+        let mut index = IvfIndex::new(IvfConfig {
+            dimension: dim,
+            ..Default::default()
+        });
+        let sample: Vec<Vec<f32>> = data.iter().step_by(5).map(|(_, v)| v.clone()).collect();
+        index.train(&sample, 64).unwrap();
+        for (id, v) in &data {
+            index.insert(*id, v.clone()).unwrap();
+        }
+        assert_eq!(index.len(), 5000);
+
+        let mut hits = 0;
+        for (_, query) in data.iter().step_by(100) {
+            let truth: HashSet<u64> = brute_force_topk(query, &data, 10)
+                .into_iter()
+                .map(|r| r.id)
+                .collect();
+            hits += index
+                .search(query, 10, 8)
+                .unwrap()
+                .iter()
+                .filter(|r| truth.contains(&r.id))
+                .count();
+        }
+        let recall = hits as f32 / 500.0;
+        assert!(recall > 0.8, "recall@10 {}", recall);
+    }
+
+    #[test]
+    fn test_ivf_serialize_roundtrip() {
+        let mut index = IvfIndex::new(IvfConfig {
+            dimension: 2,
+            n_lists: 2,
+            n_probe: 1,
+        });
+        assert!(index.insert(1, vec![1.0, 0.0]).is_err());
+
+        index
+            .train(
+                &[
+                    vec![1.0, 0.0],
+                    vec![0.9, 0.1],
+                    vec![0.0, 1.0],
+                    vec![0.1, 0.9],
+                ],
+                2,
+            )
+            .unwrap();
+        index.insert(1, vec![1.0, 0.0]).unwrap();
+        index.insert(2, vec![0.0, 1.0]).unwrap();
+        index.insert(2, vec![0.2, 1.0]).unwrap();
+        assert_eq!(index.len(), 2);
+
+        let loaded = IvfIndex::deserialize(&index.serialize().unwrap()).unwrap();
+        let results = loaded.search(&[0.0, 1.0], 2, 1).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, 2);
+        assert_eq!(loaded.search(&[0.0, 1.0], 2, 2).unwrap().len(), 2);
+        assert_eq!(loaded.search_default(&[0.0, 1.0], 2).unwrap().len(), 1);
+
+        assert!(index.train(&[vec![1.0, 0.0]], 2).is_err());
+        assert!(index.search(&[1.0], 1, 1).is_err());
+    }
+}
@@ -1,9 +1,40 @@
 pub mod hnsw;
+pub mod ivf;
+pub mod query_log;
 
+use crate::vector::{dot_product, SearchResult};
 use crate::{Error, Result};
-pub use hnsw::{HnswConfig, HnswIndex};
+pub use hnsw::{
+    BoolOp, Compression, ConcurrentHnsw, GraphStats, HnswConfig, HnswIndex, HnswStats,
+    ReadOnlyHnsw, SearchStats,
+};
+pub use ivf::{IvfConfig, IvfIndex};
+pub use query_log::{QueryLogConfig, QueryLogHandle, QueryLogger, QueryRecord};
 use std::collections::HashMap;
 
+/// Operations shared by every index kind
+///
+/// Lets code be generic over the index type, or hold one as `&dyn Index`.
+/// Each implementation searches with its own configured defaults.
+pub trait Index {
+    /// Insert a vector under `id`
+    fn insert(&mut self, id: u64, vector: Vec<f32>) -> Result<()>;
+
+    /// Search for the top K closest vectors
+    fn search(&self, query: &[f32], k: usize) -> Result<Vec<SearchResult>>;
+
+    /// Number of searchable vectors
+    fn len(&self) -> usize;
+
+    /// Check if the index holds no searchable vectors
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Vector dimension
+    fn dimension(&self) -> usize;
+}
+
 /// Index configuration
 #[derive(Debug, Clone)]
 pub struct IndexConfig {
@@ -15,6 +46,8 @@ pub struct IndexConfig {
     pub ef_construction: usize,
     /// HNSW ef_search parameter
     pub ef_search: usize,
+    /// Precompute each vector's norm at insert so search only computes dot products
+    pub cache_norms: bool,
 }
 
 impl Default for IndexConfig {
@@ -24,6 +57,7 @@ impl Default for IndexConfig {
             m: 16,
             ef_construction: 200,
             ef_search: 50,
+            cache_norms: false,
         }
     }
 }
@@ -32,6 +66,8 @@ impl Default for IndexConfig {
 pub struct VectorIndex {
     config: IndexConfig,
     vectors: HashMap<u64, Vec<f32>>,
+    /// Cached vector norms, populated only when `cache_norms` is set
+    norms: HashMap<u64, f32>,
     next_id: u64,
 }
 
@@ -41,6 +77,7 @@ impl VectorIndex {
         Self {
             config,
             vectors: HashMap::new(),
+            norms: HashMap::new(),
             next_id: 0,
         }
     }
@@ -64,6 +101,7 @@ impl VectorIndex {
 
         let id = self.next_id;
         self.next_id += 1;
+        self.cache_norm(id, &vector);
         self.vectors.insert(id, vector);
         Ok(id)
     }
@@ -77,6 +115,7 @@ impl VectorIndex {
             });
         }
 
+        self.cache_norm(id, &vector);
         self.vectors.insert(id, vector);
         if id >= self.next_id {
             self.next_id = id + 1;
@@ -84,6 +123,22 @@ impl VectorIndex {
         Ok(())
     }
 
+    /// Insert a vector with a specific ID, failing if the ID is taken
+    ///
+    /// Unlike [`insert_with_id`](Self::insert_with_id), which overwrites, an
+    /// existing vector is left untouched and `Error::InvalidConfig` returned.
+    pub fn try_insert_with_id(&mut self, id: u64, vector: Vec<f32>) -> Result<()> {
+        if self.contains(id) {
+            return Err(Error::InvalidConfig(format!("duplicate id {}", id)));
+        }
+        self.insert_with_id(id, vector)
+    }
+
+    /// Check if a vector with this ID is in the index
+    pub fn contains(&self, id: u64) -> bool {
+        self.vectors.contains_key(&id)
+    }
+
     /// Get a vector by ID
     pub fn get(&self, id: u64) -> Option<&Vec<f32>> {
         self.vectors.get(&id)
@@ -91,11 +146,18 @@ impl VectorIndex {
 
     /// Delete a vector by ID
     pub fn delete(&mut self, id: u64) -> bool {
+        self.norms.remove(&id);
         self.vectors.remove(&id).is_some()
     }
 
+    fn cache_norm(&mut self, id: u64, vector: &[f32]) {
+        if self.config.cache_norms {
+            self.norms.insert(id, dot_product(vector, vector).sqrt());
+        }
+    }
+
     /// Search for the top K most similar vectors
-    pub fn search(&self, query: &[f32], k: usize) -> Result<Vec<crate::vector::SearchResult>> {
+    pub fn search(&self, query: &[f32], k: usize) -> Result<Vec<SearchResult>> {
         if query.len() != self.config.dimension {
             return Err(Error::DimensionMismatch {
                 expected: self.config.dimension,
@@ -103,6 +165,10 @@ impl VectorIndex {
             });
         }
 
+        if self.config.cache_norms {
+            return Ok(self.search_cached_norms(query, k));
+        }
+
         let vectors: Vec<(u64, Vec<f32>)> = self
             .vectors
             .iter()
@@ -112,6 +178,23 @@ impl VectorIndex {
         Ok(crate::vector::brute_force_topk(query, &vectors, k))
     }
 
+    /// Brute-force cosine search using the cached vector norms
+    fn search_cached_norms(&self, query: &[f32], k: usize) -> Vec<SearchResult> {
+        let query_norm = dot_product(query, query).sqrt();
+
+        let scores = self.vectors.iter().map(|(id, v)| {
+            let norm = self.norms[id];
+            let score = if query_norm == 0.0 || norm == 0.0 {
+                0.0
+            } else {
+                dot_product(query, v) / (query_norm * norm)
+            };
+            (*id, score)
+        });
+
+        crate::vector::topk_scored(scores, k, true)
+    }
+
     /// Get the number of vectors in the index
     pub fn len(&self) -> usize {
         self.vectors.len()
@@ -127,17 +210,99 @@ impl VectorIndex {
         self.config.dimension
     }
 
+    /// Reassign live vectors to contiguous IDs starting from 0
+    ///
+    /// IDs keep their relative order. Returns the old-to-new mapping so callers
+    /// can update external references.
+    pub fn compact_ids(&mut self) -> HashMap<u64, u64> {
+        let mut ids: Vec<u64> = self.vectors.keys().copied().collect();
+        ids.sort_unstable();
+
+        let mut vectors = HashMap::with_capacity(ids.len());
+        let mut norms = HashMap::with_capacity(ids.len());
+        let mut mapping = HashMap::with_capacity(ids.len());
+        for (new_id, old_id) in ids.into_iter().enumerate() {
+            let new_id = new_id as u64;
+            vectors.insert(new_id, self.vectors.remove(&old_id).unwrap());
+            if let Some(norm) = self.norms.remove(&old_id) {
+                norms.insert(new_id, norm);
+            }
+            mapping.insert(old_id, new_id);
+        }
+
+        self.vectors = vectors;
+        self.norms = norms;
+        self.next_id = self.vectors.len() as u64;
+        mapping
+    }
+
     /// Clear all vectors from the index
     pub fn clear(&mut self) {
         self.vectors.clear();
+        self.norms.clear();
         self.next_id = 0;
     }
 }
 
+impl Index for VectorIndex {
+    fn insert(&mut self, id: u64, vector: Vec<f32>) -> Result<()> {
+        self.insert_with_id(id, vector)
+    }
+
+    fn search(&self, query: &[f32], k: usize) -> Result<Vec<SearchResult>> {
+        VectorIndex::search(self, query, k)
+    }
+
+    fn len(&self) -> usize {
+        VectorIndex::len(self)
+    }
+
+    fn dimension(&self) -> usize {
+        VectorIndex::dimension(self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_index_trait_objects() {
+        let mut ivf = IvfIndex::new(IvfConfig {
+            dimension: 2,
+            n_lists: 2,
+            n_probe: 2,
+        });
+        ivf.train(&[vec![1.0, 0.0], vec![0.0, 1.0]], 2).unwrap();
+        let mut indexes: Vec<Box<dyn Index>> = vec![
+            Box::new(VectorIndex::with_dimension(2)),
+            Box::new(HnswIndex::new(HnswConfig {
+                dimension: 2,
+                ..Default::default()
+            })),
+            Box::new(ivf),
+        ];
+
+        for index in &mut indexes {
+            let index: &mut dyn Index = index.as_mut();
+            assert!(index.is_empty());
+            for i in 0..20u64 {
+                let a = i as f32 * 0.08;
+                index.insert(i, vec![a.cos(), a.sin()]).unwrap();
+            }
+            assert!(index.insert(99, vec![1.0]).is_err());
+        }
+
+        for index in &indexes {
+            let index: &dyn Index = index.as_ref();
+            assert_eq!(index.len(), 20);
+            assert_eq!(index.dimension(), 2);
+            let results = index.search(&[1.0, 0.0], 3).unwrap();
+            let ids: Vec<u64> = results.iter().map(|r| r.id).collect();
+            assert_eq!(ids, vec![0, 1, 2]);
+        }
+    }
+
     #[test]
     fn test_index_insert_and_get() {
         let mut index = VectorIndex::with_dimension(3);
@@ -149,6 +314,25 @@ mod tests {
         assert_eq!(vec, &vec![1.0, 2.0, 3.0]);
     }
 
+    #[test]
+    fn test_try_insert_with_id_rejects_duplicates() {
+        let mut index = VectorIndex::with_dimension(2);
+        index.try_insert_with_id(7, vec![1.0, 0.0]).unwrap();
+        assert!(index.contains(7));
+        assert!(!index.contains(8));
+
+        assert!(matches!(
+            index.try_insert_with_id(7, vec![0.0, 1.0]),
+            Err(Error::InvalidConfig(_))
+        ));
+        assert_eq!(index.get(7).unwrap(), &vec![1.0, 0.0]);
+
+        // insert_with_id keeps upsert semantics
+        index.insert_with_id(7, vec![0.0, 1.0]).unwrap();
+        assert_eq!(index.get(7).unwrap(), &vec![0.0, 1.0]);
+        assert_eq!(index.len(), 1);
+    }
+
     #[test]
     fn test_index_dimension_mismatch() {
         let mut index = VectorIndex::with_dimension(3);
@@ -171,6 +355,25 @@ mod tests {
         assert_eq!(results[0].id, 0); // Most similar
     }
 
+    #[test]
+    fn test_index_search_with_nan_vector() {
+        for cache_norms in [false, true] {
+            let mut index = VectorIndex::new(IndexConfig {
+                dimension: 2,
+                cache_norms,
+                ..Default::default()
+            });
+            let bad = index.insert(vec![f32::NAN, 1.0]).unwrap();
+            index.insert(vec![1.0, 0.0]).unwrap();
+            index.insert(vec![0.0, 1.0]).unwrap();
+
+            let results = index.search(&[1.0, 0.0], 3).unwrap();
+            assert_eq!(results.len(), 3);
+            assert_eq!(results[2].id, bad);
+            assert!(results[2].score.is_nan());
+        }
+    }
+
     #[test]
     fn test_index_delete() {
         let mut index = VectorIndex::with_dimension(3);
@@ -182,6 +385,58 @@ mod tests {
         assert!(index.get(id).is_none());
     }
 
+    #[test]
+    fn test_index_compact_ids() {
+        let mut index = VectorIndex::with_dimension(2);
+        for i in 0..6 {
+            index.insert(vec![i as f32, 1.0]).unwrap();
+        }
+        index.delete(0);
+        index.delete(2);
+        index.delete(3);
+
+        let mapping = index.compact_ids();
+        assert_eq!(mapping.len(), 3);
+        assert_eq!(mapping[&1], 0);
+        assert_eq!(mapping[&4], 1);
+        assert_eq!(mapping[&5], 2);
+
+        assert_eq!(index.get(0).unwrap(), &vec![1.0, 1.0]);
+        assert_eq!(index.get(1).unwrap(), &vec![4.0, 1.0]);
+        assert_eq!(index.get(2).unwrap(), &vec![5.0, 1.0]);
+        assert!(index.get(3).is_none());
+
+        assert_eq!(index.insert(vec![9.0, 1.0]).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_index_cached_norms_match() {
+        let mut cached = VectorIndex::new(IndexConfig {
+            dimension: 4,
+            cache_norms: true,
+            ..Default::default()
+        });
+        let mut plain = VectorIndex::with_dimension(4);
+
+        for i in 0..50 {
+            let v: Vec<f32> = (0..4).map(|j| ((i * 31 + j * 17) as f32).sin()).collect();
+            cached.insert(v.clone()).unwrap();
+            plain.insert(v).unwrap();
+        }
+        for id in [3, 17, 42] {
+            cached.delete(id);
+            plain.delete(id);
+        }
+        cached.compact_ids();
+        plain.compact_ids();
+
+        let query = [0.5, -1.0, 2.0, 0.25];
+        assert_eq!(
+            cached.search(&query, 10).unwrap(),
+            plain.search(&query, 10).unwrap()
+        );
+    }
+
     #[test]
     fn test_index_clear() {
         let mut index = VectorIndex::with_dimension(3);
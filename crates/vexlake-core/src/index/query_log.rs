@@ -0,0 +1,270 @@
+//! Query logging for workload capture and replay
+//!
+//! Searches on an index with a [`QueryLogHandle`] attached are recorded to
+//! rolling Parquet files. Records go through a bounded channel to a background
+//! task, so the search path never blocks on storage; when the channel is full
+//! the record is dropped and counted instead. File names carry the logger's
+//! start time and a random suffix, so a restarted logger never overwrites the
+//! files of an earlier run.
+
+use arrow::array::{ArrayRef, ListArray, RecordBatch, UInt64Array};
+use arrow::datatypes::Float32Type;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+use crate::storage::{ParquetWriter, StorageClient};
+use crate::{Error, Result};
+
+/// A single logged search
+#[derive(Debug, Clone)]
+pub struct QueryRecord {
+    /// Query vector as passed to search
+    pub query: Vec<f32>,
+    /// Requested number of results
+    pub k: usize,
+    /// Requested search width
+    pub ef: usize,
+    /// Wall-clock time of the search in milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    /// Search latency
+    pub latency: Duration,
+}
+
+/// Query logger configuration
+#[derive(Debug, Clone)]
+pub struct QueryLogConfig {
+    /// Directory prefix for the log files
+    pub prefix: String,
+    /// Rows per Parquet file before rolling over to a new one
+    pub rows_per_file: usize,
+    /// Capacity of the channel between searches and the writer task
+    pub channel_capacity: usize,
+}
+
+impl Default for QueryLogConfig {
+    fn default() -> Self {
+        Self {
+            prefix: "query_log".to_string(),
+            rows_per_file: 10_000,
+            channel_capacity: 1024,
+        }
+    }
+}
+
+/// Cheap, cloneable handle used by an index to submit records
+#[derive(Debug, Clone)]
+pub struct QueryLogHandle {
+    tx: mpsc::Sender<QueryRecord>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl QueryLogHandle {
+    /// Submit a record without blocking, dropping it if the channel is full
+    pub fn record(&self, record: QueryRecord) {
+        if self.tx.try_send(record).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Number of records dropped because the writer fell behind or a file
+    /// failed to write
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Background writer for query logs
+pub struct QueryLogger {
+    handle: QueryLogHandle,
+    stop_tx: oneshot::Sender<()>,
+    task: JoinHandle<Vec<String>>,
+}
+
+impl QueryLogger {
+    /// Spawn the writer task on the current tokio runtime
+    pub fn start(client: Arc<StorageClient>, config: QueryLogConfig) -> Self {
+        let (tx, mut rx) = mpsc::channel(config.channel_capacity.max(1));
+        let (stop_tx, mut stop_rx) = oneshot::channel();
+        let dropped = Arc::new(AtomicU64::new(0));
+        let mut files = LogFiles {
+            client,
+            prefix: format!(
+                "{}/queries-{:013}-{:08x}",
+                config.prefix,
+                now_ms(),
+                rand::random::<u32>()
+            ),
+            seq: 0,
+            written: Vec::new(),
+            dropped: Arc::clone(&dropped),
+        };
+
+        let task = tokio::spawn(async move {
+            let rows_per_file = config.rows_per_file.max(1);
+            let mut buffer = Vec::with_capacity(rows_per_file);
+
+            loop {
+                tokio::select! {
+                    _ = &mut stop_rx => break,
+                    record = rx.recv() => match record {
+                        Some(record) => buffer.push(record),
+                        None => break,
+                    },
+                }
+                if buffer.len() >= rows_per_file {
+                    files.flush(&mut buffer).await;
+                }
+            }
+
+            // Drain whatever was submitted before the stop
+            while let Ok(record) = rx.try_recv() {
+                buffer.push(record);
+                if buffer.len() >= rows_per_file {
+                    files.flush(&mut buffer).await;
+                }
+            }
+            if !buffer.is_empty() {
+                files.flush(&mut buffer).await;
+            }
+            files.written
+        });
+
+        Self {
+            handle: QueryLogHandle { tx, dropped },
+            stop_tx,
+            task,
+        }
+    }
+
+    /// Get a handle to attach to an index
+    pub fn handle(&self) -> QueryLogHandle {
+        self.handle.clone()
+    }
+
+    /// Flush pending records and stop the writer
+    ///
+    /// Returns the paths of all files written, in order. A file that failed
+    /// to write is logged and skipped, and its records count as dropped.
+    pub async fn stop(self) -> Result<Vec<String>> {
+        let _ = self.stop_tx.send(());
+        self.task
+            .await
+            .map_err(|e| Error::Index(format!("query log task failed: {}", e)))
+    }
+}
+
+/// Files written by one logger run
+struct LogFiles {
+    client: Arc<StorageClient>,
+    /// Path prefix unique to this run
+    prefix: String,
+    seq: usize,
+    written: Vec<String>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl LogFiles {
+    /// Write the records as the next file and clear them
+    ///
+    /// A failed write is logged and its records are counted as dropped; the
+    /// writer keeps going with the next file.
+    async fn flush(&mut self, records: &mut Vec<QueryRecord>) {
+        let path = format!("{}-{:06}.parquet", self.prefix, self.seq);
+        self.seq += 1;
+        match write_file(&self.client, &path, records).await {
+            Ok(()) => self.written.push(path),
+            Err(e) => {
+                tracing::warn!("query log write failed for {}: {}", path, e);
+                self.dropped
+                    .fetch_add(records.len() as u64, Ordering::Relaxed);
+            }
+        }
+        records.clear();
+    }
+}
+
+/// Current wall-clock time in milliseconds since the Unix epoch
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+async fn write_file(client: &StorageClient, path: &str, records: &[QueryRecord]) -> Result<()> {
+    let column = |f: fn(&QueryRecord) -> u64| -> ArrayRef {
+        Arc::new(UInt64Array::from_iter_values(records.iter().map(f)))
+    };
+    let queries = ListArray::from_iter_primitive::<Float32Type, _, _>(
+        records
+            .iter()
+            .map(|r| Some(r.query.iter().map(|&x| Some(x)).collect::<Vec<_>>())),
+    );
+
+    let batch = RecordBatch::try_from_iter(vec![
+        ("timestamp_ms", column(|r| r.timestamp_ms)),
+        ("k", column(|r| r.k as u64)),
+        ("ef", column(|r| r.ef as u64)),
+        ("latency_us", column(|r| r.latency.as_micros() as u64)),
+        ("query", Arc::new(queries) as ArrayRef),
+    ])
+    .map_err(Error::Arrow)?;
+
+    ParquetWriter::new(client, 0)
+        .write_batch(path, &batch)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::{HnswConfig, HnswIndex};
+    use crate::storage::ParquetReader;
+
+    #[tokio::test]
+    async fn test_query_log_records_searches() {
+        let client = Arc::new(StorageClient::memory().unwrap());
+        let logger = QueryLogger::start(
+            Arc::clone(&client),
+            QueryLogConfig {
+                rows_per_file: 3,
+                ..Default::default()
+            },
+        );
+
+        let mut index = HnswIndex::new(HnswConfig {
+            dimension: 2,
+            ..Default::default()
+        });
+        index.insert(1, vec![1.0, 0.0]).unwrap();
+        index.insert(2, vec![0.0, 1.0]).unwrap();
+        index.set_query_log(Some(logger.handle()));
+
+        for i in 0..5 {
+            index.search(&[1.0, i as f32], 1, 10).unwrap();
+        }
+
+        let files = logger.stop().await.unwrap();
+        assert_eq!(files.len(), 2);
+
+        // A restarted logger writes new files next to the earlier ones
+        let logger = QueryLogger::start(Arc::clone(&client), QueryLogConfig::default());
+        index.set_query_log(Some(logger.handle()));
+        index.search(&[1.0, 0.0], 1, 10).unwrap();
+        let restarted = logger.stop().await.unwrap();
+        assert_eq!(restarted.len(), 1);
+        assert!(!files.contains(&restarted[0]));
+
+        let reader = ParquetReader::new(&client);
+        let mut rows = 0;
+        for path in files.iter().chain(&restarted) {
+            for batch in reader.read_all(path).await.unwrap() {
+                rows += batch.num_rows();
+            }
+        }
+        assert_eq!(rows, 6);
+    }
+}
@@ -7,6 +7,10 @@
 //! - Parquet read/write via DataFusion
 //! - FFI exports for Go integration
 //!
+//! With the `tracing` feature, HNSW search and insert and storage reads and
+//! writes emit [`tracing`](https://docs.rs/tracing) spans and events;
+//! without it they compile to nothing.
+//!
 //! # Architecture
 //!
 //! VexLake follows the "Sandwich Architecture":
@@ -15,8 +19,10 @@
 //! - Storage: SeaweedFS via S3 API
 
 pub mod error;
+pub mod eval;
 pub mod ffi;
 pub mod index;
+pub mod metrics;
 pub mod storage;
 pub mod vector;
 
@@ -26,6 +32,9 @@ pub use error::{Error, Result};
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// Health check function
+///
+/// Only reports that the library is loaded; use
+/// [`storage::storage_health`] to check the storage backend.
 pub fn health_check() -> bool {
     true
 }
@@ -43,4 +52,82 @@ mod tests {
     fn test_health_check() {
         assert!(health_check());
     }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn test_tracing_spans() {
+        use std::fmt::Write;
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing_subscriber::layer::{Context, SubscriberExt};
+
+        /// Collects span and event names with their fields as strings
+        struct Capture(Arc<Mutex<Vec<String>>>);
+        struct Fields(String);
+        impl Visit for Fields {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                let _ = write!(self.0, " {}={:?}", field.name(), value);
+            }
+        }
+        impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for Capture {
+            fn on_new_span(
+                &self,
+                attrs: &tracing::span::Attributes<'_>,
+                _: &tracing::span::Id,
+                _: Context<'_, S>,
+            ) {
+                let mut fields = Fields(attrs.metadata().name().to_string());
+                attrs.record(&mut fields);
+                self.0.lock().unwrap().push(fields.0);
+            }
+            fn on_record(
+                &self,
+                _: &tracing::span::Id,
+                values: &tracing::span::Record<'_>,
+                _: Context<'_, S>,
+            ) {
+                let mut fields = Fields("record".to_string());
+                values.record(&mut fields);
+                self.0.lock().unwrap().push(fields.0);
+            }
+            fn on_event(&self, event: &tracing::Event<'_>, _: Context<'_, S>) {
+                let mut fields = Fields(event.metadata().level().to_string());
+                event.record(&mut fields);
+                self.0.lock().unwrap().push(fields.0);
+            }
+        }
+
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = tracing_subscriber::registry().with(Capture(captured.clone()));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let mut index = index::HnswIndex::new(index::HnswConfig {
+            dimension: 2,
+            ..Default::default()
+        });
+        for i in 0..20u64 {
+            index.insert(i, vec![1.0, i as f32]).unwrap();
+        }
+        index.search(&[1.0, 3.0], 3, 10).unwrap();
+
+        let client = storage::StorageClient::memory().unwrap();
+        client.write("t/a.bin", vec![0; 42]).await.unwrap();
+        client.read("t/a.bin").await.unwrap();
+
+        let lines = captured.lock().unwrap().join("\n");
+        assert!(lines.contains("hnsw_insert id=19"), "{}", lines);
+        assert!(lines.contains("hnsw_search k=3 ef=10"), "{}", lines);
+        assert!(lines.contains("record visited="), "{}", lines);
+        assert!(
+            lines.contains("storage_write path=\"t/a.bin\" bytes=42"),
+            "{}",
+            lines
+        );
+        assert!(lines.contains("storage_read path=\"t/a.bin\""), "{}", lines);
+        assert!(
+            lines.contains("op=\"read\" path=\"t/a.bin\" bytes=42"),
+            "{}",
+            lines
+        );
+    }
 }
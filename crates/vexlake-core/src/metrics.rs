@@ -0,0 +1,284 @@
+//! Operation latency metrics
+//!
+//! Latencies are recorded into log-bucketed histograms in the style of HDR
+//! histograms: each power of two is split into 8 linear sub-buckets, bounding
+//! the relative error of a reported percentile to 12.5%. Recording is a single
+//! relaxed atomic increment, so it is cheap enough for the search hot path.
+//!
+//! Storage requests are additionally counted (total, in flight and failures by
+//! kind) to tell whether the object store is the bottleneck.
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Linear sub-buckets per power of two, as a power of two
+const SUB_BITS: u32 = 3;
+const SUB_BUCKETS: usize = 1 << SUB_BITS;
+/// Values below `SUB_BUCKETS` get exact buckets; above, one group per exponent
+const BUCKETS: usize = SUB_BUCKETS + (64 - SUB_BITS as usize) * SUB_BUCKETS;
+
+static METRICS: Lazy<Metrics> = Lazy::new(Metrics::default);
+
+/// Get the process-wide metrics
+pub fn global() -> &'static Metrics {
+    &METRICS
+}
+
+/// Lock-free latency histogram with microsecond resolution
+pub struct LatencyHistogram {
+    buckets: Box<[AtomicU64]>,
+    count: AtomicU64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+            count: AtomicU64::new(0),
+        }
+    }
+}
+
+impl LatencyHistogram {
+    /// Record one latency sample
+    pub fn record(&self, latency: Duration) {
+        let micros = latency.as_micros().min(u64::MAX as u128) as u64;
+        self.buckets[bucket_index(micros)].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Number of recorded samples
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Latency at quantile `q` (0.0-1.0) in microseconds, or 0 if empty
+    ///
+    /// Reports the midpoint of the bucket holding the sample of that rank.
+    pub fn percentile(&self, q: f64) -> u64 {
+        let total = self.count();
+        if total == 0 {
+            return 0;
+        }
+
+        let rank = ((q.clamp(0.0, 1.0) * total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, bucket) in self.buckets.iter().enumerate() {
+            seen += bucket.load(Ordering::Relaxed);
+            if seen >= rank {
+                return bucket_midpoint(index);
+            }
+        }
+        bucket_midpoint(BUCKETS - 1)
+    }
+
+    /// Count and p50/p90/p99 in microseconds
+    pub fn summary(&self) -> LatencySummary {
+        LatencySummary {
+            count: self.count(),
+            p50_us: self.percentile(0.50),
+            p90_us: self.percentile(0.90),
+            p99_us: self.percentile(0.99),
+        }
+    }
+}
+
+fn bucket_index(value: u64) -> usize {
+    if value < SUB_BUCKETS as u64 {
+        return value as usize;
+    }
+    let exponent = 63 - value.leading_zeros();
+    let shift = exponent - SUB_BITS;
+    let sub = (value >> shift) as usize - SUB_BUCKETS;
+    SUB_BUCKETS + shift as usize * SUB_BUCKETS + sub
+}
+
+fn bucket_midpoint(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+    let shift = ((index - SUB_BUCKETS) / SUB_BUCKETS) as u32;
+    let sub = ((index - SUB_BUCKETS) % SUB_BUCKETS) as u64;
+    let lower = (SUB_BUCKETS as u64 + sub) << shift;
+    lower + (1u64 << shift) / 2
+}
+
+/// Percentile summary of a histogram
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LatencySummary {
+    /// Number of samples
+    pub count: u64,
+    /// Median latency in microseconds
+    pub p50_us: u64,
+    /// 90th percentile latency in microseconds
+    pub p90_us: u64,
+    /// 99th percentile latency in microseconds
+    pub p99_us: u64,
+}
+
+/// Storage request counters
+#[derive(Default)]
+pub struct StorageRequestMetrics {
+    in_flight: AtomicU64,
+    total: AtomicU64,
+    not_found: AtomicU64,
+    permission_denied: AtomicU64,
+    rate_limited: AtomicU64,
+    other_errors: AtomicU64,
+}
+
+/// Marks a storage request as in flight until dropped
+pub struct InFlightGuard<'a> {
+    metrics: &'a StorageRequestMetrics,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.metrics.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl StorageRequestMetrics {
+    /// Count a new request, in flight until the guard is dropped
+    pub fn start(&self) -> InFlightGuard<'_> {
+        self.total.fetch_add(1, Ordering::Relaxed);
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard { metrics: self }
+    }
+
+    /// Count a failed request by error kind
+    pub fn record_error(&self, kind: opendal::ErrorKind) {
+        let counter = match kind {
+            opendal::ErrorKind::NotFound => &self.not_found,
+            opendal::ErrorKind::PermissionDenied => &self.permission_denied,
+            opendal::ErrorKind::RateLimited => &self.rate_limited,
+            _ => &self.other_errors,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Current counter values
+    pub fn summary(&self) -> StorageRequestSummary {
+        let load = |c: &AtomicU64| c.load(Ordering::Relaxed);
+        StorageRequestSummary {
+            in_flight: load(&self.in_flight),
+            total: load(&self.total),
+            errors: StorageErrorCounts {
+                not_found: load(&self.not_found),
+                permission_denied: load(&self.permission_denied),
+                rate_limited: load(&self.rate_limited),
+                other: load(&self.other_errors),
+            },
+        }
+    }
+}
+
+/// Snapshot of [`StorageRequestMetrics`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StorageRequestSummary {
+    /// Requests currently running
+    pub in_flight: u64,
+    /// Requests started since process start
+    pub total: u64,
+    /// Failed requests by error kind
+    pub errors: StorageErrorCounts,
+}
+
+/// Failed storage requests by error kind
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StorageErrorCounts {
+    /// Object did not exist
+    pub not_found: u64,
+    /// Credentials rejected
+    pub permission_denied: u64,
+    /// Throttled by the backend
+    pub rate_limited: u64,
+    /// Any other failure
+    pub other: u64,
+}
+
+/// Latency histograms per operation
+#[derive(Default)]
+pub struct Metrics {
+    /// Index search
+    pub search: LatencyHistogram,
+    /// Index insert
+    pub insert: LatencyHistogram,
+    /// Storage object read
+    pub storage_read: LatencyHistogram,
+    /// Storage object write
+    pub storage_write: LatencyHistogram,
+    /// Storage request counters
+    pub storage_requests: StorageRequestMetrics,
+}
+
+/// Serializable snapshot of [`Metrics`]
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsSnapshot {
+    /// Index search
+    pub search: LatencySummary,
+    /// Index insert
+    pub insert: LatencySummary,
+    /// Storage object read
+    pub storage_read: LatencySummary,
+    /// Storage object write
+    pub storage_write: LatencySummary,
+    /// Storage request counters
+    pub storage_requests: StorageRequestSummary,
+}
+
+impl Metrics {
+    /// Take a percentile snapshot of every histogram
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            search: self.search.summary(),
+            insert: self.insert.summary(),
+            storage_read: self.storage_read.summary(),
+            storage_write: self.storage_write.summary(),
+            storage_requests: self.storage_requests.summary(),
+        }
+    }
+
+    /// Snapshot encoded as JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.snapshot()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_percentiles() {
+        let hist = LatencyHistogram::default();
+        assert_eq!(hist.percentile(0.5), 0);
+
+        // 1..=100 ms, one sample each
+        for ms in 1..=100 {
+            hist.record(Duration::from_millis(ms));
+        }
+        let summary = hist.summary();
+        assert_eq!(summary.count, 100);
+
+        let within = |actual: u64, expected: u64| {
+            (actual as f64 - expected as f64).abs() <= expected as f64 * 0.125
+        };
+        assert!(within(summary.p50_us, 50_000), "{:?}", summary);
+        assert!(within(summary.p90_us, 90_000), "{:?}", summary);
+        assert!(within(summary.p99_us, 99_000), "{:?}", summary);
+
+        // Tiny values are exact
+        let small = LatencyHistogram::default();
+        small.record(Duration::from_micros(3));
+        assert_eq!(small.percentile(0.99), 3);
+
+        let metrics = Metrics::default();
+        metrics.search.record(Duration::from_micros(200));
+        let json: serde_json::Value = serde_json::from_str(&metrics.to_json()).unwrap();
+        assert_eq!(json["search"]["count"], 1);
+        assert_eq!(json["insert"]["count"], 0);
+    }
+}
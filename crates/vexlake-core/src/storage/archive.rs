@@ -0,0 +1,418 @@
+//! Portable single-file snapshots of a version
+//!
+//! An archive is a POSIX tar file holding the version metadata as
+//! `version.json` plus every data, index and codebook file the version
+//! references, each under its original storage path. Archives whose path ends
+//! in `.gz` are gzip-compressed. Importing writes the files under a prefix of
+//! the new version and commits it, so a collection can be moved between
+//! clusters as one object.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use super::parquet::WRITE_CHUNK_SIZE;
+use super::{MetadataManager, StorageClient, VersionInfo};
+use crate::{Error, Result};
+
+/// Archive entry holding the version metadata
+const VERSION_ENTRY: &str = "version.json";
+/// Prefix under which imported files are written, followed by the version
+const IMPORT_PREFIX: &str = "_imports/version_";
+const BLOCK: usize = 512;
+/// Largest entry the 11 octal digits of the ustar size field can hold
+const MAX_ENTRY_SIZE: u64 = 0o77777777777;
+
+/// Bundle a version and all the files it references into one archive
+///
+/// The archive is streamed to `out_path` in the same storage, gzip-compressed
+/// if the path ends in `.gz`; only one referenced file is held in memory at a
+/// time. Returns the archived version.
+pub async fn export_archive(
+    client: &StorageClient,
+    version: u64,
+    out_path: &str,
+) -> Result<VersionInfo> {
+    let info = MetadataManager::new(client).get_version(version).await?;
+
+    let mut paths: Vec<&String> = info
+        .data_files
+        .values()
+        .chain(info.index_files.values())
+        .chain(info.codebook_files.values())
+        .collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut sink = ArchiveSink::open(client, out_path).await?;
+    let result = async {
+        sink.append(VERSION_ENTRY, &serde_json::to_vec(&info)?)
+            .await?;
+        for path in paths {
+            sink.append(path, &client.read(path).await?).await?;
+        }
+        sink.put(&[0; 2 * BLOCK]).await
+    }
+    .await;
+    match result {
+        Ok(()) => sink.finish().await?,
+        Err(e) => {
+            // Leave no partial archive behind; the original error matters more
+            let _ = sink.writer.abort().await;
+            return Err(e);
+        }
+    }
+    Ok(info)
+}
+
+/// Unpack an archive and commit its version
+///
+/// The version is committed as the target's next version, whatever its number
+/// in the source, since versions are committed in sequence. Files
+/// are written under `_imports/version_{version}/` followed by their original
+/// path, and the committed manifest points at those copies, so files of
+/// existing versions are never overwritten. Fails with [`Error::Conflict`] if
+/// another writer commits first. Returns the committed version.
+pub async fn import_archive(client: &StorageClient, archive_path: &str) -> Result<VersionInfo> {
+    let data = client.read_decompressed(archive_path).await?;
+    let entries = parse_entries(&data)?;
+
+    let mut info: Option<VersionInfo> = None;
+    for (path, contents) in &entries {
+        if path == VERSION_ENTRY {
+            info = Some(serde_json::from_slice(contents)?);
+        }
+    }
+    let mut info = info
+        .ok_or_else(|| Error::NotFound(format!("{} in archive {}", VERSION_ENTRY, archive_path)))?;
+
+    let manager = MetadataManager::new(client);
+    let latest = manager.get_latest_version_num().await?;
+    info.version = manager.next_version_num(latest).await?;
+    let imported = |path: &str| format!("{}{}/{}", IMPORT_PREFIX, info.version, path);
+
+    for (path, contents) in &entries {
+        if path != VERSION_ENTRY {
+            client.write(&imported(path), contents.to_vec()).await?;
+        }
+    }
+    let relocate = |files: &HashMap<String, String>| -> HashMap<String, String> {
+        files
+            .iter()
+            .map(|(key, path)| (key.clone(), imported(path)))
+            .collect()
+    };
+    info.data_files = relocate(&info.data_files);
+    info.index_files = relocate(&info.index_files);
+    info.codebook_files = relocate(&info.codebook_files);
+
+    manager.commit_version_cas(latest, info.clone()).await?;
+    Ok(info)
+}
+
+/// Archive output streamed into a storage object, optionally gzip-compressed
+struct ArchiveSink {
+    writer: opendal::Writer,
+    gzip: Option<flate2::write::GzEncoder<Vec<u8>>>,
+}
+
+impl ArchiveSink {
+    async fn open(client: &StorageClient, path: &str) -> Result<Self> {
+        let writer = client
+            .operator()
+            .writer_with(path)
+            .chunk(WRITE_CHUNK_SIZE)
+            .await
+            .map_err(|e| Error::Storage(Box::new(e)))?;
+        let gzip = path
+            .ends_with(".gz")
+            .then(|| flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default()));
+        Ok(Self { writer, gzip })
+    }
+
+    /// Append one regular file as a ustar header, contents and padding
+    async fn append(&mut self, path: &str, contents: &[u8]) -> Result<()> {
+        self.put(&entry_header(path, contents.len() as u64)?)
+            .await?;
+        self.put(contents).await?;
+        self.put(&[0; BLOCK][..padding(contents.len())]).await
+    }
+
+    async fn put(&mut self, bytes: &[u8]) -> Result<()> {
+        let chunk = match &mut self.gzip {
+            Some(gz) => {
+                gz.write_all(bytes).map_err(compress_error)?;
+                if gz.get_ref().len() < WRITE_CHUNK_SIZE {
+                    return Ok(());
+                }
+                std::mem::take(gz.get_mut())
+            }
+            None => bytes.to_vec(),
+        };
+        self.write(chunk).await
+    }
+
+    async fn finish(mut self) -> Result<()> {
+        if let Some(gz) = self.gzip.take() {
+            let rest = gz.finish().map_err(compress_error)?;
+            self.write(rest).await?;
+        }
+        self.writer
+            .close()
+            .await
+            .map_err(|e| Error::Storage(Box::new(e)))
+    }
+
+    async fn write(&mut self, chunk: Vec<u8>) -> Result<()> {
+        self.writer
+            .write(chunk)
+            .await
+            .map_err(|e| Error::Storage(Box::new(e)))
+    }
+}
+
+fn compress_error(e: std::io::Error) -> Error {
+    Error::Index(format!("compressing archive: {}", e))
+}
+
+/// ustar header of a regular file
+fn entry_header(path: &str, size: u64) -> Result<[u8; BLOCK]> {
+    if size > MAX_ENTRY_SIZE {
+        return Err(Error::InvalidConfig(format!(
+            "{} is too large for an archive entry ({} bytes)",
+            path, size
+        )));
+    }
+    let (prefix, name) = split_path(path)?;
+
+    let mut header = [0u8; BLOCK];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    header[100..108].copy_from_slice(b"0000644\0");
+    header[108..116].copy_from_slice(b"0000000\0");
+    header[116..124].copy_from_slice(b"0000000\0");
+    header[124..136].copy_from_slice(format!("{:011o}\0", size).as_bytes());
+    header[136..148].copy_from_slice(b"00000000000\0");
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|&b| b as u32).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+    Ok(header)
+}
+
+/// Zero bytes after an entry of `len` bytes to reach a block boundary
+fn padding(len: usize) -> usize {
+    len.next_multiple_of(BLOCK) - len
+}
+
+/// Split a path into ustar prefix (155 bytes) and name (100 bytes) fields
+fn split_path(path: &str) -> Result<(&str, &str)> {
+    if path.len() <= 100 {
+        return Ok(("", path));
+    }
+    path.match_indices('/')
+        .map(|(i, _)| (&path[..i], &path[i + 1..]))
+        .find(|(prefix, name)| prefix.len() <= 155 && name.len() <= 100 && !name.is_empty())
+        .ok_or_else(|| Error::InvalidConfig(format!("path too long for archive: {}", path)))
+}
+
+/// Parse the regular files of a ustar stream as `(path, contents)` pairs
+fn parse_entries(data: &[u8]) -> Result<Vec<(String, &[u8])>> {
+    let corrupt = |msg: &str| Error::InvalidConfig(format!("corrupt archive: {}", msg));
+
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while offset + BLOCK <= data.len() {
+        let header = &data[offset..offset + BLOCK];
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+
+        let expected = parse_octal(&header[148..156]).ok_or_else(|| corrupt("checksum"))?;
+        let actual: u64 = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| if (148..156).contains(&i) { b' ' } else { b } as u64)
+            .sum();
+        if expected != actual {
+            return Err(corrupt("checksum mismatch"));
+        }
+
+        let size = parse_octal(&header[124..136]).ok_or_else(|| corrupt("size"))? as usize;
+        let start = offset + BLOCK;
+        let end = start
+            .checked_add(size)
+            .filter(|&end| end <= data.len())
+            .ok_or_else(|| corrupt("truncated entry"))?;
+
+        if matches!(header[156], b'0' | 0) {
+            let name = field_str(&header[..100]);
+            let prefix = field_str(&header[345..500]);
+            let path = if prefix.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}/{}", prefix, name)
+            };
+            if path.starts_with('/') || path.split('/').any(|part| part == "..") {
+                return Err(corrupt(&format!("unsafe path {}", path)));
+            }
+            entries.push((path, &data[start..end]));
+        }
+        offset = start + size.next_multiple_of(BLOCK);
+    }
+    Ok(entries)
+}
+
+fn field_str(field: &[u8]) -> &str {
+    let len = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    std::str::from_utf8(&field[..len]).unwrap_or("")
+}
+
+fn parse_octal(field: &[u8]) -> Option<u64> {
+    let digits = field_str(field).trim_matches(|c: char| c == ' ');
+    u64::from_str_radix(digits, 8).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{search_version, ParquetWriter};
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_archive_roundtrip() {
+        let source = StorageClient::memory().unwrap();
+        let writer = ParquetWriter::new(&source, 2);
+
+        let mut data_files = HashMap::new();
+        let partitions = [
+            ("0", vec![1u64, 2], vec![vec![1.0, 0.0], vec![0.8, 0.2]]),
+            ("1", vec![3u64, 4], vec![vec![0.0, 1.0], vec![0.3, 0.7]]),
+        ];
+        for (partition, ids, vectors) in &partitions {
+            let batch = writer
+                .create_batch(ids, vectors, &vec![None; ids.len()])
+                .unwrap();
+            let path = format!("data/part-{}.parquet", partition);
+            writer.write_batch(&path, &batch).await.unwrap();
+            data_files.insert(partition.to_string(), path);
+        }
+        source
+            .write("indexes/hnsw.bin", vec![7; 1000])
+            .await
+            .unwrap();
+        let manager = MetadataManager::new(&source);
+        manager
+            .commit_version(VersionInfo {
+                version: 1,
+                timestamp: 42,
+                data_files,
+                index_files: HashMap::from([("hnsw".to_string(), "indexes/hnsw.bin".to_string())]),
+                codebook_files: HashMap::new(),
+                total_vectors: 4,
+            })
+            .await
+            .unwrap();
+
+        for out_path in ["backup/v1.tar", "backup/v1.tar.gz"] {
+            export_archive(&source, 1, out_path).await.unwrap();
+            let bytes = source.read(out_path).await.unwrap();
+            let transfer = StorageClient::memory().unwrap();
+            transfer.write("in/archive", bytes).await.unwrap();
+
+            let imported = import_archive(&transfer, "in/archive").await.unwrap();
+            assert_eq!(imported.version, 1);
+            assert_eq!(imported.total_vectors, 4);
+            assert_eq!(
+                imported.index_files["hnsw"],
+                "_imports/version_1/indexes/hnsw.bin"
+            );
+            assert_eq!(
+                transfer.read(&imported.index_files["hnsw"]).await.unwrap(),
+                vec![7; 1000]
+            );
+
+            let latest = MetadataManager::new(&transfer)
+                .get_latest_version()
+                .await
+                .unwrap();
+            let results = search_version(&transfer, &latest, &[0.0, 1.0], 2)
+                .await
+                .unwrap();
+            let ids: Vec<u64> = results.iter().map(|r| r.id).collect();
+            assert_eq!(ids, vec![3, 4]);
+
+            // Importing again cannot roll the latest version back
+            let again = import_archive(&transfer, "in/archive").await.unwrap();
+            assert_eq!(again.version, 2);
+            assert_eq!(
+                again.data_files["0"],
+                "_imports/version_2/data/part-0.parquet"
+            );
+        }
+
+        // Numbered after the target's versions, not the source's
+        let sparse = StorageClient::memory().unwrap();
+        let mut tar = Vec::new();
+        let manifest = serde_json::to_vec(&VersionInfo {
+            version: 9,
+            timestamp: 0,
+            data_files: HashMap::new(),
+            index_files: HashMap::new(),
+            codebook_files: HashMap::new(),
+            total_vectors: 0,
+        })
+        .unwrap();
+        append_entry(&mut tar, VERSION_ENTRY, &manifest).unwrap();
+        tar.resize(tar.len() + 2 * BLOCK, 0);
+        sparse.write("in/archive", tar).await.unwrap();
+        let imported = import_archive(&sparse, "in/archive").await.unwrap();
+        assert_eq!(imported.version, 1);
+
+        // Importing into the source leaves the live files of version 1 alone
+        source
+            .write("data/part-0.parquet", b"live".to_vec())
+            .await
+            .unwrap();
+        let imported = import_archive(&source, "backup/v1.tar").await.unwrap();
+        assert_eq!(imported.version, 2);
+        assert_eq!(source.read("data/part-0.parquet").await.unwrap(), b"live");
+    }
+
+    /// Append one regular file to an in-memory ustar stream
+    fn append_entry(tar: &mut Vec<u8>, path: &str, contents: &[u8]) -> Result<()> {
+        tar.extend_from_slice(&entry_header(path, contents.len() as u64)?);
+        tar.extend_from_slice(contents);
+        tar.resize(tar.len() + padding(contents.len()), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_archive_entries() {
+        let long = format!("{}/{}", "d".repeat(120), "f".repeat(90));
+        let mut tar = Vec::new();
+        append_entry(&mut tar, "a.txt", b"hello").unwrap();
+        append_entry(&mut tar, &long, b"").unwrap();
+        tar.extend_from_slice(&[0; 2 * BLOCK]);
+
+        let entries = parse_entries(&tar).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0], ("a.txt".to_string(), &b"hello"[..]));
+        assert_eq!(entries[1].0, long);
+        assert!(append_entry(&mut Vec::new(), &"x".repeat(300), b"").is_err());
+        assert!(entry_header("big", MAX_ENTRY_SIZE).is_ok());
+        assert!(matches!(
+            entry_header("big", 8 << 30),
+            Err(Error::InvalidConfig(_))
+        ));
+
+        let mut evil = Vec::new();
+        append_entry(&mut evil, "../etc/passwd", b"x").unwrap();
+        assert!(parse_entries(&evil).is_err());
+
+        tar[148] ^= 1;
+        assert!(parse_entries(&tar).is_err());
+    }
+}
@@ -0,0 +1,256 @@
+//! Read-through LRU cache for storage objects
+//!
+//! Index shards and Parquet footers are read over and over, and every read
+//! is a round-trip to the object store. [`CachedStorageClient`] keeps the most
+//! recently read objects in memory up to a byte budget. Writes and deletes
+//! made through it invalidate the cached copy; changes made by other clients
+//! are not seen until the entry is evicted or invalidated.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::StorageClient;
+use crate::Result;
+
+/// Hit and occupancy counters of a [`CachedStorageClient`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CacheStats {
+    /// Reads served from memory
+    pub hits: u64,
+    /// Reads that went to storage
+    pub misses: u64,
+    /// Objects currently cached
+    pub entries: usize,
+    /// Bytes currently cached
+    pub bytes: usize,
+}
+
+struct Entry {
+    data: Arc<Vec<u8>>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Lru {
+    entries: HashMap<String, Entry>,
+    /// Paths by last use, oldest first
+    order: BTreeMap<u64, String>,
+    tick: u64,
+    bytes: usize,
+    /// Bumped by every write and delete, so a read that raced one is not cached
+    generation: u64,
+}
+
+impl Lru {
+    fn get(&mut self, path: &str) -> Option<Arc<Vec<u8>>> {
+        self.tick += 1;
+        let entry = self.entries.get_mut(path)?;
+        self.order.remove(&entry.last_used);
+        entry.last_used = self.tick;
+        self.order.insert(self.tick, path.to_string());
+        Some(entry.data.clone())
+    }
+
+    fn put(&mut self, path: &str, data: Arc<Vec<u8>>, capacity: usize) {
+        self.remove(path);
+        while self.bytes + data.len() > capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            if let Some(evicted) = self.entries.remove(&oldest) {
+                self.bytes -= evicted.data.len();
+            }
+        }
+        self.tick += 1;
+        self.bytes += data.len();
+        self.order.insert(self.tick, path.to_string());
+        self.entries.insert(
+            path.to_string(),
+            Entry {
+                data,
+                last_used: self.tick,
+            },
+        );
+    }
+
+    fn remove(&mut self, path: &str) {
+        if let Some(entry) = self.entries.remove(path) {
+            self.order.remove(&entry.last_used);
+            self.bytes -= entry.data.len();
+        }
+    }
+}
+
+/// [`StorageClient`] wrapper that caches object reads in memory
+///
+/// Holds at most `capacity_bytes` of object data, evicting the least
+/// recently read objects first. Objects larger than the whole budget are
+/// never cached.
+pub struct CachedStorageClient {
+    client: StorageClient,
+    capacity_bytes: usize,
+    lru: Mutex<Lru>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CachedStorageClient {
+    /// Wrap a client with a cache of `capacity_bytes`
+    pub fn new(client: StorageClient, capacity_bytes: usize) -> Self {
+        Self {
+            client,
+            capacity_bytes,
+            lru: Mutex::new(Lru::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Get the wrapped client; its operations bypass the cache
+    pub fn inner(&self) -> &StorageClient {
+        &self.client
+    }
+
+    /// Read an object, from memory if it is cached
+    pub async fn read(&self, path: &str) -> Result<Vec<u8>> {
+        let generation = {
+            let mut lru = self.lru();
+            if let Some(data) = lru.get(path) {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(data.as_ref().clone());
+            }
+            lru.generation
+        };
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let data = self.client.read(path).await?;
+        if data.len() <= self.capacity_bytes {
+            let mut lru = self.lru();
+            if lru.generation == generation {
+                lru.put(path, Arc::new(data.clone()), self.capacity_bytes);
+            }
+        }
+        Ok(data)
+    }
+
+    /// Write an object and drop its cached copy
+    pub async fn write(&self, path: &str, data: Vec<u8>) -> Result<()> {
+        self.invalidate(path);
+        let result = self.client.write(path, data).await;
+        // A read that started before the write may have cached the old data
+        self.invalidate(path);
+        result
+    }
+
+    /// Delete an object and drop its cached copy
+    pub async fn delete(&self, path: &str) -> Result<()> {
+        self.invalidate(path);
+        let result = self.client.delete(path).await;
+        self.invalidate(path);
+        result
+    }
+
+    /// Check if a path exists in storage (not answered from the cache)
+    pub async fn exists(&self, path: &str) -> Result<bool> {
+        self.client.exists(path).await
+    }
+
+    /// List objects under a prefix in storage
+    pub async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        self.client.list(prefix).await
+    }
+
+    /// Drop the cached copy of `path`, if any
+    pub fn invalidate(&self, path: &str) {
+        let mut lru = self.lru();
+        lru.generation += 1;
+        lru.remove(path);
+    }
+
+    /// Drop every cached object; the counters are kept
+    pub fn clear(&self) {
+        let mut lru = self.lru();
+        let generation = lru.generation + 1;
+        *lru = Lru {
+            generation,
+            ..Lru::default()
+        };
+    }
+
+    /// Hit/miss counters and current occupancy
+    pub fn stats(&self) -> CacheStats {
+        let lru = self.lru();
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: lru.entries.len(),
+            bytes: lru.bytes,
+        }
+    }
+
+    /// The cache state; a panic while holding the lock cannot leave it
+    /// inconsistent in a harmful way, so poisoning is ignored
+    fn lru(&self) -> MutexGuard<'_, Lru> {
+        self.lru.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cached_read_survives_backing_delete() {
+        let cached = CachedStorageClient::new(StorageClient::memory().unwrap(), 1024);
+        cached.write("shard/0", b"graph".to_vec()).await.unwrap();
+
+        assert_eq!(cached.read("shard/0").await.unwrap(), b"graph");
+        // Remove the object behind the cache's back
+        cached.inner().delete("shard/0").await.unwrap();
+        assert_eq!(cached.read("shard/0").await.unwrap(), b"graph");
+        assert_eq!(
+            cached.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                entries: 1,
+                bytes: 5,
+            }
+        );
+
+        // Writes and deletes through the cache invalidate it
+        cached.write("shard/0", b"graph v2".to_vec()).await.unwrap();
+        assert_eq!(cached.read("shard/0").await.unwrap(), b"graph v2");
+        cached.delete("shard/0").await.unwrap();
+        assert!(cached.read("shard/0").await.is_err());
+        assert_eq!(cached.stats().entries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_cache_evicts_least_recently_used() {
+        let cached = CachedStorageClient::new(StorageClient::memory().unwrap(), 10);
+        for (path, data) in [("a", vec![0u8; 4]), ("b", vec![1; 4]), ("c", vec![2; 4])] {
+            cached.inner().write(path, data).await.unwrap();
+        }
+        cached.inner().write("big", vec![3; 11]).await.unwrap();
+
+        cached.read("a").await.unwrap();
+        cached.read("b").await.unwrap();
+        cached.read("a").await.unwrap();
+        // Needs room: "b" is the least recently used
+        cached.read("c").await.unwrap();
+        let stats = cached.stats();
+        assert_eq!((stats.entries, stats.bytes), (2, 8));
+
+        cached.read("a").await.unwrap();
+        cached.read("b").await.unwrap();
+        assert_eq!(cached.stats().hits, 2);
+
+        // Larger than the whole budget, never cached
+        cached.read("big").await.unwrap();
+        assert!(cached.stats().bytes <= 10);
+        cached.clear();
+        assert_eq!(cached.stats().entries, 0);
+    }
+}
@@ -0,0 +1,288 @@
+//! Background compaction of small partitions
+//!
+//! Incremental ingest leaves many small Parquet partitions behind. Compaction
+//! merges them into a single partition and commits the result as a new version,
+//! so readers keep seeing a consistent snapshot throughout.
+
+use arrow::array::RecordBatch;
+use arrow::compute::concat_batches;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{oneshot, Mutex};
+use tokio::task::JoinHandle;
+
+use super::{MetadataManager, ParquetReader, ParquetWriter, StorageClient, VersionInfo};
+use crate::{Error, Result};
+
+/// Policy deciding which partitions get compacted
+#[derive(Debug, Clone)]
+pub struct CompactionPolicy {
+    /// Partitions with fewer rows than this are compaction candidates
+    pub small_partition_rows: usize,
+    /// Minimum number of candidates before a compaction runs
+    pub min_candidates: usize,
+}
+
+impl Default for CompactionPolicy {
+    fn default() -> Self {
+        Self {
+            small_partition_rows: 10_000,
+            min_candidates: 2,
+        }
+    }
+}
+
+/// Get the partitions of a version that are small enough to compact
+///
+/// Returns partition IDs in sorted order.
+pub async fn compaction_candidates(
+    client: &StorageClient,
+    version: &VersionInfo,
+    policy: &CompactionPolicy,
+) -> Result<Vec<String>> {
+    let reader = ParquetReader::new(client);
+    let mut partitions: Vec<_> = version.data_files.iter().collect();
+    partitions.sort();
+
+    let mut candidates = Vec::new();
+    for (partition, path) in partitions {
+        if reader.row_count(path).await? < policy.small_partition_rows {
+            candidates.push(partition.clone());
+        }
+    }
+
+    Ok(candidates)
+}
+
+/// Commit attempts before a compaction gives up on concurrent writers
+const MAX_COMMIT_ATTEMPTS: usize = 3;
+
+/// Merge the small partitions of the latest version into one
+///
+/// The result is committed with [`MetadataManager::commit_version_cas`], so
+/// a version committed by another writer in the meantime is never
+/// overwritten; the compaction starts over from the new latest version, up
+/// to three attempts, then fails with [`Error::Conflict`]. The compacted
+/// version has no index files, since indexes built over the old partitions
+/// would point at data that moved, and drops the codebooks of the merged
+/// partitions. Returns the committed version, or `None` if there was nothing
+/// to compact.
+pub async fn compact(
+    client: &StorageClient,
+    policy: &CompactionPolicy,
+) -> Result<Option<VersionInfo>> {
+    let mut attempt = 1;
+    loop {
+        match try_compact(client, policy).await {
+            Err(Error::Conflict(_)) if attempt < MAX_COMMIT_ATTEMPTS => attempt += 1,
+            result => return result,
+        }
+    }
+}
+
+async fn try_compact(
+    client: &StorageClient,
+    policy: &CompactionPolicy,
+) -> Result<Option<VersionInfo>> {
+    let manager = MetadataManager::new(client);
+    let latest = manager.get_latest_version().await?;
+
+    let candidates = compaction_candidates(client, &latest, policy).await?;
+    if candidates.len() < policy.min_candidates.max(2) {
+        return Ok(None);
+    }
+
+    let reader = ParquetReader::new(client);
+    let mut batches: Vec<RecordBatch> = Vec::new();
+    for partition in &candidates {
+        batches.extend(reader.read_all(&latest.data_files[partition]).await?);
+    }
+
+    let Some(first) = batches.first() else {
+        return Ok(None);
+    };
+    let merged = concat_batches(&first.schema(), &batches).map_err(Error::Arrow)?;
+
+    let expected = latest.version;
+    let version = manager.next_version_num(expected).await?;
+    let partition = format!("compacted-{}", version);
+    // Unique per attempt, so racing compactions never write the same object
+    let path = format!("data/{}-{:016x}.parquet", partition, rand::random::<u64>());
+    ParquetWriter::new(client, 0)
+        .write_batch(&path, &merged)
+        .await?;
+
+    let mut info = latest;
+    for candidate in &candidates {
+        info.data_files.remove(candidate);
+        info.codebook_files.remove(candidate);
+    }
+    info.index_files.clear();
+    info.data_files.insert(partition, path.clone());
+    info.version = version;
+    info.timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    if let Err(e) = manager.commit_version_cas(expected, info.clone()).await {
+        // No version references the merged file; a failed delete only leaks it
+        let _ = client.delete(&path).await;
+        return Err(e);
+    }
+    Ok(Some(info))
+}
+
+/// Background task that periodically compacts small partitions
+pub struct Compactor {
+    lock: Arc<Mutex<()>>,
+    stop_tx: oneshot::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+impl Compactor {
+    /// Spawn the compaction loop on the current tokio runtime
+    pub fn start(client: Arc<StorageClient>, interval: Duration, policy: CompactionPolicy) -> Self {
+        let lock = Arc::new(Mutex::new(()));
+        let (stop_tx, mut stop_rx) = oneshot::channel();
+
+        let task_lock = Arc::clone(&lock);
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = &mut stop_rx => break,
+                    _ = ticker.tick() => {
+                        let _guard = task_lock.lock().await;
+                        if let Err(e) = compact(&client, &policy).await {
+                            tracing::warn!("compaction failed: {}", e);
+                        }
+                    }
+                }
+            }
+        });
+
+        Self {
+            lock,
+            stop_tx,
+            handle,
+        }
+    }
+
+    /// Lock held while a compaction runs
+    ///
+    /// Only an optimization: compactions commit with
+    /// [`MetadataManager::commit_version_cas`], so a writer racing the
+    /// compactor cannot be overwritten either way. Writers in this process
+    /// that hold the lock just avoid making the compaction redo its work.
+    pub fn metadata_lock(&self) -> Arc<Mutex<()>> {
+        Arc::clone(&self.lock)
+    }
+
+    /// Stop the compaction loop and wait for it to finish
+    pub async fn stop(self) {
+        let _ = self.stop_tx.send(());
+        let _ = self.handle.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_compactor_merges_small_partitions() {
+        let client = Arc::new(StorageClient::memory().unwrap());
+        let writer = ParquetWriter::new(&client, 2);
+
+        let mut data_files = HashMap::new();
+        for p in 0..3u64 {
+            let batch = writer
+                .create_batch(
+                    &[p * 2, p * 2 + 1],
+                    &[vec![p as f32, 0.0], vec![0.0, p as f32]],
+                    &[None, None],
+                )
+                .unwrap();
+            let path = format!("data/part-{}.parquet", p);
+            writer.write_batch(&path, &batch).await.unwrap();
+            data_files.insert(p.to_string(), path);
+        }
+
+        let manager = MetadataManager::new(&client);
+        manager
+            .commit_version(VersionInfo {
+                version: 1,
+                timestamp: 0,
+                data_files,
+                index_files: HashMap::new(),
+                codebook_files: HashMap::new(),
+                total_vectors: 6,
+            })
+            .await
+            .unwrap();
+
+        let compactor = Compactor::start(
+            Arc::clone(&client),
+            Duration::from_millis(10),
+            CompactionPolicy::default(),
+        );
+
+        let mut latest = manager.get_latest_version().await.unwrap();
+        for _ in 0..100 {
+            if latest.data_files.len() == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            latest = manager.get_latest_version().await.unwrap();
+        }
+        compactor.stop().await;
+
+        assert_eq!(latest.data_files.len(), 1);
+        assert_eq!(latest.total_vectors, 6);
+
+        let path = latest.data_files.values().next().unwrap();
+        let batches = ParquetReader::new(&client).read_all(path).await.unwrap();
+        let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+        assert_eq!(rows, 6);
+    }
+
+    #[tokio::test]
+    async fn test_compact_drops_stale_indexes() {
+        let client = StorageClient::memory().unwrap();
+        let writer = ParquetWriter::new(&client, 2);
+        let mut data_files = HashMap::new();
+        for p in 0..2u64 {
+            let batch = writer
+                .create_batch(&[p], &[vec![p as f32, 1.0]], &[None])
+                .unwrap();
+            let path = format!("data/part-{}.parquet", p);
+            writer.write_batch(&path, &batch).await.unwrap();
+            data_files.insert(p.to_string(), path);
+        }
+        let manager = MetadataManager::new(&client);
+        manager
+            .commit_version(VersionInfo {
+                version: 1,
+                timestamp: 0,
+                data_files,
+                index_files: HashMap::from([("hnsw".to_string(), "idx/hnsw.bin".to_string())]),
+                codebook_files: HashMap::from([("0".to_string(), "cb/0.bin".to_string())]),
+                total_vectors: 2,
+            })
+            .await
+            .unwrap();
+
+        let info = compact(&client, &CompactionPolicy::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.version, 2);
+        assert!(info.index_files.is_empty());
+        assert!(info.codebook_files.is_empty());
+        assert_eq!(info.data_files.len(), 1);
+        assert!(info.data_files["compacted-2"].starts_with("data/compacted-2-"));
+        assert_eq!(manager.get_latest_version_num().await.unwrap(), 2);
+    }
+}
@@ -0,0 +1,95 @@
+//! Storage connectivity probe for readiness checks
+//!
+//! Unlike [`crate::health_check`], which only reports that the library is
+//! loaded, [`storage_health`] does a real write, read and delete against the
+//! backend, so a readiness probe fails while the object store is unreachable.
+
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use super::StorageClient;
+use crate::Result;
+
+/// Prefix under which probe objects are written
+pub const HEALTH_PREFIX: &str = "_health/";
+
+const PROBE: &[u8] = b"vexlake-health";
+
+/// Outcome of [`storage_health`]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct HealthReport {
+    /// The probe object could be written
+    pub writable: bool,
+    /// The probe object read back with the written content
+    pub readable: bool,
+    /// Milliseconds for the write, read and delete together
+    pub round_trip_ms: u64,
+    /// Microseconds taken by the probe write
+    pub write_us: u64,
+    /// Microseconds taken by the read back, `None` if the write failed
+    pub read_us: Option<u64>,
+    /// Microseconds taken by the delete, `None` if the write failed
+    pub delete_us: Option<u64>,
+}
+
+impl HealthReport {
+    /// Both writes and reads work
+    pub fn is_healthy(&self) -> bool {
+        self.writable && self.readable
+    }
+}
+
+/// Write, read back and delete a probe object under [`HEALTH_PREFIX`]
+///
+/// Failed writes and reads are reported as `false` flags rather than
+/// errors. An error means the probe was written but could not be deleted.
+pub async fn storage_health(client: &StorageClient) -> Result<HealthReport> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    let path = format!("{}probe-{}-{}", HEALTH_PREFIX, std::process::id(), nanos);
+
+    let micros = |since: Instant| since.elapsed().as_micros() as u64;
+
+    let start = Instant::now();
+    let writable = client.write(&path, PROBE.to_vec()).await.is_ok();
+    let write_us = micros(start);
+
+    let (mut readable, mut read_us, mut delete_us) = (false, None, None);
+    if writable {
+        let step = Instant::now();
+        readable = client.read(&path).await.is_ok_and(|data| data == PROBE);
+        read_us = Some(micros(step));
+
+        let step = Instant::now();
+        client.delete(&path).await?;
+        delete_us = Some(micros(step));
+    }
+
+    Ok(HealthReport {
+        writable,
+        readable,
+        round_trip_ms: start.elapsed().as_millis() as u64,
+        write_us,
+        read_us,
+        delete_us,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_storage_health_memory() {
+        let client = StorageClient::memory().unwrap();
+        let report = storage_health(&client).await.unwrap();
+        assert!(report.writable && report.readable && report.is_healthy());
+        assert!(report.round_trip_ms < 5_000);
+        let steps = [report.read_us, report.delete_us];
+        assert!(steps.iter().all(Option::is_some));
+        let total_us = report.write_us + steps.iter().flatten().sum::<u64>();
+        assert!(total_us <= (report.round_trip_ms + 1) * 1000);
+        // The probe is cleaned up
+        assert!(client.list(HEALTH_PREFIX).await.unwrap().is_empty());
+    }
+}
@@ -6,7 +6,7 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use super::StorageClient;
+use super::{ParquetReader, StorageClient};
 use crate::{Error, Result};
 
 /// Information about a VexLake data version
@@ -20,6 +20,13 @@ pub struct VersionInfo {
     pub data_files: HashMap<String, String>,
     /// Map of index name to index file path
     pub index_files: HashMap<String, String>,
+    /// Map of partition ID to quantization codebook path
+    ///
+    /// Versioned with the data so older snapshots dequantize with the
+    /// codebook they were encoded with. Absent in versions written before
+    /// quantization existed.
+    #[serde(default)]
+    pub codebook_files: HashMap<String, String>,
     /// Number of vectors in this version
     pub total_vectors: usize,
 }
@@ -45,13 +52,34 @@ impl<'a> MetadataManager<'a> {
         "_metadata/latest".to_string()
     }
 
+    /// Get the path recording the highest version number a rollback moved
+    /// the latest pointer away from
+    fn high_water_path() -> String {
+        "_metadata/high_water".to_string()
+    }
+
     /// Get the latest version number
     pub async fn get_latest_version_num(&self) -> Result<u64> {
-        if !self.client.exists(&Self::latest_path()).await? {
+        self.read_num(&Self::latest_path()).await
+    }
+
+    /// Number for the version committed on top of `latest`
+    ///
+    /// `latest + 1`, unless a [`MetadataManager::rollback_to`] moved the
+    /// pointer back: the numbers of the versions it rolled back over stay
+    /// taken, so their files are never rewritten.
+    pub async fn next_version_num(&self, latest: u64) -> Result<u64> {
+        let high_water = self.read_num(&Self::high_water_path()).await?;
+        Ok(latest.max(high_water) + 1)
+    }
+
+    /// Read a version number object, 0 if it does not exist
+    async fn read_num(&self, path: &str) -> Result<u64> {
+        if !self.client.exists(path).await? {
             return Ok(0);
         }
 
-        let data = self.client.read(&Self::latest_path()).await?;
+        let data = self.client.read(path).await?;
         let content = String::from_utf8(data).map_err(|e| Error::Ffi(e.to_string()))?;
         content
             .trim()
@@ -67,6 +95,7 @@ impl<'a> MetadataManager<'a> {
                 timestamp: 0,
                 data_files: HashMap::new(),
                 index_files: HashMap::new(),
+                codebook_files: HashMap::new(),
                 total_vectors: 0,
             });
         }
@@ -81,6 +110,51 @@ impl<'a> MetadataManager<'a> {
         self.get_version(latest).await
     }
 
+    /// Recompute the number of vectors in a version from its data files
+    ///
+    /// Sums the row counts in each partition's Parquet footer, so no rows are
+    /// decoded. `VersionInfo::total_vectors` can drift across merges; this is
+    /// the authoritative figure. IDs duplicated across partitions are counted
+    /// once per partition, hence approximate.
+    pub async fn approx_vector_count(&self, version: u64) -> Result<usize> {
+        let info = self.get_version(version).await?;
+        let reader = ParquetReader::new(self.client);
+
+        let mut total = 0;
+        for path in info.data_files.values() {
+            total += reader.row_count(path).await?;
+        }
+        Ok(total)
+    }
+
+    /// Store a partition's codebook for a version and record it in `info`
+    ///
+    /// Returns the object path. The reference becomes visible to readers once
+    /// `info` is committed.
+    pub async fn store_codebook(
+        &self,
+        info: &mut VersionInfo,
+        partition: &str,
+        data: Vec<u8>,
+    ) -> Result<String> {
+        let path = format!("_codebooks/version_{}/{}.bin", info.version, partition);
+        self.client.write(&path, data).await?;
+        info.codebook_files
+            .insert(partition.to_string(), path.clone());
+        Ok(path)
+    }
+
+    /// Load a partition's codebook as referenced by a version
+    pub async fn load_codebook(&self, info: &VersionInfo, partition: &str) -> Result<Vec<u8>> {
+        let path = info.codebook_files.get(partition).ok_or_else(|| {
+            Error::NotFound(format!(
+                "codebook for partition {} in version {}",
+                partition, info.version
+            ))
+        })?;
+        self.client.read(path).await
+    }
+
     /// Commit a new version
     pub async fn commit_version(&self, info: VersionInfo) -> Result<()> {
         let version = info.version;
@@ -98,6 +172,77 @@ impl<'a> MetadataManager<'a> {
 
         Ok(())
     }
+
+    /// Commit a new version only if the latest version is still `expected_latest`
+    ///
+    /// `info.version` must be [`MetadataManager::next_version_num`] of
+    /// `expected_latest`, or the commit fails with [`Error::InvalidConfig`].
+    /// The version file is created with a
+    /// conditional write, so of several writers racing from the same latest
+    /// version exactly one succeeds; the others get [`Error::Conflict`] and
+    /// should reload and retry.
+    pub async fn commit_version_cas(&self, expected_latest: u64, info: VersionInfo) -> Result<()> {
+        let version = info.version;
+        let next = self.next_version_num(expected_latest).await?;
+        if version != next {
+            return Err(Error::InvalidConfig(format!(
+                "version {} is not the next version {} after expected latest {}",
+                version, next, expected_latest
+            )));
+        }
+
+        let current = self.get_latest_version_num().await?;
+        if current != expected_latest {
+            return Err(Error::Conflict(format!(
+                "latest version is {}, expected {}",
+                current, expected_latest
+            )));
+        }
+
+        let data = serde_json::to_vec(&info).map_err(Error::Serialization)?;
+        if !self
+            .client
+            .write_if_absent(&Self::version_path(version), data)
+            .await?
+        {
+            return Err(Error::Conflict(format!(
+                "version {} was committed concurrently",
+                version
+            )));
+        }
+
+        // Every writer from `expected_latest` targets the same file and only
+        // its creator gets here, so a losing writer cannot move the pointer
+        self.client
+            .write(&Self::latest_path(), version.to_string().into_bytes())
+            .await
+    }
+
+    /// Make an earlier version current again
+    ///
+    /// Rewrites the latest pointer to `version` without touching any version
+    /// file, so the versions after it stay readable. Their numbers stay taken:
+    /// later commits continue after the highest version ever committed (see
+    /// [`MetadataManager::next_version_num`]). Fails with [`Error::NotFound`]
+    /// if `version` was never committed. Writers should be paused meanwhile;
+    /// a commit racing the rollback can land after it and supersede it.
+    pub async fn rollback_to(&self, version: u64) -> Result<()> {
+        if !self.client.exists(&Self::version_path(version)).await? {
+            return Err(Error::NotFound(format!("version {}", version)));
+        }
+
+        // Recorded before the pointer moves, so no later commit can reuse a
+        // number that was already committed
+        let latest = self.get_latest_version_num().await?;
+        if latest > self.read_num(&Self::high_water_path()).await? {
+            self.client
+                .write(&Self::high_water_path(), latest.to_string().into_bytes())
+                .await?;
+        }
+        self.client
+            .write(&Self::latest_path(), version.to_string().into_bytes())
+            .await
+    }
 }
 
 #[cfg(test)]
@@ -121,6 +266,7 @@ mod tests {
             timestamp: 123456789,
             data_files,
             index_files: HashMap::new(),
+            codebook_files: HashMap::new(),
             total_vectors: 100,
         };
 
@@ -132,4 +278,189 @@ mod tests {
         assert_eq!(loaded.version, 1);
         assert_eq!(loaded.total_vectors, 100);
     }
+
+    #[tokio::test]
+    async fn test_commit_version_cas() {
+        let client = StorageClient::memory().unwrap();
+        let manager = MetadataManager::new(&client);
+        let version = |version, total_vectors| VersionInfo {
+            version,
+            timestamp: 0,
+            data_files: HashMap::new(),
+            index_files: HashMap::new(),
+            codebook_files: HashMap::new(),
+            total_vectors,
+        };
+
+        let (a, b) = tokio::join!(
+            manager.commit_version_cas(0, version(1, 10)),
+            manager.commit_version_cas(0, version(1, 20)),
+        );
+        assert_eq!(a.is_ok() as u8 + b.is_ok() as u8, 1);
+        let loser = if a.is_ok() { b } else { a };
+        assert!(matches!(loser, Err(Error::Conflict(_))));
+
+        let winner = manager.get_latest_version().await.unwrap();
+        assert_eq!(winner.version, 1);
+        assert_eq!(
+            manager.get_version(1).await.unwrap().total_vectors,
+            winner.total_vectors
+        );
+
+        // A stale expectation is rejected before anything is written
+        assert!(matches!(
+            manager.commit_version_cas(0, version(1, 30)).await,
+            Err(Error::Conflict(_))
+        ));
+        assert_eq!(
+            manager.get_version(1).await.unwrap().total_vectors,
+            winner.total_vectors
+        );
+        assert!(matches!(
+            manager.commit_version_cas(1, version(1, 0)).await,
+            Err(Error::InvalidConfig(_))
+        ));
+
+        // Writers from the same latest version cannot skip ahead, so the
+        // pointer never moves backwards
+        let (a, b) = tokio::join!(
+            manager.commit_version_cas(1, version(2, 0)),
+            manager.commit_version_cas(1, version(3, 0)),
+        );
+        a.unwrap();
+        assert!(matches!(b, Err(Error::InvalidConfig(_))));
+        assert!(!client.exists("_metadata/version_3.json").await.unwrap());
+        assert_eq!(manager.get_latest_version_num().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_approx_vector_count() {
+        use crate::storage::ParquetWriter;
+
+        let client = StorageClient::memory().unwrap();
+        let writer = ParquetWriter::new(&client, 2);
+
+        let mut data_files = HashMap::new();
+        for (partition, rows) in [("0", 3u64), ("1", 5u64)] {
+            let ids: Vec<u64> = (0..rows).collect();
+            let vectors = vec![vec![1.0, 0.0]; rows as usize];
+            let metadata = vec![None; rows as usize];
+            let batch = writer.create_batch(&ids, &vectors, &metadata).unwrap();
+            let path = format!("data/part-{}.parquet", partition);
+            writer.write_batch(&path, &batch).await.unwrap();
+            data_files.insert(partition.to_string(), path);
+        }
+
+        let manager = MetadataManager::new(&client);
+        manager
+            .commit_version(VersionInfo {
+                version: 1,
+                timestamp: 0,
+                data_files,
+                index_files: HashMap::new(),
+                codebook_files: HashMap::new(),
+                // Deliberately stale
+                total_vectors: 0,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(manager.approx_vector_count(1).await.unwrap(), 8);
+        assert_eq!(manager.approx_vector_count(0).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_codebook_versioning() {
+        let client = StorageClient::memory().unwrap();
+        let manager = MetadataManager::new(&client);
+
+        let mut info = VersionInfo {
+            version: 1,
+            timestamp: 0,
+            data_files: HashMap::new(),
+            index_files: HashMap::new(),
+            codebook_files: HashMap::new(),
+            total_vectors: 0,
+        };
+        manager
+            .store_codebook(&mut info, "0", vec![1, 2, 3])
+            .await
+            .unwrap();
+        manager.commit_version(info).await.unwrap();
+
+        let loaded = manager.get_latest_version().await.unwrap();
+        assert_eq!(loaded.codebook_files.len(), 1);
+        assert_eq!(
+            manager.load_codebook(&loaded, "0").await.unwrap(),
+            vec![1, 2, 3]
+        );
+        assert!(matches!(
+            manager.load_codebook(&loaded, "1").await,
+            Err(Error::NotFound(_))
+        ));
+
+        // Versions written before codebooks existed still load
+        let legacy =
+            r#"{"version":2,"timestamp":0,"data_files":{},"index_files":{},"total_vectors":0}"#;
+        let info: VersionInfo = serde_json::from_str(legacy).unwrap();
+        assert!(info.codebook_files.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rollback_to() {
+        let client = StorageClient::memory().unwrap();
+        let manager = MetadataManager::new(&client);
+        for version in 1..=3 {
+            let info = VersionInfo {
+                version,
+                timestamp: 0,
+                data_files: HashMap::new(),
+                index_files: HashMap::new(),
+                codebook_files: HashMap::new(),
+                total_vectors: version as usize,
+            };
+            manager.commit_version(info).await.unwrap();
+        }
+
+        manager.rollback_to(1).await.unwrap();
+        assert_eq!(manager.get_latest_version_num().await.unwrap(), 1);
+        assert_eq!(manager.get_latest_version().await.unwrap().total_vectors, 1);
+        for version in [2, 3] {
+            assert!(client
+                .exists(&format!("_metadata/version_{}.json", version))
+                .await
+                .unwrap());
+        }
+
+        for missing in [0, 7] {
+            assert!(matches!(
+                manager.rollback_to(missing).await,
+                Err(Error::NotFound(_))
+            ));
+        }
+        assert_eq!(manager.get_latest_version_num().await.unwrap(), 1);
+
+        // Commits continue after the versions rolled back over
+        let version = |version| VersionInfo {
+            version,
+            timestamp: 0,
+            data_files: HashMap::new(),
+            index_files: HashMap::new(),
+            codebook_files: HashMap::new(),
+            total_vectors: 9,
+        };
+        assert_eq!(manager.next_version_num(1).await.unwrap(), 4);
+        assert!(matches!(
+            manager.commit_version_cas(1, version(2)).await,
+            Err(Error::InvalidConfig(_))
+        ));
+        manager.commit_version_cas(1, version(4)).await.unwrap();
+        assert_eq!(manager.get_latest_version_num().await.unwrap(), 4);
+        assert_eq!(manager.get_version(2).await.unwrap().total_vectors, 2);
+
+        // Rolling forward to an abandoned version keeps every number taken
+        manager.rollback_to(3).await.unwrap();
+        assert_eq!(manager.get_latest_version().await.unwrap().total_vectors, 3);
+        assert_eq!(manager.next_version_num(3).await.unwrap(), 5);
+    }
 }
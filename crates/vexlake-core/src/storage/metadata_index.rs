@@ -0,0 +1,119 @@
+//! Inverted index over the metadata column
+//!
+//! Maps each metadata value of a partition to the IDs carrying it, so
+//! attribute filters can be resolved without scanning Parquet. The index is
+//! stored as JSON next to the partition it was built from.
+
+use arrow::array::{RecordBatch, StringArray, UInt64Array};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::StorageClient;
+use crate::{Error, Result};
+
+/// Inverted index from metadata value to vector IDs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetadataIndex {
+    postings: BTreeMap<String, Vec<u64>>,
+}
+
+impl MetadataIndex {
+    /// Create an empty index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build an index from the `id` and `metadata` columns of batches
+    ///
+    /// Rows with null metadata are not indexed.
+    pub fn from_batches(batches: &[RecordBatch]) -> Result<Self> {
+        let mut index = Self::new();
+        for batch in batches {
+            index.add_batch(batch)?;
+        }
+        Ok(index)
+    }
+
+    /// Index the rows of one batch
+    pub fn add_batch(&mut self, batch: &RecordBatch) -> Result<()> {
+        let ids = batch
+            .column_by_name("id")
+            .and_then(|c| c.as_any().downcast_ref::<UInt64Array>())
+            .ok_or_else(|| Error::InvalidConfig("missing UInt64 column: id".to_string()))?;
+        let metadata = batch
+            .column_by_name("metadata")
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+            .ok_or_else(|| Error::InvalidConfig("missing Utf8 column: metadata".to_string()))?;
+
+        for (id, value) in ids.values().iter().zip(metadata.iter()) {
+            if let Some(value) = value {
+                self.insert(value, *id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a single `(value, id)` posting
+    pub fn insert(&mut self, value: &str, id: u64) {
+        let ids = self.postings.entry(value.to_string()).or_default();
+        if let Err(pos) = ids.binary_search(&id) {
+            ids.insert(pos, id);
+        }
+    }
+
+    /// Get the IDs whose metadata equals `value`, in ascending order
+    pub fn ids_for(&self, value: &str) -> Vec<u64> {
+        self.postings.get(value).cloned().unwrap_or_default()
+    }
+
+    /// Number of distinct values
+    pub fn len(&self) -> usize {
+        self.postings.len()
+    }
+
+    /// Check if the index is empty
+    pub fn is_empty(&self) -> bool {
+        self.postings.is_empty()
+    }
+
+    /// Write the index to storage
+    pub async fn save(&self, client: &StorageClient, path: &str) -> Result<()> {
+        client.write(path, serde_json::to_vec(self)?).await
+    }
+
+    /// Read an index from storage
+    pub async fn load(client: &StorageClient, path: &str) -> Result<Self> {
+        Ok(serde_json::from_slice(&client.read(path).await?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::ParquetWriter;
+
+    #[tokio::test]
+    async fn test_metadata_index_lookup() {
+        let client = StorageClient::memory().unwrap();
+        let tag = |s: &str| Some(s.to_string());
+        let batch = ParquetWriter::new(&client, 2)
+            .create_batch(
+                &[5, 1, 3, 4, 2],
+                &vec![vec![1.0, 0.0]; 5],
+                &[tag("red"), tag("blue"), tag("red"), None, tag("blue")],
+            )
+            .unwrap();
+
+        let index = MetadataIndex::from_batches(&[batch]).unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.ids_for("red"), vec![3, 5]);
+        assert_eq!(index.ids_for("blue"), vec![1, 2]);
+        assert!(index.ids_for("green").is_empty());
+
+        index.save(&client, "data/part-0.tags.json").await.unwrap();
+        let loaded = MetadataIndex::load(&client, "data/part-0.tags.json")
+            .await
+            .unwrap();
+        assert_eq!(loaded, index);
+    }
+}
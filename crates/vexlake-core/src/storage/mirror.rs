@@ -0,0 +1,121 @@
+//! Dual-write storage for backend migration
+//!
+//! Writes go to both a primary and a secondary backend, while reads come from
+//! the primary and fall back to the secondary. This allows moving a live
+//! deployment to a new backend without downtime.
+
+use opendal::Operator;
+
+use super::StorageClient;
+use crate::Result;
+
+/// Storage client that mirrors writes to a secondary backend
+pub struct MirroringStorageClient {
+    primary: StorageClient,
+    secondary: StorageClient,
+    require_secondary: bool,
+}
+
+impl MirroringStorageClient {
+    /// Create a mirroring client from primary and secondary operators
+    pub fn new(primary: Operator, secondary: Operator) -> Self {
+        Self {
+            primary: StorageClient::new(primary),
+            secondary: StorageClient::new(secondary),
+            require_secondary: false,
+        }
+    }
+
+    /// Fail writes when the secondary fails (by default they are only logged)
+    pub fn require_secondary(mut self, require: bool) -> Self {
+        self.require_secondary = require;
+        self
+    }
+
+    /// Get the primary client
+    pub fn primary(&self) -> &StorageClient {
+        &self.primary
+    }
+
+    /// Get the secondary client
+    pub fn secondary(&self) -> &StorageClient {
+        &self.secondary
+    }
+
+    /// Write data to both backends
+    pub async fn write(&self, path: &str, data: Vec<u8>) -> Result<()> {
+        self.primary.write(path, data.clone()).await?;
+        let mirrored = self.secondary.write(path, data).await;
+        self.check_secondary(path, mirrored)
+    }
+
+    /// Read data from the primary, falling back to the secondary
+    pub async fn read(&self, path: &str) -> Result<Vec<u8>> {
+        match self.primary.read(path).await {
+            Ok(data) => Ok(data),
+            Err(primary_err) => self.secondary.read(path).await.map_err(|_| primary_err),
+        }
+    }
+
+    /// Check if a path exists in either backend
+    pub async fn exists(&self, path: &str) -> Result<bool> {
+        if self.primary.exists(path).await? {
+            return Ok(true);
+        }
+        self.secondary.exists(path).await
+    }
+
+    /// Delete a path from both backends
+    pub async fn delete(&self, path: &str) -> Result<()> {
+        self.primary.delete(path).await?;
+        let mirrored = self.secondary.delete(path).await;
+        self.check_secondary(path, mirrored)
+    }
+
+    /// List objects under a prefix in the primary
+    pub async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        self.primary.list(prefix).await
+    }
+
+    fn check_secondary(&self, path: &str, result: Result<()>) -> Result<()> {
+        match result {
+            Err(e) if !self.require_secondary => {
+                tracing::warn!("secondary storage write failed for {}: {}", path, e);
+                Ok(())
+            }
+            other => other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::create_memory_operator;
+
+    #[tokio::test]
+    async fn test_mirroring_write_and_fallback() {
+        let primary = create_memory_operator().unwrap();
+        let secondary = create_memory_operator().unwrap();
+        let client = MirroringStorageClient::new(primary, secondary);
+
+        client.write("data/a", b"hello".to_vec()).await.unwrap();
+        assert_eq!(client.primary().read("data/a").await.unwrap(), b"hello");
+        assert_eq!(client.secondary().read("data/a").await.unwrap(), b"hello");
+
+        // Only the secondary has this object, e.g. not yet backfilled
+        client
+            .secondary()
+            .write("data/b", b"legacy".to_vec())
+            .await
+            .unwrap();
+        assert!(!client.primary().exists("data/b").await.unwrap());
+        assert!(client.exists("data/b").await.unwrap());
+        assert_eq!(client.read("data/b").await.unwrap(), b"legacy");
+
+        assert!(client.read("data/missing").await.is_err());
+
+        client.delete("data/a").await.unwrap();
+        assert!(!client.exists("data/a").await.unwrap());
+    }
+}
@@ -5,18 +5,60 @@
 //! - Index file management
 //! - Version metadata handling
 
+pub mod archive;
+pub mod cache;
+pub mod compaction;
+pub mod health;
 pub mod metadata;
+pub mod metadata_index;
+pub mod mirror;
+pub mod opendal_store;
 pub mod parquet;
+pub mod search;
 
+pub use archive::{export_archive, import_archive};
+pub use cache::{CacheStats, CachedStorageClient};
+pub use compaction::{CompactionPolicy, Compactor};
+pub use health::{storage_health, HealthReport};
 pub use metadata::{MetadataManager, VersionInfo};
+pub use metadata_index::MetadataIndex;
+pub use mirror::MirroringStorageClient;
 use opendal::Operator;
-pub use parquet::{ParquetReader, ParquetWriter, VexSchema};
+pub use opendal_store::OpendalStore;
+pub use parquet::{
+    extract_rows, extract_vectors, ParquetCompression, ParquetFileWriter, ParquetReader,
+    ParquetWriter, ParquetWriterConfig, VectorRows, VexRow, VexSchema,
+};
+pub use search::{search_version, search_version_tagged, TaggedResult};
+use std::future::IntoFuture;
+use std::io::Read;
+use std::ops::Range;
+use std::time::{Duration, Instant};
 
+use crate::metrics;
 use crate::{Error, Result};
 
+/// Which OpenDAL service a [`StorageConfig`] connects to
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum StorageBackend {
+    /// S3-compatible object store at `endpoint`/`bucket`
+    #[default]
+    S3,
+    /// Local filesystem under `root`
+    Fs { root: String },
+    /// In-process memory, lost on drop
+    Memory,
+}
+
 /// Storage configuration
+///
+/// Build one with [`StorageConfig::builder`] or [`StorageConfig::from_env`]
+/// to have the endpoint validated.
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct StorageConfig {
+    /// Backend service; the S3 fields below are only used by `S3`
+    #[serde(default)]
+    pub backend: StorageBackend,
     /// S3 endpoint URL
     pub endpoint: String,
     /// S3 bucket name
@@ -27,20 +69,153 @@ pub struct StorageConfig {
     pub secret_access_key: Option<String>,
     /// AWS region
     pub region: String,
+    /// Retries of a request that failed with a transient error
+    #[serde(default = "default_max_retries")]
+    pub max_retries: usize,
+    /// Delay before the first retry, doubled on each further attempt
+    #[serde(default = "default_base_backoff_ms")]
+    pub base_backoff_ms: u64,
+}
+
+fn default_max_retries() -> usize {
+    3
+}
+
+fn default_base_backoff_ms() -> u64 {
+    100
 }
 
 impl Default for StorageConfig {
     fn default() -> Self {
         Self {
+            backend: StorageBackend::default(),
             endpoint: "http://localhost:8333".to_string(),
             bucket: "vexlake".to_string(),
             access_key_id: None,
             secret_access_key: None,
             region: "us-east-1".to_string(),
+            max_retries: default_max_retries(),
+            base_backoff_ms: default_base_backoff_ms(),
         }
     }
 }
 
+impl StorageConfig {
+    /// Start a [`StorageConfigBuilder`] from the defaults
+    pub fn builder() -> StorageConfigBuilder {
+        StorageConfigBuilder::default()
+    }
+
+    /// Read an S3 configuration from the standard AWS environment variables
+    ///
+    /// Uses `AWS_ENDPOINT_URL`, `AWS_REGION` (or `AWS_DEFAULT_REGION`),
+    /// `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY`, plus `VEXLAKE_BUCKET`
+    /// for the bucket. Unset variables keep their default. Fails with
+    /// [`Error::InvalidConfig`] if the endpoint is not a valid URL.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// [`StorageConfig::from_env`] with variables looked up through `lookup`
+    fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let var = |name: &str| lookup(name).filter(|v| !v.is_empty());
+        let mut builder = Self::builder();
+        if let Some(endpoint) = var("AWS_ENDPOINT_URL") {
+            builder = builder.endpoint(endpoint);
+        }
+        if let Some(region) = var("AWS_REGION").or_else(|| var("AWS_DEFAULT_REGION")) {
+            builder = builder.region(region);
+        }
+        if let Some(bucket) = var("VEXLAKE_BUCKET") {
+            builder = builder.bucket(bucket);
+        }
+        if let Some(key) = var("AWS_ACCESS_KEY_ID") {
+            builder = builder.access_key_id(key);
+        }
+        if let Some(secret) = var("AWS_SECRET_ACCESS_KEY") {
+            builder = builder.secret_access_key(secret);
+        }
+        builder.build()
+    }
+}
+
+/// Chainable constructor for [`StorageConfig`]
+#[derive(Debug, Clone, Default)]
+pub struct StorageConfigBuilder {
+    config: StorageConfig,
+}
+
+impl StorageConfigBuilder {
+    /// Set the backend service
+    pub fn backend(mut self, backend: StorageBackend) -> Self {
+        self.config.backend = backend;
+        self
+    }
+
+    /// Set the S3 endpoint URL
+    pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.config.endpoint = endpoint.into();
+        self
+    }
+
+    /// Set the bucket name
+    pub fn bucket(mut self, bucket: impl Into<String>) -> Self {
+        self.config.bucket = bucket.into();
+        self
+    }
+
+    /// Set the access key ID
+    pub fn access_key_id(mut self, key: impl Into<String>) -> Self {
+        self.config.access_key_id = Some(key.into());
+        self
+    }
+
+    /// Set the secret access key
+    pub fn secret_access_key(mut self, secret: impl Into<String>) -> Self {
+        self.config.secret_access_key = Some(secret.into());
+        self
+    }
+
+    /// Set the region
+    pub fn region(mut self, region: impl Into<String>) -> Self {
+        self.config.region = region.into();
+        self
+    }
+
+    /// Set how transient failures are retried, as in [`StorageClient::with_retries`]
+    pub fn retries(mut self, max_retries: usize, base_backoff_ms: u64) -> Self {
+        self.config.max_retries = max_retries;
+        self.config.base_backoff_ms = base_backoff_ms;
+        self
+    }
+
+    /// Validate and return the configuration
+    ///
+    /// The endpoint must be an absolute URL with a host, such as
+    /// `http://localhost:8333`.
+    pub fn build(self) -> Result<StorageConfig> {
+        let endpoint = &self.config.endpoint;
+        let parsed = url::Url::parse(endpoint)
+            .map_err(|e| Error::InvalidConfig(format!("invalid endpoint {}: {}", endpoint, e)))?;
+        if !parsed.has_host() {
+            return Err(Error::InvalidConfig(format!(
+                "invalid endpoint {}: no host",
+                endpoint
+            )));
+        }
+        Ok(self.config)
+    }
+}
+
+/// Create an operator for the configured backend
+pub fn create_operator(config: &StorageConfig) -> Result<Operator> {
+    match &config.backend {
+        StorageBackend::S3 => create_s3_operator(config),
+        StorageBackend::Fs { root } => create_fs_operator(root),
+        StorageBackend::Memory => create_memory_operator(),
+    }
+}
+
 /// Create an S3 operator from configuration
 pub fn create_s3_operator(config: &StorageConfig) -> Result<Operator> {
     let mut builder = opendal::services::S3::default()
@@ -65,6 +240,15 @@ pub fn create_s3_operator(config: &StorageConfig) -> Result<Operator> {
     Ok(op)
 }
 
+/// Create an operator over a local directory
+pub fn create_fs_operator(root: &str) -> Result<Operator> {
+    let builder = opendal::services::Fs::default().root(root);
+    let op = Operator::new(builder)
+        .map_err(|e| Error::Storage(Box::new(e)))?
+        .finish();
+    Ok(op)
+}
+
 /// Create an in-memory operator for testing
 pub fn create_memory_operator() -> Result<Operator> {
     let builder = opendal::services::Memory::default();
@@ -75,20 +259,38 @@ pub fn create_memory_operator() -> Result<Operator> {
 }
 
 /// Storage client for VexLake operations
+#[derive(Clone)]
 pub struct StorageClient {
     operator: Operator,
+    max_retries: usize,
+    base_backoff: Duration,
 }
 
 impl StorageClient {
     /// Create a new storage client
     pub fn new(operator: Operator) -> Self {
-        Self { operator }
+        Self {
+            operator,
+            max_retries: default_max_retries(),
+            base_backoff: Duration::from_millis(default_base_backoff_ms()),
+        }
     }
 
-    /// Create from S3 configuration
+    /// Create from configuration, using the configured backend
     pub fn from_config(config: &StorageConfig) -> Result<Self> {
-        let operator = create_s3_operator(config)?;
-        Ok(Self::new(operator))
+        let operator = create_operator(config)?;
+        Ok(Self::new(operator).with_retries(config.max_retries, config.base_backoff_ms))
+    }
+
+    /// Set how transient failures of `read`, `write`, `delete` and `list`
+    /// are retried
+    ///
+    /// A failed attempt waits `base_backoff_ms * 2^attempt`, jittered down by
+    /// up to half, before retrying. Zero retries fails immediately.
+    pub fn with_retries(mut self, max_retries: usize, base_backoff_ms: u64) -> Self {
+        self.max_retries = max_retries;
+        self.base_backoff = Duration::from_millis(base_backoff_ms);
+        self
     }
 
     /// Create an in-memory client for testing
@@ -102,55 +304,557 @@ impl StorageClient {
         &self.operator
     }
 
+    /// Run a storage request, counting it in the request metrics
+    async fn tracked<T>(&self, request: impl IntoFuture<Output = opendal::Result<T>>) -> Result<T> {
+        let requests = &metrics::global().storage_requests;
+        let _in_flight = requests.start();
+        request.await.map_err(|e| {
+            requests.record_error(e.kind());
+            Error::Storage(Box::new(e))
+        })
+    }
+
+    /// Run a storage request, retrying transient failures with backoff
+    ///
+    /// Rate limiting and errors opendal marks as temporary (5xx responses,
+    /// connection resets) are retried; anything else fails fast.
+    async fn retrying<T, F, R>(&self, request: F) -> Result<T>
+    where
+        F: Fn() -> R,
+        R: IntoFuture<Output = opendal::Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match self.tracked(request()).await {
+                Err(Error::Storage(e)) if attempt < self.max_retries && is_retryable(&e) => {
+                    tokio::time::sleep(self.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn backoff(&self, attempt: usize) -> Duration {
+        let ceiling = self.base_backoff.saturating_mul(1 << attempt.min(16));
+        ceiling.mul_f64(1.0 - rand::random::<f64>() / 2.0)
+    }
+
     /// Write data to storage
     pub async fn write(&self, path: &str, data: Vec<u8>) -> Result<()> {
-        self.operator
-            .write(path, data)
-            .await
-            .map_err(|e| Error::Storage(Box::new(e)))
+        let start = Instant::now();
+        let data = opendal::Buffer::from(data);
+        let request = self.retrying(|| self.operator.write(path, data.clone()));
+        #[cfg(feature = "tracing")]
+        let request = tracing::Instrument::instrument(
+            request,
+            tracing::debug_span!("storage_write", path, bytes = data.len()),
+        );
+        let result = request.await;
+        metrics::global().storage_write.record(start.elapsed());
+        #[cfg(feature = "tracing")]
+        trace_request("write", path, data.len(), start.elapsed(), &result);
+        result
+    }
+
+    /// Write data only if nothing exists at `path` yet
+    ///
+    /// Returns `false` without writing if the object already exists. Uses an
+    /// `If-None-Match: *` conditional write where the backend supports it, so
+    /// it is atomic across processes. Other backends fall back to a check then
+    /// write that is only serialized within this process.
+    pub async fn write_if_absent(&self, path: &str, data: Vec<u8>) -> Result<bool> {
+        static FALLBACK_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+        let start = Instant::now();
+        let result = if self
+            .operator
+            .info()
+            .full_capability()
+            .write_with_if_none_match
+        {
+            match self
+                .tracked(self.operator.write_with(path, data).if_none_match("*"))
+                .await
+            {
+                Ok(()) => Ok(true),
+                Err(Error::Storage(e)) if e.kind() == opendal::ErrorKind::ConditionNotMatch => {
+                    Ok(false)
+                }
+                Err(e) => Err(e),
+            }
+        } else {
+            let _guard = FALLBACK_LOCK.lock().await;
+            if self.exists(path).await? {
+                Ok(false)
+            } else {
+                self.tracked(self.operator.write(path, data))
+                    .await
+                    .map(|()| true)
+            }
+        };
+        metrics::global().storage_write.record(start.elapsed());
+        result
     }
 
     /// Read data from storage
     pub async fn read(&self, path: &str) -> Result<Vec<u8>> {
-        self.operator
-            .read(path)
+        let start = Instant::now();
+        let request = self.retrying(|| self.operator.read(path));
+        #[cfg(feature = "tracing")]
+        let request =
+            tracing::Instrument::instrument(request, tracing::debug_span!("storage_read", path));
+        let result = request.await.map(|buf| buf.to_vec());
+        metrics::global().storage_read.record(start.elapsed());
+        #[cfg(feature = "tracing")]
+        trace_request(
+            "read",
+            path,
+            result.as_ref().map_or(0, Vec::len),
+            start.elapsed(),
+            &result,
+        );
+        result
+    }
+
+    /// Read a byte range of an object
+    ///
+    /// Fetches only the requested bytes. The range is checked against the
+    /// object size first, so one reaching past the end is an error rather
+    /// than a short read.
+    pub async fn read_range(&self, path: &str, range: Range<u64>) -> Result<Vec<u8>> {
+        if range.start > range.end {
+            return Err(Error::InvalidConfig(format!(
+                "invalid range {}..{} for {}",
+                range.start, range.end, path
+            )));
+        }
+        let size = self.stat(path).await?;
+        if range.end > size {
+            return Err(Error::InvalidConfig(format!(
+                "range {}..{} out of bounds for {} of {} bytes",
+                range.start, range.end, path, size
+            )));
+        }
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let start = Instant::now();
+        let result = self
+            .retrying(|| self.operator.read_with(path).range(range.clone()))
+            .await
+            .map(|buf| buf.to_vec());
+        metrics::global().storage_read.record(start.elapsed());
+        result
+    }
+
+    /// Get the size of an object in bytes
+    pub async fn stat(&self, path: &str) -> Result<u64> {
+        self.retrying(|| self.operator.stat(path))
             .await
-            .map(|buf| buf.to_vec())
-            .map_err(|e| Error::Storage(Box::new(e)))
+            .map(|meta| meta.content_length())
+    }
+
+    /// Read data from storage, decompressing gzip or zstd objects
+    ///
+    /// The compression is detected from the `.gz`/`.zst` extension or, failing
+    /// that, from the magic bytes. Anything else is returned as stored.
+    pub async fn read_decompressed(&self, path: &str) -> Result<Vec<u8>> {
+        let data = self.read(path).await?;
+        decompress(path, data)
     }
 
     /// Check if a path exists
     pub async fn exists(&self, path: &str) -> Result<bool> {
-        self.operator
-            .exists(path)
-            .await
-            .map_err(|e| Error::Storage(Box::new(e)))
+        self.tracked(self.operator.exists(path)).await
     }
 
     /// Delete a path
     pub async fn delete(&self, path: &str) -> Result<()> {
-        self.operator
-            .delete(path)
-            .await
-            .map_err(|e| Error::Storage(Box::new(e)))
+        self.retrying(|| self.operator.delete(path)).await
     }
 
     /// Delete all objects under a prefix
     pub async fn delete_all(&self, prefix: &str) -> Result<()> {
-        self.operator
-            .remove_all(prefix)
-            .await
-            .map_err(|e| Error::Storage(Box::new(e)))
+        self.tracked(self.operator.remove_all(prefix)).await
     }
 
     /// List objects under a prefix
     pub async fn list(&self, prefix: &str) -> Result<Vec<String>> {
-        let entries = self
-            .operator
-            .list(prefix)
-            .await
-            .map_err(|e| Error::Storage(Box::new(e)))?;
+        let entries = self.retrying(|| self.operator.list(prefix)).await?;
 
         Ok(entries.into_iter().map(|e| e.path().to_string()).collect())
     }
 }
+
+/// Log a finished storage request with its size and duration
+#[cfg(feature = "tracing")]
+fn trace_request<T>(op: &str, path: &str, bytes: usize, elapsed: Duration, result: &Result<T>) {
+    let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+    match result {
+        Ok(_) => tracing::debug!(op, path, bytes, elapsed_ms, "storage request"),
+        Err(e) => tracing::warn!(op, path, elapsed_ms, error = %e, "storage request failed"),
+    }
+}
+
+fn is_retryable(err: &opendal::Error) -> bool {
+    err.is_temporary() || err.kind() == opendal::ErrorKind::RateLimited
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Debug, PartialEq)]
+enum Compression {
+    Gzip,
+    Zstd,
+}
+
+fn detect_compression(path: &str, data: &[u8]) -> Option<Compression> {
+    let extension = path
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("gz" | "gzip") => Some(Compression::Gzip),
+        Some("zst" | "zstd") => Some(Compression::Zstd),
+        _ if data.starts_with(&GZIP_MAGIC) => Some(Compression::Gzip),
+        _ if data.starts_with(&ZSTD_MAGIC) => Some(Compression::Zstd),
+        _ => None,
+    }
+}
+
+fn decompress(path: &str, data: Vec<u8>) -> Result<Vec<u8>> {
+    let decoded = match detect_compression(path, &data) {
+        Some(Compression::Gzip) => {
+            let mut out = Vec::new();
+            flate2::read::MultiGzDecoder::new(data.as_slice())
+                .read_to_end(&mut out)
+                .map(|_| out)
+        }
+        Some(Compression::Zstd) => zstd::decode_all(data.as_slice()),
+        None => return Ok(data),
+    };
+    decoded.map_err(|e| Error::Decompression(format!("{}: {}", path, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_storage_request_metrics() {
+        let client = std::sync::Arc::new(StorageClient::memory().unwrap());
+        let requests = &metrics::global().storage_requests;
+        // Other tests share the global counters, so only check lower bounds
+        let before = requests.summary();
+
+        let tasks: Vec<_> = (0..8)
+            .map(|i| {
+                let client = std::sync::Arc::clone(&client);
+                tokio::spawn(async move {
+                    let path = format!("metrics/{}", i);
+                    client.write(&path, vec![i as u8]).await.unwrap();
+                    assert_eq!(client.read(&path).await.unwrap(), vec![i as u8]);
+                    assert!(client.read("metrics/missing").await.is_err());
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let after = requests.summary();
+        assert!(after.total >= before.total + 24);
+        assert!(after.errors.not_found >= before.errors.not_found + 8);
+        assert_eq!(
+            after.errors.permission_denied,
+            before.errors.permission_denied
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&metrics::global().to_json()).unwrap();
+        assert!(json["storage_requests"]["total"].as_u64().unwrap() >= 24);
+    }
+
+    #[tokio::test]
+    async fn test_read_decompressed() {
+        use std::io::Write;
+
+        let client = StorageClient::memory().unwrap();
+        let original = b"vexlake compressed payload ".repeat(100);
+
+        let zstd_bytes = zstd::encode_all(original.as_slice(), 3).unwrap();
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&original).unwrap();
+        let gzip_bytes = gz.finish().unwrap();
+
+        client
+            .write("a.parquet.zst", zstd_bytes.clone())
+            .await
+            .unwrap();
+        client.write("b.gz", gzip_bytes.clone()).await.unwrap();
+        // No telling extension: detected by magic bytes
+        client.write("c.bin", zstd_bytes).await.unwrap();
+        client.write("d.bin", gzip_bytes).await.unwrap();
+        client.write("e.bin", original.clone()).await.unwrap();
+
+        for path in ["a.parquet.zst", "b.gz", "c.bin", "d.bin", "e.bin"] {
+            assert_eq!(client.read_decompressed(path).await.unwrap(), original);
+        }
+
+        client.write("bad.zst", b"not zstd".to_vec()).await.unwrap();
+        assert!(matches!(
+            client.read_decompressed("bad.zst").await,
+            Err(Error::Decompression(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fs_backend_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = StorageConfig {
+            backend: StorageBackend::Fs {
+                root: dir.path().to_string_lossy().into_owned(),
+            },
+            ..Default::default()
+        };
+        let client = StorageClient::from_config(&config).unwrap();
+
+        client.write("vectors/a.bin", vec![1, 2, 3]).await.unwrap();
+        assert_eq!(client.read("vectors/a.bin").await.unwrap(), vec![1, 2, 3]);
+        assert!(dir.path().join("vectors/a.bin").is_file());
+        let listed = client.list("vectors/").await.unwrap();
+        assert!(listed.contains(&"vectors/a.bin".to_string()));
+
+        client.delete("vectors/a.bin").await.unwrap();
+        assert!(!client.exists("vectors/a.bin").await.unwrap());
+    }
+
+    #[test]
+    fn test_storage_config_from_env_and_builder() {
+        // The process environment is shared with concurrent tests, so
+        // exercise the lookup through a map
+        let mut vars = HashMap::from([
+            ("AWS_ENDPOINT_URL", "https://s3.example.com:9000"),
+            ("AWS_DEFAULT_REGION", "eu-west-1"),
+            ("AWS_ACCESS_KEY_ID", "AKIDEXAMPLE"),
+            ("AWS_SECRET_ACCESS_KEY", "secret"),
+            ("VEXLAKE_BUCKET", "vectors"),
+            ("AWS_REGION", ""),
+        ]);
+        let from = |vars: &HashMap<&str, &str>| {
+            StorageConfig::from_vars(|name| vars.get(name).map(|v| v.to_string()))
+        };
+        let config = from(&vars).unwrap();
+        assert_eq!(config.endpoint, "https://s3.example.com:9000");
+        assert_eq!(config.region, "eu-west-1");
+        assert_eq!(config.bucket, "vectors");
+        assert_eq!(config.access_key_id.as_deref(), Some("AKIDEXAMPLE"));
+        assert_eq!(config.secret_access_key.as_deref(), Some("secret"));
+
+        vars.insert("AWS_ENDPOINT_URL", "not a url");
+        assert!(matches!(from(&vars), Err(Error::InvalidConfig(_))));
+
+        let config = StorageConfig::builder()
+            .endpoint("http://seaweed:8333")
+            .bucket("b")
+            .retries(5, 10)
+            .build()
+            .unwrap();
+        assert_eq!(config.endpoint, "http://seaweed:8333");
+        assert_eq!(config.max_retries, 5);
+        assert_eq!(config.region, StorageConfig::default().region);
+        assert!(StorageConfig::builder()
+            .endpoint("localhost")
+            .build()
+            .is_err());
+        assert!(StorageConfig::builder().endpoint("").build().is_err());
+    }
+
+    #[test]
+    fn test_backend_defaults_to_s3() {
+        let config: StorageConfig = serde_json::from_str(
+            r#"{"endpoint":"http://s3","bucket":"b","access_key_id":null,"secret_access_key":null,"region":"r"}"#,
+        )
+        .unwrap();
+        assert_eq!(config.backend, StorageBackend::S3);
+    }
+
+    #[tokio::test]
+    async fn test_read_range_and_stat() {
+        let client = StorageClient::memory().unwrap();
+        let blob: Vec<u8> = (0..100u8).collect();
+        client.write("blobs/a", blob.clone()).await.unwrap();
+
+        assert_eq!(client.stat("blobs/a").await.unwrap(), 100);
+        assert_eq!(
+            client.read_range("blobs/a", 10..20).await.unwrap(),
+            blob[10..20]
+        );
+        assert_eq!(
+            client.read_range("blobs/a", 90..100).await.unwrap(),
+            blob[90..]
+        );
+        assert!(client.read_range("blobs/a", 5..5).await.unwrap().is_empty());
+
+        for range in [95..105, 100..110, 200..210] {
+            let err = client.read_range("blobs/a", range).await.unwrap_err();
+            assert!(matches!(err, Error::InvalidConfig(ref m) if m.contains("out of bounds")));
+        }
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 20..10;
+        assert!(client.read_range("blobs/a", reversed).await.is_err());
+        assert!(matches!(
+            client.stat("blobs/missing").await,
+            Err(Error::Storage(_))
+        ));
+    }
+
+    /// Layer failing the first `failures` writes and reads with `error`
+    #[derive(Debug, Clone)]
+    struct FlakyLayer {
+        failures: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+        attempts: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+        error: fn() -> opendal::Error,
+    }
+
+    #[derive(Debug)]
+    struct FlakyAccessor<A> {
+        inner: A,
+        layer: FlakyLayer,
+    }
+
+    impl<A: opendal::raw::Access> opendal::raw::Layer<A> for FlakyLayer {
+        type LayeredAccess = FlakyAccessor<A>;
+
+        fn layer(&self, inner: A) -> Self::LayeredAccess {
+            FlakyAccessor {
+                inner,
+                layer: self.clone(),
+            }
+        }
+    }
+
+    impl FlakyLayer {
+        /// Count an attempt and tell whether it should fail
+        fn should_fail(&self) -> bool {
+            use std::sync::atomic::Ordering;
+
+            self.attempts.fetch_add(1, Ordering::SeqCst);
+            self.failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+        }
+    }
+
+    impl<A: opendal::raw::Access> opendal::raw::LayeredAccess for FlakyAccessor<A> {
+        type Inner = A;
+        type Reader = A::Reader;
+        type BlockingReader = A::BlockingReader;
+        type Writer = A::Writer;
+        type BlockingWriter = A::BlockingWriter;
+        type Lister = A::Lister;
+        type BlockingLister = A::BlockingLister;
+
+        fn inner(&self) -> &Self::Inner {
+            &self.inner
+        }
+
+        async fn read(
+            &self,
+            path: &str,
+            args: opendal::raw::OpRead,
+        ) -> opendal::Result<(opendal::raw::RpRead, Self::Reader)> {
+            if self.layer.should_fail() {
+                return Err((self.layer.error)());
+            }
+            self.inner.read(path, args).await
+        }
+
+        async fn write(
+            &self,
+            path: &str,
+            args: opendal::raw::OpWrite,
+        ) -> opendal::Result<(opendal::raw::RpWrite, Self::Writer)> {
+            if self.layer.should_fail() {
+                return Err((self.layer.error)());
+            }
+            self.inner.write(path, args).await
+        }
+
+        async fn list(
+            &self,
+            path: &str,
+            args: opendal::raw::OpList,
+        ) -> opendal::Result<(opendal::raw::RpList, Self::Lister)> {
+            self.inner.list(path, args).await
+        }
+
+        fn blocking_read(
+            &self,
+            path: &str,
+            args: opendal::raw::OpRead,
+        ) -> opendal::Result<(opendal::raw::RpRead, Self::BlockingReader)> {
+            self.inner.blocking_read(path, args)
+        }
+
+        fn blocking_write(
+            &self,
+            path: &str,
+            args: opendal::raw::OpWrite,
+        ) -> opendal::Result<(opendal::raw::RpWrite, Self::BlockingWriter)> {
+            self.inner.blocking_write(path, args)
+        }
+
+        fn blocking_list(
+            &self,
+            path: &str,
+            args: opendal::raw::OpList,
+        ) -> opendal::Result<(opendal::raw::RpList, Self::BlockingLister)> {
+            self.inner.blocking_list(path, args)
+        }
+    }
+
+    fn flaky_client(failures: usize, error: fn() -> opendal::Error) -> (StorageClient, FlakyLayer) {
+        let layer = FlakyLayer {
+            failures: std::sync::Arc::new(failures.into()),
+            attempts: Default::default(),
+            error,
+        };
+        let operator = create_memory_operator().unwrap().layer(layer.clone());
+        (StorageClient::new(operator).with_retries(3, 1), layer)
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient_errors() {
+        use std::sync::atomic::Ordering;
+
+        let reset = || {
+            opendal::Error::new(opendal::ErrorKind::Unexpected, "connection reset").set_temporary()
+        };
+        let (client, layer) = flaky_client(2, reset);
+        client.write("retry/a", vec![1, 2, 3]).await.unwrap();
+        assert_eq!(layer.attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(client.read("retry/a").await.unwrap(), vec![1, 2, 3]);
+
+        // Retries are bounded
+        let (client, layer) = flaky_client(10, reset);
+        assert!(client.write("retry/b", vec![1]).await.is_err());
+        assert_eq!(layer.attempts.load(Ordering::SeqCst), 4);
+
+        let throttled = || opendal::Error::new(opendal::ErrorKind::RateLimited, "slow down");
+        let (client, _) = flaky_client(1, throttled);
+        client.write("retry/c", vec![1]).await.unwrap();
+
+        // Permanent errors fail fast
+        let denied = || opendal::Error::new(opendal::ErrorKind::PermissionDenied, "denied");
+        let (client, layer) = flaky_client(1, denied);
+        assert!(client.write("retry/d", vec![1]).await.is_err());
+        assert_eq!(layer.attempts.load(Ordering::SeqCst), 1);
+
+        let (client, layer) = flaky_client(0, reset);
+        assert!(client.read("retry/missing").await.is_err());
+        assert_eq!(layer.attempts.load(Ordering::SeqCst), 1);
+    }
+}
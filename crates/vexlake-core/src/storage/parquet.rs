@@ -4,11 +4,18 @@
 //! reading and writing vector data in Parquet format.
 
 use arrow::array::{
-    ArrayRef, FixedSizeListArray, Float32Array, RecordBatch, StringArray, UInt64Array,
+    new_null_array, Array, ArrayRef, FixedSizeListArray, Float32Array, RecordBatch, StringArray,
+    UInt64Array,
 };
+use arrow::compute::concat_batches;
 use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use futures::future::BoxFuture;
+use parquet::arrow::async_reader::{ParquetObjectReader, ParquetRecordBatchStream};
+use parquet::arrow::ParquetRecordBatchStreamBuilder;
 use std::sync::Arc;
 
+use super::metadata::VersionInfo;
+use super::opendal_store::{OpendalStore, STORE_URL};
 use super::StorageClient;
 use crate::{Error, Result};
 
@@ -31,19 +38,256 @@ impl VexSchema {
             Field::new("metadata", DataType::Utf8, true),
         ]))
     }
+
+    /// Check that `schema` holds VexLake vector data of `dimension`
+    ///
+    /// Columns are matched by name and extra columns are ignored. `metadata`
+    /// may be absent, as [`extract_rows`] reads it as all-null. A vector
+    /// column of another list size fails with [`Error::DimensionMismatch`].
+    pub fn validate(schema: &Schema, dimension: usize) -> Result<()> {
+        let column = |name: &str| {
+            schema
+                .field_with_name(name)
+                .map_err(|_| Error::InvalidConfig(format!("missing column: {}", name)))
+        };
+        let wrong_type = |name: &str, expected: &str, actual: &DataType| {
+            Error::InvalidConfig(format!(
+                "column {} must be {}, found {}",
+                name, expected, actual
+            ))
+        };
+
+        let id = column("id")?;
+        if id.data_type() != &DataType::UInt64 {
+            return Err(wrong_type("id", "UInt64", id.data_type()));
+        }
+
+        let vector = column("vector")?;
+        match vector.data_type() {
+            DataType::FixedSizeList(item, size) if item.data_type() == &DataType::Float32 => {
+                if *size as usize != dimension {
+                    return Err(Error::DimensionMismatch {
+                        expected: dimension,
+                        actual: *size as usize,
+                    });
+                }
+            }
+            other => return Err(wrong_type("vector", "FixedSizeList of Float32", other)),
+        }
+
+        if let Ok(metadata) = schema.field_with_name("metadata") {
+            if metadata.data_type() != &DataType::Utf8 {
+                return Err(wrong_type("metadata", "Utf8", metadata.data_type()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Extract `(id, vector)` pairs from a VexLake RecordBatch
+///
+/// Columns are looked up by name rather than position, so batches whose
+/// fields are ordered differently from [`VexSchema`] are still accepted.
+pub fn extract_vectors(batch: &RecordBatch) -> Result<Vec<(u64, Vec<f32>)>> {
+    let ids = batch
+        .column_by_name("id")
+        .ok_or_else(|| Error::InvalidConfig("missing column: id".to_string()))?
+        .as_any()
+        .downcast_ref::<UInt64Array>()
+        .ok_or_else(|| Error::InvalidConfig("column id must be UInt64".to_string()))?;
+
+    let vectors = batch
+        .column_by_name("vector")
+        .ok_or_else(|| Error::InvalidConfig("missing column: vector".to_string()))?
+        .as_any()
+        .downcast_ref::<FixedSizeListArray>()
+        .ok_or_else(|| Error::InvalidConfig("column vector must be FixedSizeList".to_string()))?;
+
+    let values = vectors
+        .values()
+        .as_any()
+        .downcast_ref::<Float32Array>()
+        .ok_or_else(|| Error::InvalidConfig("column vector must contain Float32".to_string()))?;
+
+    let dimension = vectors.value_length() as usize;
+    Ok(ids
+        .values()
+        .iter()
+        .enumerate()
+        .map(|(row, id)| {
+            let start = vectors.value_offset(row) as usize;
+            (*id, values.values()[start..start + dimension].to_vec())
+        })
+        .collect())
+}
+
+/// One decoded row of VexLake vector data
+#[derive(Debug, Clone, PartialEq)]
+pub struct VexRow {
+    /// Vector ID
+    pub id: u64,
+    /// Vector components
+    pub vector: Vec<f32>,
+    /// Optional metadata (None when null)
+    pub metadata: Option<String>,
+}
+
+/// Decode a VexLake RecordBatch into rows
+///
+/// Fails with [`Error::DimensionMismatch`] if the `vector` column's list size
+/// differs from `dimension`. A missing `metadata` column reads as all-null.
+pub fn extract_rows(batch: &RecordBatch, dimension: usize) -> Result<Vec<VexRow>> {
+    if let Some(DataType::FixedSizeList(_, size)) = batch
+        .column_by_name("vector")
+        .map(|c| c.data_type().clone())
+    {
+        if size as usize != dimension {
+            return Err(Error::DimensionMismatch {
+                expected: dimension,
+                actual: size as usize,
+            });
+        }
+    }
+    let vectors = extract_vectors(batch)?;
+
+    let metadata = match batch.column_by_name("metadata") {
+        Some(column) => Some(
+            column
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .ok_or_else(|| Error::InvalidConfig("column metadata must be Utf8".to_string()))?,
+        ),
+        None => None,
+    };
+
+    Ok(vectors
+        .into_iter()
+        .enumerate()
+        .map(|(row, (id, vector))| VexRow {
+            id,
+            vector,
+            metadata: metadata
+                .filter(|m| m.is_valid(row))
+                .map(|m| m.value(row).to_string()),
+        })
+        .collect())
+}
+
+/// Compression codec for written Parquet files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParquetCompression {
+    /// Snappy: fast, moderate ratio
+    Snappy,
+    /// Zstandard at the default level: slower, better ratio
+    Zstd,
+    /// No compression
+    #[default]
+    None,
+}
+
+/// Settings for written Parquet files
+#[derive(Debug, Clone)]
+pub struct ParquetWriterConfig {
+    /// Maximum rows per row group; a row group is flushed once it fills up
+    pub max_row_group_size: usize,
+    /// Compression codec for all columns
+    pub compression: ParquetCompression,
+}
+
+impl Default for ParquetWriterConfig {
+    fn default() -> Self {
+        Self {
+            max_row_group_size: parquet::file::properties::DEFAULT_MAX_ROW_GROUP_SIZE,
+            compression: ParquetCompression::None,
+        }
+    }
+}
+
+impl ParquetWriterConfig {
+    /// Build the equivalent Parquet writer properties
+    pub fn writer_properties(&self) -> parquet::file::properties::WriterProperties {
+        use parquet::basic::{Compression, ZstdLevel};
+
+        let compression = match self.compression {
+            ParquetCompression::Snappy => Compression::SNAPPY,
+            ParquetCompression::Zstd => Compression::ZSTD(ZstdLevel::default()),
+            ParquetCompression::None => Compression::UNCOMPRESSED,
+        };
+        parquet::file::properties::WriterProperties::builder()
+            .set_max_row_group_size(self.max_row_group_size)
+            .set_compression(compression)
+            .build()
+    }
 }
 
 /// Writer for VexLake Parquet files
 pub struct ParquetWriter<'a> {
-    #[allow(dead_code)]
     client: &'a StorageClient,
     dimension: usize,
+    config: ParquetWriterConfig,
 }
 
 impl<'a> ParquetWriter<'a> {
     /// Create a new Parquet writer
     pub fn new(client: &'a StorageClient, dimension: usize) -> Self {
-        Self { client, dimension }
+        Self {
+            client,
+            dimension,
+            config: ParquetWriterConfig::default(),
+        }
+    }
+
+    /// Use the given row group and compression settings
+    pub fn with_config(mut self, config: ParquetWriterConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Open a file for incremental writing
+    ///
+    /// Batches passed to [`ParquetFileWriter::write_batch`] are streamed to
+    /// storage one row group at a time. The object only becomes visible once
+    /// [`ParquetFileWriter::close`] succeeds.
+    pub async fn open(&self, path: &str) -> Result<ParquetFileWriter> {
+        self.open_with_schema(path, VexSchema::get(self.dimension))
+            .await
+    }
+
+    /// [`open`](Self::open) for batches of an arbitrary schema
+    ///
+    /// For auxiliary files such as search exports; the writer's dimension is
+    /// ignored.
+    pub async fn open_with_schema(
+        &self,
+        path: &str,
+        schema: SchemaRef,
+    ) -> Result<ParquetFileWriter> {
+        use parquet::arrow::AsyncArrowWriter;
+
+        if self.config.max_row_group_size == 0 {
+            return Err(Error::InvalidConfig(
+                "max_row_group_size must be positive".to_string(),
+            ));
+        }
+        let sink = self
+            .client
+            .operator()
+            .writer_with(path)
+            .chunk(WRITE_CHUNK_SIZE)
+            .await
+            .map_err(|e| Error::Storage(Box::new(e)))?;
+        let writer = AsyncArrowWriter::try_new(
+            StorageSink(sink),
+            schema,
+            Some(self.config.writer_properties()),
+        )
+        .map_err(|e| Error::Index(e.to_string()))?;
+
+        Ok(ParquetFileWriter {
+            writer,
+            path: path.to_string(),
+            rows: 0,
+        })
     }
 
     /// Create a RecordBatch from raw vector data
@@ -100,7 +344,8 @@ impl<'a> ParquetWriter<'a> {
         use parquet::arrow::AsyncArrowWriter;
 
         let mut buf = Vec::new();
-        let mut writer = AsyncArrowWriter::try_new(&mut buf, batch.schema(), None)
+        let props = self.config.writer_properties();
+        let mut writer = AsyncArrowWriter::try_new(&mut buf, batch.schema(), Some(props))
             .map_err(|e| Error::Index(e.to_string()))?;
 
         writer
@@ -115,6 +360,156 @@ impl<'a> ParquetWriter<'a> {
         self.client.write(path, buf).await?;
         Ok(())
     }
+
+    /// Append a RecordBatch to an existing Parquet file, creating it if missing
+    ///
+    /// Supports additive schema evolution: columns only present on one side
+    /// are backfilled with nulls on the other. The `id` and `vector` columns
+    /// must be present on both sides, and a column present on both must keep
+    /// its type, so changing the vector dimension is rejected.
+    pub async fn append(&self, path: &str, batch: &RecordBatch) -> Result<()> {
+        if !self.client.exists(path).await? {
+            return self.write_batch(path, batch).await;
+        }
+
+        let existing = ParquetReader::new(self.client).read_all(path).await?;
+        let old_schema = match existing.first() {
+            Some(first) => first.schema(),
+            None => return self.write_batch(path, batch).await,
+        };
+        let schema = evolve_schema(&old_schema, &batch.schema())?;
+
+        let mut batches = Vec::with_capacity(existing.len() + 1);
+        for b in existing.iter().chain(std::iter::once(batch)) {
+            batches.push(conform_batch(b, &schema)?);
+        }
+        let merged = concat_batches(&schema, &batches).map_err(Error::Arrow)?;
+        self.write_batch(path, &merged).await
+    }
+}
+
+/// Buffer size for multipart uploads of incrementally written files
+pub(super) const WRITE_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Streams encoded Parquet bytes into a storage object
+struct StorageSink(opendal::Writer);
+
+impl parquet::arrow::async_writer::AsyncFileWriter for StorageSink {
+    fn write(&mut self, bs: bytes::Bytes) -> BoxFuture<'_, parquet::errors::Result<()>> {
+        Box::pin(async move {
+            self.0
+                .write(bs)
+                .await
+                .map_err(|e| parquet::errors::ParquetError::External(Box::new(e)))
+        })
+    }
+
+    fn complete(&mut self) -> BoxFuture<'_, parquet::errors::Result<()>> {
+        Box::pin(async move {
+            let start = std::time::Instant::now();
+            let result = self
+                .0
+                .close()
+                .await
+                .map_err(|e| parquet::errors::ParquetError::External(Box::new(e)));
+            crate::metrics::global()
+                .storage_write
+                .record(start.elapsed());
+            result
+        })
+    }
+}
+
+/// Stateful writer producing a single Parquet object from many batches
+///
+/// Created by [`ParquetWriter::open`]. Dropping it without calling
+/// [`close`](Self::close) abandons the object.
+pub struct ParquetFileWriter {
+    writer: parquet::arrow::AsyncArrowWriter<StorageSink>,
+    path: String,
+    rows: usize,
+}
+
+impl ParquetFileWriter {
+    /// Append a batch, flushing every full row group to storage
+    pub async fn write_batch(&mut self, batch: &RecordBatch) -> Result<()> {
+        self.writer
+            .write(batch)
+            .await
+            .map_err(|e| Error::Index(format!("writing {}: {}", self.path, e)))?;
+        self.rows += batch.num_rows();
+        Ok(())
+    }
+
+    /// Number of rows written so far
+    pub fn rows_written(&self) -> usize {
+        self.rows
+    }
+
+    /// Flush the last row group, write the footer and finalize the object
+    ///
+    /// Returns the total number of rows in the file.
+    pub async fn close(self) -> Result<usize> {
+        self.writer
+            .close()
+            .await
+            .map_err(|e| Error::Index(format!("closing {}: {}", self.path, e)))?;
+        Ok(self.rows)
+    }
+}
+
+/// Merge two schemas additively, keeping `old` field order first
+fn evolve_schema(old: &Schema, new: &Schema) -> Result<SchemaRef> {
+    for required in ["id", "vector"] {
+        if old.field_with_name(required).is_err() || new.field_with_name(required).is_err() {
+            return Err(Error::InvalidConfig(format!(
+                "column {} must be present in both schemas",
+                required
+            )));
+        }
+    }
+
+    let mut fields = Vec::new();
+    for field in old.fields() {
+        match new.field_with_name(field.name()) {
+            Ok(other) if other.data_type() != field.data_type() => {
+                return Err(Error::InvalidConfig(format!(
+                    "column {} changed type from {} to {}",
+                    field.name(),
+                    field.data_type(),
+                    other.data_type()
+                )));
+            }
+            Ok(other) => fields.push(
+                field
+                    .as_ref()
+                    .clone()
+                    .with_nullable(field.is_nullable() || other.is_nullable()),
+            ),
+            // Missing from the new batch: backfilled with nulls
+            Err(_) => fields.push(field.as_ref().clone().with_nullable(true)),
+        }
+    }
+    for field in new.fields() {
+        if old.field_with_name(field.name()).is_err() {
+            fields.push(field.as_ref().clone().with_nullable(true));
+        }
+    }
+
+    Ok(Arc::new(Schema::new(fields)))
+}
+
+/// Reorder a batch's columns to `schema`, filling absent columns with nulls
+fn conform_batch(batch: &RecordBatch, schema: &SchemaRef) -> Result<RecordBatch> {
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| match batch.column_by_name(field.name()) {
+            Some(column) => column.clone(),
+            None => new_null_array(field.data_type(), batch.num_rows()),
+        })
+        .collect();
+    RecordBatch::try_new(schema.clone(), columns).map_err(Error::Arrow)
 }
 
 use datafusion::physical_plan::collect;
@@ -132,62 +527,270 @@ impl<'a> ParquetReader<'a> {
     }
 
     /// Read all vectors from a Parquet file
+    ///
+    /// Streams the file with range requests through [`OpendalStore`], so the
+    /// object is fetched footer first and then row group by row group.
     pub async fn read_all(&self, path: &str) -> Result<Vec<RecordBatch>> {
-        // DataFusion SessionContext
-        let _ctx = SessionContext::new();
+        self.read_all_validated(path, None).await
+    }
+
+    /// Like [`read_all`](Self::read_all), but first checks the file schema
+    ///
+    /// The footer schema is validated with [`VexSchema::validate`] before any
+    /// row is decoded, so a file written with another dimension fails with
+    /// [`Error::DimensionMismatch`] instead of yielding mis-sized vectors.
+    pub async fn read_all_checked(
+        &self,
+        path: &str,
+        expected_dim: usize,
+    ) -> Result<Vec<RecordBatch>> {
+        self.read_all_validated(path, Some(expected_dim)).await
+    }
 
-        // Since we are using OpenDAL, for now we might need to read the whole file
-        // into memory or implement an ObjectStore for DataFusion.
-        // For simplicity in this phase, we'll read the file and use ctx.read_parquet with a local path
-        // OR better, we use RecordBatchReader from the parquet crate directly for now
-        // until we have the full DataFusion ObjectStore integrated.
+    async fn read_all_validated(
+        &self,
+        path: &str,
+        expected_dim: Option<usize>,
+    ) -> Result<Vec<RecordBatch>> {
+        use futures::TryStreamExt;
 
-        let data = self.client.read(path).await?;
-        let bytes = bytes::Bytes::from(data);
+        let builder = self.open_stream(path).await?;
+        if let Some(dimension) = expected_dim {
+            VexSchema::validate(builder.schema(), dimension)?;
+        }
+        let stream = builder.build().map_err(|e| corrupt(path, e))?;
 
-        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+        stream.try_collect().await.map_err(|e| corrupt(path, e))
+    }
 
-        let builder = ParquetRecordBatchReaderBuilder::try_new(bytes)
-            .map_err(|e| Error::Index(e.to_string()))?;
+    /// Start a ranged-read stream over a file, fetching only its footer
+    async fn open_stream(&self, path: &str) -> Result<ObjectStreamBuilder> {
+        let store = Arc::new(OpendalStore::new(self.client));
+        let meta = store.object_meta(path).await?;
+        ParquetRecordBatchStreamBuilder::new(ParquetObjectReader::new(store, meta))
+            .await
+            .map_err(|e| corrupt(path, e))
+    }
 
-        let reader = builder.build().map_err(|e| Error::Index(e.to_string()))?;
+    /// Read every row of a file as [`VexRow`]s
+    ///
+    /// Errors if the file's vector dimension is not `dimension`.
+    pub async fn read_rows(&self, path: &str, dimension: usize) -> Result<Vec<VexRow>> {
+        let mut rows = Vec::new();
+        for batch in self.read_all(path).await? {
+            rows.extend(extract_rows(&batch, dimension)?);
+        }
+        Ok(rows)
+    }
 
-        let mut batches = Vec::new();
-        for batch in reader {
-            batches.push(batch.map_err(Error::Arrow)?);
+    /// Iterate over the `(id, vector)` pairs of a file row by row
+    ///
+    /// Only the footer is fetched here. The iterator then fetches the `id`
+    /// and `vector` columns of one row group at a time with range requests
+    /// and decodes them one record batch at a time, so pairing it with
+    /// [`crate::vector::brute_force_topk_stream`] keeps memory at
+    /// O(k + batch). Each fetch blocks on the tokio runtime that created the
+    /// iterator, so consume it on a blocking thread, e.g. in
+    /// [`tokio::task::spawn_blocking`].
+    pub async fn vector_rows(&self, path: &str) -> Result<VectorRows> {
+        use parquet::arrow::ProjectionMask;
+
+        let builder = self.open_stream(path).await?;
+        let schema = builder.schema().clone();
+        let roots = ["id", "vector"]
+            .iter()
+            .map(|name| {
+                schema
+                    .index_of(name)
+                    .map_err(|_| Error::InvalidConfig(format!("missing column: {}", name)))
+            })
+            .collect::<Result<Vec<usize>>>()?;
+        let mask = ProjectionMask::roots(builder.parquet_schema(), roots);
+        let batches = builder
+            .with_projection(mask)
+            .build()
+            .map_err(|e| corrupt(path, e))?;
+
+        Ok(VectorRows {
+            batches,
+            runtime: tokio::runtime::Handle::current(),
+            path: path.to_string(),
+            rows: Vec::new().into_iter(),
+        })
+    }
+
+    /// Read only the named columns, optionally filtering rows
+    ///
+    /// Without a filter the Parquet projection mask skips the other columns
+    /// entirely, so reading `id` and `metadata` never decodes `vector`. A
+    /// filter is a SQL predicate such as `id BETWEEN 10 AND 20`, run through
+    /// DataFusion so it can prune row groups by their statistics. Columns come
+    /// back in the order given.
+    pub async fn read_projected(
+        &self,
+        path: &str,
+        columns: &[&str],
+        filter: Option<&str>,
+    ) -> Result<Vec<RecordBatch>> {
+        use futures::TryStreamExt;
+        use parquet::arrow::ProjectionMask;
+
+        if columns.is_empty() {
+            return Err(Error::InvalidConfig("no columns to read".to_string()));
+        }
+        if let Some(filter) = filter {
+            let select = columns
+                .iter()
+                .map(|c| format!("\"{}\"", c.replace('"', "\"\"")))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let sql = format!("SELECT {} FROM vectors WHERE {}", select, filter);
+            return self.query(path, &sql).await;
         }
 
+        let builder = self.open_stream(path).await?;
+        let schema = builder.schema().clone();
+        let mut roots = columns
+            .iter()
+            .map(|name| {
+                schema
+                    .index_of(name)
+                    .map_err(|_| Error::InvalidConfig(format!("unknown column: {}", name)))
+            })
+            .collect::<Result<Vec<usize>>>()?;
+        let mask = ProjectionMask::roots(builder.parquet_schema(), roots.iter().copied());
+
+        // The mask yields columns in file order; map them back to the request
+        let requested = roots.clone();
+        roots.sort_unstable();
+        roots.dedup();
+        let order: Vec<usize> = requested
+            .iter()
+            .map(|r| roots.binary_search(r).unwrap())
+            .collect();
+
+        let batches: Vec<RecordBatch> = builder
+            .with_projection(mask)
+            .build()
+            .map_err(|e| corrupt(path, e))?
+            .try_collect()
+            .await
+            .map_err(|e| corrupt(path, e))?;
+        batches
+            .iter()
+            .map(|b| b.project(&order).map_err(Error::Arrow))
+            .collect()
+    }
+
+    /// Read all vectors from a Parquet file, decoding row groups in parallel
+    ///
+    /// The footer is fetched and parsed once. Each row group is then fetched
+    /// with range requests and decoded in its own task on the tokio runtime,
+    /// at most `threads` at a time, so the decode only runs in parallel on a
+    /// multi-threaded runtime. Batches are returned in file order, the same
+    /// rows as [`Self::read_all`].
+    pub async fn read_all_parallel(&self, path: &str, threads: usize) -> Result<Vec<RecordBatch>> {
+        use futures::{StreamExt, TryStreamExt};
+        use parquet::arrow::arrow_reader::ArrowReaderMetadata;
+
+        let store = Arc::new(OpendalStore::new(self.client));
+        let meta = store.object_meta(path).await?;
+        let mut input = ParquetObjectReader::new(store, meta);
+        let metadata = ArrowReaderMetadata::load_async(&mut input, Default::default())
+            .await
+            .map_err(|e| corrupt(path, e))?;
+
+        let num_row_groups = metadata.metadata().num_row_groups();
+        let per_group: Vec<Vec<RecordBatch>> = futures::stream::iter(0..num_row_groups)
+            .map(|group| {
+                let builder = ParquetRecordBatchStreamBuilder::new_with_metadata(
+                    input.clone(),
+                    metadata.clone(),
+                );
+                let path = path.to_string();
+                tokio::spawn(async move {
+                    builder
+                        .with_row_groups(vec![group])
+                        .build()
+                        .map_err(|e| corrupt(&path, e))?
+                        .try_collect::<Vec<_>>()
+                        .await
+                        .map_err(|e| corrupt(&path, e))
+                })
+            })
+            .buffered(threads.max(1))
+            .map(|joined| joined.map_err(|e| Error::Index(e.to_string()))?)
+            .try_collect()
+            .await?;
+
+        Ok(per_group.into_iter().flatten().collect())
+    }
+
+    /// Read every data file of a version, in partition ID order
+    ///
+    /// Batches from each partition are concatenated into one list. An empty
+    /// version (such as version 0) yields no batches.
+    pub async fn read_version(&self, version: &VersionInfo) -> Result<Vec<RecordBatch>> {
+        let mut batches = Vec::new();
+        for path in partition_paths(version) {
+            batches.extend(self.read_all(path).await?);
+        }
         Ok(batches)
     }
 
+    /// Get the number of rows in a Parquet file from its footer
+    ///
+    /// Only the footer is fetched, with range requests; no row group is read.
+    pub async fn row_count(&self, path: &str) -> Result<usize> {
+        let builder = self.open_stream(path).await?;
+        Ok(builder.metadata().file_metadata().num_rows() as usize)
+    }
+
     /// Execute a query using DataFusion
+    ///
+    /// The file is registered as table `vectors` and read directly from
+    /// storage, so projections and row group pruning limit what is fetched.
     pub async fn query(&self, path: &str, sql: &str) -> Result<Vec<RecordBatch>> {
+        Ok(self.query_with_store(&[path], sql).await?.0)
+    }
+
+    /// Execute a query over all data files of a version
+    ///
+    /// The partitions are registered together as the single table `vectors`.
+    /// An empty version has no table to query and yields no batches.
+    pub async fn query_version(
+        &self,
+        version: &VersionInfo,
+        sql: &str,
+    ) -> Result<Vec<RecordBatch>> {
+        let paths = partition_paths(version);
+        if paths.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(self.query_with_store(&paths, sql).await?.0)
+    }
+
+    async fn query_with_store(
+        &self,
+        paths: &[&str],
+        sql: &str,
+    ) -> Result<(Vec<RecordBatch>, Arc<OpendalStore>)> {
         let ctx = SessionContext::new();
+        let store = Arc::new(OpendalStore::new(self.client));
+        let url = datafusion::execution::object_store::ObjectStoreUrl::parse(STORE_URL)
+            .map_err(|e| Error::Index(e.to_string()))?;
+        ctx.register_object_store(url.as_ref(), store.clone());
 
-        // We'll write to a temp file to allow DataFusion to read it
-        // TODO: In Phase 4, we will register an ObjectStore for direct S3 reading
-        let data = self.client.read(path).await?;
-        let temp_dir = tempfile::tempdir().map_err(|e| {
-            Error::Storage(Box::new(opendal::Error::new(
-                opendal::ErrorKind::Unexpected,
-                e.to_string(),
-            )))
-        })?;
-        let file_path = temp_dir.path().join("data.parquet");
-        std::fs::write(&file_path, data).map_err(|e| {
-            Error::Storage(Box::new(opendal::Error::new(
-                opendal::ErrorKind::Unexpected,
-                e.to_string(),
-            )))
-        })?;
-
-        ctx.register_parquet(
-            "vectors",
-            file_path.to_str().unwrap(),
-            ParquetReadOptions::default(),
-        )
-        .await
-        .map_err(|e| Error::Index(e.to_string()))?;
+        let urls: Vec<String> = paths
+            .iter()
+            .map(|path| format!("{}/{}", STORE_URL, path))
+            .collect();
+        let table = ctx
+            .read_parquet(urls, ParquetReadOptions::default())
+            .await
+            .map_err(|e| Error::Index(e.to_string()))?;
+        ctx.register_table("vectors", table.into_view())
+            .map_err(|e| Error::Index(e.to_string()))?;
 
         let df = ctx
             .sql(sql)
@@ -202,10 +805,64 @@ impl<'a> ParquetReader<'a> {
         let result = collect(plan, task_ctx)
             .await
             .map_err(|e| Error::Index(e.to_string()))?;
-        Ok(result)
+        Ok((result, store))
     }
 }
 
+/// Row iterator returned by [`ParquetReader::vector_rows`]
+///
+/// A row group that fails to fetch or decode is reported as an `Err` item.
+/// Must not be advanced from within an async task.
+pub struct VectorRows {
+    batches: ParquetRecordBatchStream<ParquetObjectReader>,
+    runtime: tokio::runtime::Handle,
+    path: String,
+    rows: std::vec::IntoIter<(u64, Vec<f32>)>,
+}
+
+impl Iterator for VectorRows {
+    type Item = Result<(u64, Vec<f32>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use futures::StreamExt;
+
+        loop {
+            if let Some(row) = self.rows.next() {
+                return Some(Ok(row));
+            }
+            let decoded = self
+                .runtime
+                .block_on(self.batches.next())?
+                .map_err(|e| corrupt(&self.path, e))
+                .and_then(|batch| extract_vectors(&batch));
+            match decoded {
+                Ok(rows) => self.rows = rows.into_iter(),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Stream builder over a storage object read with range requests
+type ObjectStreamBuilder = ParquetRecordBatchStreamBuilder<ParquetObjectReader>;
+
+/// Error for a file whose footer or data cannot be decoded
+///
+/// A missing or damaged footer usually means an interrupted upload.
+fn corrupt(path: &str, e: impl std::fmt::Display) -> Error {
+    Error::InvalidConfig(format!("corrupt or truncated parquet: {}: {}", path, e))
+}
+
+/// Data file paths of a version, sorted by partition ID
+fn partition_paths(version: &VersionInfo) -> Vec<&str> {
+    let mut partitions: Vec<_> = version.data_files.iter().collect();
+    partitions.sort();
+    partitions
+        .into_iter()
+        .map(|(_, path)| path.as_str())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -240,5 +897,513 @@ mod tests {
             .unwrap();
         assert_eq!(query_results.len(), 1);
         assert_eq!(query_results[0].num_rows(), 1);
+
+        let extracted = extract_vectors(&read_batches[0]).unwrap();
+        assert_eq!(
+            extracted,
+            vec![(1, vectors[0].clone()), (2, vectors[1].clone())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_rows() {
+        let client = StorageClient::memory().unwrap();
+        let writer = ParquetWriter::new(&client, 3);
+        let reader = ParquetReader::new(&client);
+        let vectors = vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]];
+        let batch = writer
+            .create_batch(&[7, 9], &vectors, &[Some("a".to_string()), None])
+            .unwrap();
+        writer
+            .write_batch("data/rows.parquet", &batch)
+            .await
+            .unwrap();
+
+        let rows = reader.read_rows("data/rows.parquet", 3).await.unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                VexRow {
+                    id: 7,
+                    vector: vectors[0].clone(),
+                    metadata: Some("a".to_string()),
+                },
+                VexRow {
+                    id: 9,
+                    vector: vectors[1].clone(),
+                    metadata: None,
+                },
+            ]
+        );
+
+        assert!(matches!(
+            reader.read_rows("data/rows.parquet", 4).await,
+            Err(Error::DimensionMismatch {
+                expected: 4,
+                actual: 3
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_projected() {
+        let client = StorageClient::memory().unwrap();
+        let writer = ParquetWriter::new(&client, 3);
+        let reader = ParquetReader::new(&client);
+        let ids: Vec<u64> = (1..=5).collect();
+        let vectors: Vec<Vec<f32>> = ids.iter().map(|&i| vec![i as f32; 3]).collect();
+        let metadata: Vec<Option<String>> = ids.iter().map(|i| Some(i.to_string())).collect();
+        let batch = writer.create_batch(&ids, &vectors, &metadata).unwrap();
+        writer.write_batch("data/p.parquet", &batch).await.unwrap();
+
+        let only_ids = reader
+            .read_projected("data/p.parquet", &["id"], None)
+            .await
+            .unwrap();
+        assert_eq!(only_ids[0].num_columns(), 1);
+        assert_eq!(only_ids[0].schema().field(0).name(), "id");
+        assert_eq!(only_ids[0].num_rows(), 5);
+
+        let reordered = reader
+            .read_projected("data/p.parquet", &["metadata", "id"], None)
+            .await
+            .unwrap();
+        let names: Vec<String> = reordered[0]
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect();
+        assert_eq!(names, vec!["metadata", "id"]);
+
+        let filtered = reader
+            .read_projected("data/p.parquet", &["id", "metadata"], Some("id >= 4"))
+            .await
+            .unwrap();
+        let filtered = concat_batches(&filtered[0].schema(), &filtered).unwrap();
+        assert_eq!(filtered.num_columns(), 2);
+        let ids = filtered
+            .column(0)
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap();
+        assert_eq!(ids.values(), &[4, 5]);
+
+        assert!(reader
+            .read_projected("data/p.parquet", &["missing"], None)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_and_query_version() {
+        use super::super::metadata::MetadataManager;
+        use std::collections::HashMap;
+
+        let client = StorageClient::memory().unwrap();
+        let writer = ParquetWriter::new(&client, 2);
+        let reader = ParquetReader::new(&client);
+        let metadata = MetadataManager::new(&client);
+
+        let empty = metadata.get_version(0).await.unwrap();
+        assert!(reader.read_version(&empty).await.unwrap().is_empty());
+
+        // Partition "b" is written first; reads follow partition order
+        let mut data_files = HashMap::new();
+        for (partition, ids) in [("b", [3u64, 4]), ("a", [1, 2])] {
+            let vectors: Vec<Vec<f32>> = ids.iter().map(|&i| vec![i as f32, 0.0]).collect();
+            let batch = writer.create_batch(&ids, &vectors, &[None, None]).unwrap();
+            let path = format!("data/{}.parquet", partition);
+            writer.write_batch(&path, &batch).await.unwrap();
+            data_files.insert(partition.to_string(), path);
+        }
+        metadata
+            .commit_version(VersionInfo {
+                version: 1,
+                timestamp: 0,
+                data_files,
+                index_files: HashMap::new(),
+                codebook_files: HashMap::new(),
+                total_vectors: 4,
+            })
+            .await
+            .unwrap();
+        let version = metadata.get_latest_version().await.unwrap();
+
+        let batches = reader.read_version(&version).await.unwrap();
+        let ids: Vec<u64> = batches
+            .iter()
+            .flat_map(|b| extract_vectors(b).unwrap())
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+
+        let counted = reader
+            .query_version(
+                &version,
+                "SELECT COUNT(*) AS n FROM vectors WHERE id % 2 = 0",
+            )
+            .await
+            .unwrap();
+        let n = counted[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<arrow::array::Int64Array>()
+            .unwrap()
+            .value(0);
+        assert_eq!(n, 2);
+        assert!(reader
+            .query_version(&empty, "SELECT id FROM vectors")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_vector_rows_stream_search() {
+        use crate::vector::{brute_force_topk, brute_force_topk_stream, DistanceMetric};
+
+        let client = StorageClient::memory().unwrap();
+        let writer = ParquetWriter::new(&client, 4).with_config(ParquetWriterConfig {
+            max_row_group_size: 100,
+            ..Default::default()
+        });
+        let ids: Vec<u64> = (0..1000).collect();
+        let vectors: Vec<Vec<f32>> = ids
+            .iter()
+            .map(|&i| (0..4).map(|d| (i as f32 * 0.7 + d as f32).sin()).collect())
+            .collect();
+        let batch = writer
+            .create_batch(&ids, &vectors, &vec![None; ids.len()])
+            .unwrap();
+        writer.write_batch("data/v.parquet", &batch).await.unwrap();
+
+        let reader = ParquetReader::new(&client);
+        let rows = reader.vector_rows("data/v.parquet").await.unwrap();
+        let query = [1.0, 0.0, -1.0, 0.5];
+        let streamed = tokio::task::spawn_blocking(move || {
+            let mut error = None;
+            let rows = rows.map_while(|row| row.map_err(|e| error = Some(e)).ok());
+            let streamed = brute_force_topk_stream(&query, rows, 10, DistanceMetric::Cosine);
+            assert!(error.is_none());
+            streamed
+        })
+        .await
+        .unwrap();
+
+        let in_memory: Vec<(u64, Vec<f32>)> = ids.into_iter().zip(vectors).collect();
+        assert_eq!(streamed, brute_force_topk(&query, &in_memory, 10));
+        assert!(reader.vector_rows("data/missing.parquet").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_all_checked() {
+        let client = StorageClient::memory().unwrap();
+        let writer = ParquetWriter::new(&client, 3);
+        let batch = writer
+            .create_batch(&[1], &[vec![1.0, 2.0, 3.0]], &[None])
+            .unwrap();
+        writer.write_batch("data/d3.parquet", &batch).await.unwrap();
+
+        let reader = ParquetReader::new(&client);
+        assert_eq!(
+            reader.read_all_checked("data/d3.parquet", 3).await.unwrap()[0].num_rows(),
+            1
+        );
+        assert!(matches!(
+            reader.read_all_checked("data/d3.parquet", 4).await,
+            Err(Error::DimensionMismatch {
+                expected: 4,
+                actual: 3
+            })
+        ));
+
+        // A file that is not VexLake data at all
+        let foreign = RecordBatch::try_from_iter(vec![(
+            "id",
+            Arc::new(StringArray::from(vec!["a"])) as ArrayRef,
+        )])
+        .unwrap();
+        writer
+            .write_batch("data/foreign.parquet", &foreign)
+            .await
+            .unwrap();
+        let err = reader
+            .read_all_checked("data/foreign.parquet", 3)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("column id must be UInt64"));
+    }
+
+    #[tokio::test]
+    async fn test_read_truncated_parquet() {
+        let client = StorageClient::memory().unwrap();
+        let writer = ParquetWriter::new(&client, 3);
+        let batch = writer
+            .create_batch(
+                &[1, 2],
+                &[vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]],
+                &[None, None],
+            )
+            .unwrap();
+        writer
+            .write_batch("data/full.parquet", &batch)
+            .await
+            .unwrap();
+
+        // Simulate an upload that was cut off halfway
+        let full = client.read("data/full.parquet").await.unwrap();
+        client
+            .write("data/truncated.parquet", full[..full.len() / 2].to_vec())
+            .await
+            .unwrap();
+
+        let err = ParquetReader::new(&client)
+            .read_all("data/truncated.parquet")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidConfig(_)));
+        let message = err.to_string();
+        assert!(message.contains("corrupt or truncated parquet"));
+        assert!(message.contains("data/truncated.parquet"));
+    }
+
+    #[tokio::test]
+    async fn test_extract_vectors_reordered_columns() {
+        let client = StorageClient::memory().unwrap();
+        let writer = ParquetWriter::new(&client, 2);
+        let reader = ParquetReader::new(&client);
+
+        let batch = writer
+            .create_batch(&[7, 8], &[vec![1.0, 2.0], vec![3.0, 4.0]], &[None, None])
+            .unwrap();
+
+        // Same columns in reverse order: metadata, vector, id
+        let schema = batch.schema();
+        let reversed_fields: Vec<Field> = schema
+            .fields()
+            .iter()
+            .rev()
+            .map(|f| f.as_ref().clone())
+            .collect();
+        let reversed_columns: Vec<ArrayRef> = batch.columns().iter().rev().cloned().collect();
+        let reversed =
+            RecordBatch::try_new(Arc::new(Schema::new(reversed_fields)), reversed_columns).unwrap();
+
+        writer
+            .write_batch("data/reversed.parquet", &reversed)
+            .await
+            .unwrap();
+
+        let batches = reader.read_all("data/reversed.parquet").await.unwrap();
+        assert_eq!(batches[0].schema().field(0).name(), "metadata");
+
+        let extracted = extract_vectors(&batches[0]).unwrap();
+        assert_eq!(extracted, vec![(7, vec![1.0, 2.0]), (8, vec![3.0, 4.0])]);
+    }
+
+    #[tokio::test]
+    async fn test_append_with_schema_evolution() {
+        use arrow::array::{Array, Int64Array};
+
+        let client = StorageClient::memory().unwrap();
+        let writer = ParquetWriter::new(&client, 2);
+        let path = "data/evolve.parquet";
+
+        let old = writer
+            .create_batch(&[1, 2], &[vec![1.0, 0.0], vec![0.0, 1.0]], &[None, None])
+            .unwrap();
+        writer.append(path, &old).await.unwrap();
+
+        // Same columns plus a typed metadata column
+        let base = writer
+            .create_batch(&[3], &[vec![0.5, 0.5]], &[Some("x".to_string())])
+            .unwrap();
+        let mut fields = base.schema().fields().to_vec();
+        fields.push(Arc::new(Field::new("priority", DataType::Int64, true)));
+        let mut columns = base.columns().to_vec();
+        columns.push(Arc::new(Int64Array::from(vec![7])));
+        let extended = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap();
+        writer.append(path, &extended).await.unwrap();
+
+        let batches = ParquetReader::new(&client).read_all(path).await.unwrap();
+        let merged = concat_batches(&batches[0].schema(), &batches).unwrap();
+        assert_eq!(merged.num_rows(), 3);
+        let priority = merged
+            .column_by_name("priority")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert!(priority.is_null(0) && priority.is_null(1));
+        assert_eq!(priority.value(2), 7);
+        assert_eq!(extract_vectors(&merged).unwrap()[2], (3, vec![0.5, 0.5]));
+
+        // A batch without the new column backfills it with nulls
+        writer.append(path, &old).await.unwrap();
+        let batches = ParquetReader::new(&client).read_all(path).await.unwrap();
+        let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+        assert_eq!(rows, 5);
+
+        // Dimension change is incompatible
+        let wide = ParquetWriter::new(&client, 3)
+            .create_batch(&[9], &[vec![1.0, 0.0, 0.0]], &[None])
+            .unwrap();
+        assert!(matches!(
+            writer.append(path, &wide).await,
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_read_all_parallel_preserves_order() {
+        use parquet::arrow::AsyncArrowWriter;
+        use parquet::file::properties::WriterProperties;
+
+        let client = StorageClient::memory().unwrap();
+        let writer = ParquetWriter::new(&client, 2);
+        let ids: Vec<u64> = (0..1000).rev().collect();
+        let vectors: Vec<Vec<f32>> = ids.iter().map(|&i| vec![i as f32, 0.5]).collect();
+        let batch = writer
+            .create_batch(&ids, &vectors, &vec![None; ids.len()])
+            .unwrap();
+
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(64)
+            .build();
+        let mut buf = Vec::new();
+        let mut parquet_writer =
+            AsyncArrowWriter::try_new(&mut buf, batch.schema(), Some(props)).unwrap();
+        parquet_writer.write(&batch).await.unwrap();
+        parquet_writer.close().await.unwrap();
+        client.write("data/groups.parquet", buf).await.unwrap();
+
+        let reader = ParquetReader::new(&client);
+        let serial = reader.read_all("data/groups.parquet").await.unwrap();
+        let parallel = reader
+            .read_all_parallel("data/groups.parquet", 4)
+            .await
+            .unwrap();
+
+        let rows = |batches: &[RecordBatch]| -> Vec<(u64, Vec<f32>)> {
+            batches
+                .iter()
+                .flat_map(|b| extract_vectors(b).unwrap())
+                .collect()
+        };
+        assert!(parallel.len() >= 16);
+        assert_eq!(rows(&parallel), rows(&serial));
+        assert_eq!(rows(&parallel).len(), 1000);
+        assert_eq!(rows(&parallel)[0].0, 999);
+    }
+
+    #[tokio::test]
+    async fn test_query_reads_only_projected_columns() {
+        use parquet::arrow::AsyncArrowWriter;
+        use parquet::file::properties::WriterProperties;
+
+        let client = StorageClient::memory().unwrap();
+        let writer = ParquetWriter::new(&client, 64);
+        let ids: Vec<u64> = (0..2000).collect();
+        let vectors: Vec<Vec<f32>> = ids
+            .iter()
+            .map(|&i| {
+                (0..64)
+                    .map(|j| (i as f32 * 0.618 + j as f32).sin())
+                    .collect()
+            })
+            .collect();
+        let batch = writer
+            .create_batch(&ids, &vectors, &vec![None; ids.len()])
+            .unwrap();
+
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(250)
+            .build();
+        let mut buf = Vec::new();
+        let mut parquet_writer =
+            AsyncArrowWriter::try_new(&mut buf, batch.schema(), Some(props)).unwrap();
+        parquet_writer.write(&batch).await.unwrap();
+        parquet_writer.close().await.unwrap();
+        let file_size = buf.len() as u64;
+        client.write("data/wide.parquet", buf).await.unwrap();
+
+        let reader = ParquetReader::new(&client);
+        let (batches, store) = reader
+            .query_with_store(
+                &["data/wide.parquet"],
+                "SELECT id FROM vectors WHERE id >= 1500",
+            )
+            .await
+            .unwrap();
+
+        let schema = batches[0].schema();
+        let columns: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(columns, vec!["id"]);
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 500);
+        // The vector column dominates the file and is never fetched
+        assert!(
+            store.bytes_read() < file_size / 4,
+            "read {} of {} bytes",
+            store.bytes_read(),
+            file_size
+        );
+
+        let all = reader.read_all("data/wide.parquet").await.unwrap();
+        assert_eq!(all.iter().map(|b| b.num_rows()).sum::<usize>(), 2000);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_writer_row_groups() {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+        use parquet::basic::Compression;
+
+        let client = StorageClient::memory().unwrap();
+        for (compression, codec) in [
+            (ParquetCompression::Snappy, Compression::SNAPPY),
+            (
+                ParquetCompression::Zstd,
+                Compression::ZSTD(Default::default()),
+            ),
+            (ParquetCompression::None, Compression::UNCOMPRESSED),
+        ] {
+            let writer = ParquetWriter::new(&client, 4).with_config(ParquetWriterConfig {
+                max_row_group_size: 2500,
+                compression,
+            });
+            let path = format!("data/{:?}.parquet", compression);
+            let mut file = writer.open(&path).await.unwrap();
+            for chunk in 0..10u64 {
+                let ids: Vec<u64> = (chunk * 1000..(chunk + 1) * 1000).collect();
+                let vectors: Vec<Vec<f32>> = ids.iter().map(|&i| vec![i as f32; 4]).collect();
+                let batch = writer
+                    .create_batch(&ids, &vectors, &vec![None; ids.len()])
+                    .unwrap();
+                file.write_batch(&batch).await.unwrap();
+            }
+            assert_eq!(file.rows_written(), 10000);
+            assert!(!client.exists(&path).await.unwrap());
+            assert_eq!(file.close().await.unwrap(), 10000);
+
+            let data = bytes::Bytes::from(client.read(&path).await.unwrap());
+            let builder = ParquetRecordBatchReaderBuilder::try_new(data).unwrap();
+            let meta = builder.metadata();
+            assert_eq!(meta.num_row_groups(), 4);
+            assert_eq!(meta.row_group(0).column(0).compression(), codec);
+
+            let batches = ParquetReader::new(&client).read_all(&path).await.unwrap();
+            let rows: Vec<(u64, Vec<f32>)> = batches
+                .iter()
+                .flat_map(|b| extract_vectors(b).unwrap())
+                .collect();
+            assert_eq!(rows.len(), 10000);
+            assert_eq!(rows[9999], (9999, vec![9999.0; 4]));
+        }
+
+        let zero = ParquetWriter::new(&client, 4).with_config(ParquetWriterConfig {
+            max_row_group_size: 0,
+            ..Default::default()
+        });
+        assert!(zero.open("data/zero.parquet").await.is_err());
     }
 }
@@ -0,0 +1,132 @@
+//! Brute-force search across the partitions of a version
+//!
+//! Each partition is scanned independently and the per-partition top-k lists
+//! are merged. The tagged variant keeps the source partition path alongside
+//! every result, which helps debugging and locality-aware caching.
+
+use std::cmp::Ordering;
+
+use super::{extract_vectors, ParquetReader, StorageClient, VersionInfo};
+use crate::vector::{brute_force_topk, SearchResult};
+use crate::{Error, Result};
+
+/// Search result tagged with the partition file it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaggedResult {
+    /// The search result
+    pub result: SearchResult,
+    /// Path of the source partition
+    pub partition: String,
+}
+
+/// Search all partitions of a version for the top K most similar vectors
+pub async fn search_version(
+    client: &StorageClient,
+    version: &VersionInfo,
+    query: &[f32],
+    k: usize,
+) -> Result<Vec<SearchResult>> {
+    Ok(search_version_tagged(client, version, query, k)
+        .await?
+        .into_iter()
+        .map(|t| t.result)
+        .collect())
+}
+
+/// Like [`search_version`], but tags each result with its source partition
+pub async fn search_version_tagged(
+    client: &StorageClient,
+    version: &VersionInfo,
+    query: &[f32],
+    k: usize,
+) -> Result<Vec<TaggedResult>> {
+    let reader = ParquetReader::new(client);
+    let mut paths: Vec<&String> = version.data_files.values().collect();
+    paths.sort();
+
+    let mut merged = Vec::new();
+    for path in paths {
+        let mut vectors = Vec::new();
+        for batch in reader.read_all(path).await? {
+            vectors.extend(extract_vectors(&batch)?);
+        }
+        if let Some((_, v)) = vectors.first() {
+            if v.len() != query.len() {
+                return Err(Error::DimensionMismatch {
+                    expected: v.len(),
+                    actual: query.len(),
+                });
+            }
+        }
+
+        merged.extend(
+            brute_force_topk(query, &vectors, k)
+                .into_iter()
+                .map(|result| TaggedResult {
+                    result,
+                    partition: path.clone(),
+                }),
+        );
+    }
+
+    merged.sort_by(|a, b| {
+        b.result
+            .score
+            .partial_cmp(&a.result.score)
+            .unwrap_or(Ordering::Equal)
+    });
+    merged.truncate(k);
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::ParquetWriter;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_search_tags_source_partition() {
+        let client = StorageClient::memory().unwrap();
+        let writer = ParquetWriter::new(&client, 2);
+
+        let mut data_files = HashMap::new();
+        let partitions = [
+            ("a", vec![1u64, 2], vec![vec![1.0, 0.0], vec![0.9, 0.1]]),
+            ("b", vec![10u64, 11], vec![vec![0.0, 1.0], vec![0.8, 0.2]]),
+        ];
+        for (name, ids, vectors) in &partitions {
+            let batch = writer
+                .create_batch(ids, vectors, &vec![None; ids.len()])
+                .unwrap();
+            let path = format!("data/{}.parquet", name);
+            writer.write_batch(&path, &batch).await.unwrap();
+            data_files.insert(name.to_string(), path);
+        }
+        let version = VersionInfo {
+            version: 1,
+            timestamp: 0,
+            data_files,
+            index_files: HashMap::new(),
+            codebook_files: HashMap::new(),
+            total_vectors: 4,
+        };
+
+        let results = search_version_tagged(&client, &version, &[1.0, 0.0], 4)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 4);
+        for r in &results {
+            let expected = if r.result.id < 10 { "a" } else { "b" };
+            assert_eq!(r.partition, format!("data/{}.parquet", expected));
+        }
+        let ids: Vec<u64> = results.iter().map(|r| r.result.id).collect();
+        assert_eq!(ids, vec![1, 2, 11, 10]);
+
+        let untagged = search_version(&client, &version, &[1.0, 0.0], 2)
+            .await
+            .unwrap();
+        assert_eq!(untagged.len(), 2);
+        assert_eq!(untagged[0].id, 1);
+    }
+}
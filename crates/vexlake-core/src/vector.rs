@@ -4,11 +4,25 @@
 //! - Cosine similarity
 //! - L2 (Euclidean) distance
 //! - Dot product
+//! - L1 (Manhattan) distance
 //!
-//! All functions have SIMD-accelerated implementations using AVX-512/NEON
-//! when available, with automatic fallback to scalar implementations.
+//! [`ScalarQuantizer`] compresses vectors to int8 codes for memory-bound
+//! workloads; [`ProductQuantizer`] goes further, to one byte per subvector.
+//! [`cluster::kmeans`] provides the k-means both are trained with.
+//!
+//! All functions have SIMD-accelerated implementations using AVX2/FMA on
+//! x86_64 and NEON on aarch64 when available, with automatic fallback to
+//! scalar implementations. The kernel set is chosen once at runtime (see
+//! [`simd`]).
 use serde::{Deserialize, Serialize};
 
+pub mod cluster;
+pub mod fusion;
+pub mod lsh;
+pub mod simd;
+
+use crate::{Error, Result};
+
 /// Compute cosine similarity between two vectors
 ///
 /// # Arguments
@@ -23,6 +37,51 @@ use serde::{Deserialize, Serialize};
 pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     assert_eq!(a.len(), b.len(), "Vector dimensions must match");
 
+    let dot_fn = simd::kernels().dot;
+    let dot = dot_fn(a, b);
+    let norm_a = dot_fn(a, a).sqrt();
+    let norm_b = dot_fn(b, b).sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+
+    dot / (norm_a * norm_b)
+}
+
+/// Compute cosine similarity, rejecting non-finite inputs
+///
+/// Returns [`Error::InvalidConfig`] if either vector contains NaN or
+/// infinity and [`Error::DimensionMismatch`] if their lengths differ, instead
+/// of producing a NaN score or panicking.
+pub fn cosine_similarity_checked(a: &[f32], b: &[f32]) -> Result<f32> {
+    if a.len() != b.len() {
+        return Err(Error::DimensionMismatch {
+            expected: a.len(),
+            actual: b.len(),
+        });
+    }
+    if let Some(x) = a.iter().chain(b).find(|x| !x.is_finite()) {
+        return Err(Error::InvalidConfig(format!(
+            "vector contains non-finite value {}",
+            x
+        )));
+    }
+    Ok(cosine_similarity(a, b))
+}
+
+/// Compute cosine similarity with a bias term added to the dot product
+///
+/// Returns `(a·b + bias) / (|a| |b|)`, matching learned similarity models that
+/// add a bias before normalization. Unlike plain cosine the result is NOT
+/// bounded to [-1, 1]: a positive bias shifts every score up, by more for
+/// short vectors than for long ones. Zero bias reproduces [`cosine_similarity`].
+///
+/// # Panics
+/// Panics if vectors have different dimensions
+pub fn biased_cosine_similarity(a: &[f32], b: &[f32], bias: f32) -> f32 {
+    assert_eq!(a.len(), b.len(), "Vector dimensions must match");
+
     let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
     let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
     let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
@@ -31,9 +90,94 @@ pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
         return 0.0;
     }
 
+    (dot + bias) / (norm_a * norm_b)
+}
+
+/// Compute cosine similarity with per-dimension weights
+///
+/// Returns `Σ w·a·b / (sqrt(Σ w·a²) · sqrt(Σ w·b²))`, so dimension ranges can be
+/// boosted or ignored (weight 0) without re-embedding. Weights should be
+/// non-negative; uniform weights reproduce [`cosine_similarity`].
+///
+/// # Panics
+/// Panics if the vectors and weights have different lengths
+pub fn weighted_cosine_similarity(a: &[f32], b: &[f32], weights: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len(), "Vector dimensions must match");
+    assert_eq!(
+        a.len(),
+        weights.len(),
+        "Weights must match vector dimensions"
+    );
+
+    let mut dot = 0.0f32;
+    let mut norm_a = 0.0f32;
+    let mut norm_b = 0.0f32;
+    for ((x, y), w) in a.iter().zip(b).zip(weights) {
+        dot += w * x * y;
+        norm_a += w * x * x;
+        norm_b += w * y * y;
+    }
+
+    let norm_a = norm_a.sqrt();
+    let norm_b = norm_b.sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+
     dot / (norm_a * norm_b)
 }
 
+/// Compute cosine similarity using compensated (Neumaier) summation
+///
+/// Slower than [`cosine_similarity`], but keeps rounding error bounded for
+/// very high dimensions (4096+) where naive f32 accumulation drifts.
+///
+/// # Panics
+/// Panics if vectors have different dimensions
+pub fn cosine_similarity_stable(a: &[f32], b: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len(), "Vector dimensions must match");
+
+    let mut dot = CompensatedSum::default();
+    let mut norm_a = CompensatedSum::default();
+    let mut norm_b = CompensatedSum::default();
+    for (x, y) in a.iter().zip(b.iter()) {
+        dot.add(x * y);
+        norm_a.add(x * x);
+        norm_b.add(y * y);
+    }
+
+    let norm_a = norm_a.total().sqrt();
+    let norm_b = norm_b.total().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+
+    dot.total() / (norm_a * norm_b)
+}
+
+/// Neumaier compensated summation accumulator
+#[derive(Debug, Default, Clone, Copy)]
+struct CompensatedSum {
+    sum: f32,
+    compensation: f32,
+}
+
+impl CompensatedSum {
+    fn add(&mut self, value: f32) {
+        let t = self.sum + value;
+        if self.sum.abs() >= value.abs() {
+            self.compensation += (self.sum - t) + value;
+        } else {
+            self.compensation += (value - t) + self.sum;
+        }
+        self.sum = t;
+    }
+
+    fn total(&self) -> f32 {
+        self.sum + self.compensation
+    }
+}
+
 /// Compute L2 (Euclidean) distance between two vectors
 ///
 /// # Arguments
@@ -45,11 +189,45 @@ pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
 pub fn l2_distance(a: &[f32], b: &[f32]) -> f32 {
     assert_eq!(a.len(), b.len(), "Vector dimensions must match");
 
-    a.iter()
-        .zip(b.iter())
-        .map(|(x, y)| (x - y).powi(2))
-        .sum::<f32>()
-        .sqrt()
+    (simd::kernels().l2_squared)(a, b).sqrt()
+}
+
+/// Compute squared L2 distance between two vectors
+///
+/// Skips the square root of [`l2_distance`]; it orders vectors the same way,
+/// so ranking code should prefer it.
+pub fn l2_distance_squared(a: &[f32], b: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len(), "Vector dimensions must match");
+
+    (simd::kernels().l2_squared)(a, b)
+}
+
+/// Compute L1 (Manhattan) distance between two vectors
+///
+/// The sum of absolute differences; suits sparse histogram features.
+///
+/// # Panics
+/// Panics if vectors have different dimensions
+pub fn l1_distance(a: &[f32], b: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len(), "Vector dimensions must match");
+
+    (simd::kernels().l1)(a, b)
+}
+
+/// Convert a squared L2 distance between unit vectors to cosine similarity
+///
+/// For normalized vectors `||a - b||² = 2(1 - cos(a, b))`, so an L2 result can
+/// be reported as a cosine score without recomputation. The conversion is only
+/// meaningful when both vectors have unit length.
+pub fn l2_sq_to_cosine(l2_sq: f32) -> f32 {
+    1.0 - l2_sq / 2.0
+}
+
+/// Convert a cosine similarity between unit vectors to squared L2 distance
+///
+/// Inverse of [`l2_sq_to_cosine`].
+pub fn cosine_to_l2_sq(cosine: f32) -> f32 {
+    2.0 * (1.0 - cosine)
 }
 
 /// Compute dot product between two vectors
@@ -63,7 +241,47 @@ pub fn l2_distance(a: &[f32], b: &[f32]) -> f32 {
 pub fn dot_product(a: &[f32], b: &[f32]) -> f32 {
     assert_eq!(a.len(), b.len(), "Vector dimensions must match");
 
-    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+    (simd::kernels().dot)(a, b)
+}
+
+/// Late-interaction (MaxSim) score between multi-vector representations
+///
+/// For each query token, takes the maximum cosine similarity against all
+/// document tokens, then sums over query tokens (as in ColBERT). A document
+/// without tokens scores 0. A NaN similarity makes the whole score NaN, so
+/// rankings place the document last rather than scoring it on the remaining
+/// tokens.
+///
+/// # Panics
+/// Panics if tokens have different dimensions
+pub fn multi_vector_score(query_tokens: &[Vec<f32>], doc_tokens: &[Vec<f32>]) -> f32 {
+    if let Some(dim) = query_tokens.first().map(|t| t.len()) {
+        assert!(
+            query_tokens
+                .iter()
+                .chain(doc_tokens)
+                .all(|t| t.len() == dim),
+            "Vector dimensions must match"
+        );
+    }
+
+    query_tokens
+        .iter()
+        .map(|q| {
+            doc_tokens
+                .iter()
+                .map(|d| cosine_similarity(q, d))
+                // f32::max would skip NaN
+                .reduce(|a, b| {
+                    if a.is_nan() || b.is_nan() {
+                        f32::NAN
+                    } else {
+                        a.max(b)
+                    }
+                })
+                .unwrap_or(0.0)
+        })
+        .sum()
 }
 
 /// Normalize a vector to unit length
@@ -79,6 +297,69 @@ pub fn normalize(v: &mut [f32]) {
     }
 }
 
+/// Dense row-major matrix for linear vector transforms
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Matrix {
+    rows: usize,
+    cols: usize,
+    data: Vec<f32>,
+}
+
+impl Matrix {
+    /// Create a matrix from row-major data
+    pub fn new(rows: usize, cols: usize, data: Vec<f32>) -> Result<Self> {
+        if data.len() != rows * cols {
+            return Err(Error::InvalidConfig(format!(
+                "matrix data has {} values, expected {}x{}",
+                data.len(),
+                rows,
+                cols
+            )));
+        }
+        Ok(Self { rows, cols, data })
+    }
+
+    /// Create an identity matrix
+    pub fn identity(n: usize) -> Self {
+        let mut data = vec![0.0; n * n];
+        for i in 0..n {
+            data[i * n + i] = 1.0;
+        }
+        Self {
+            rows: n,
+            cols: n,
+            data,
+        }
+    }
+
+    /// Number of rows (output dimension)
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Number of columns (input dimension)
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Multiply the matrix by a vector
+    ///
+    /// A matrix with no columns maps the empty vector to `rows` zeros.
+    ///
+    /// # Panics
+    /// Panics if the vector length differs from the column count
+    pub fn apply(&self, v: &[f32]) -> Vec<f32> {
+        assert_eq!(v.len(), self.cols, "Vector dimensions must match");
+        if self.cols == 0 {
+            return vec![0.0; self.rows];
+        }
+        self.data
+            .chunks_exact(self.cols)
+            .map(|row| dot_product(row, v))
+            .collect()
+    }
+}
+
 /// Search result with ID and score
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SearchResult {
@@ -96,6 +377,7 @@ impl SearchResult {
 }
 
 use rayon::prelude::*;
+use std::collections::{BinaryHeap, HashMap};
 
 /// Brute-force TopK search (parallel version)
 ///
@@ -111,32 +393,524 @@ pub fn brute_force_topk_parallel(
     vectors: &[(u64, Vec<f32>)],
     k: usize,
 ) -> Vec<SearchResult> {
-    let mut results: Vec<SearchResult> = vectors
+    // Per-thread bounded heaps, merged pairwise
+    let heap = vectors
         .par_iter()
-        .map(|(id, vec)| SearchResult::new(*id, cosine_similarity(query, vec)))
-        .collect();
+        .enumerate()
+        .fold(BinaryHeap::new, |mut heap, (index, (id, vec))| {
+            push_bounded(
+                &mut heap,
+                k,
+                Ranked::new(index, *id, cosine_similarity(query, vec), true),
+            );
+            heap
+        })
+        .reduce(BinaryHeap::new, |mut a, b| {
+            for entry in b {
+                push_bounded(&mut a, k, entry);
+            }
+            a
+        });
+    into_results(heap)
+}
+
+/// Heap entry ordered so that the worse result compares greater
+///
+/// Ties on score fall back to dataset position, so the output matches a
+/// stable sort of all results.
+struct Ranked {
+    /// Score oriented so that lower is better
+    key: f32,
+    index: usize,
+    result: SearchResult,
+}
 
-    // Sort by score descending
-    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+impl Ranked {
+    fn new(index: usize, id: u64, score: f32, higher_is_better: bool) -> Self {
+        // A positive NaN key sorts after every number, so NaN scores from
+        // non-finite inputs rank last whatever their sign bit
+        let key = if score.is_nan() {
+            f32::NAN
+        } else if higher_is_better {
+            -score
+        } else {
+            score
+        };
+        Self {
+            key,
+            index,
+            result: SearchResult::new(id, score),
+        }
+    }
+}
 
-    results.truncate(k);
-    results
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
-/// Brute-force TopK search
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key
+            .total_cmp(&other.key)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+/// Keep the `k` best entries, with the worst of them at the root
+fn push_bounded(heap: &mut BinaryHeap<Ranked>, k: usize, entry: Ranked) {
+    if heap.len() < k {
+        heap.push(entry);
+    } else if heap.peek().is_some_and(|worst| entry < *worst) {
+        heap.pop();
+        heap.push(entry);
+    }
+}
+
+/// Drain a bounded heap into results, best first
+fn into_results(heap: BinaryHeap<Ranked>) -> Vec<SearchResult> {
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|r| r.result)
+        .collect()
+}
+
+/// Brute-force TopK search by cosine similarity
 pub fn brute_force_topk(query: &[f32], vectors: &[(u64, Vec<f32>)], k: usize) -> Vec<SearchResult> {
-    let mut results: Vec<SearchResult> = vectors
-        .iter()
-        .map(|(id, vec)| SearchResult::new(*id, cosine_similarity(query, vec)))
-        .collect();
+    brute_force_topk_metric(query, vectors, k, DistanceMetric::Cosine)
+}
+
+/// Distance or similarity measure between vectors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DistanceMetric {
+    /// Cosine similarity, higher is closer
+    #[default]
+    Cosine,
+    /// Euclidean distance, lower is closer
+    L2,
+    /// Inner product, higher is closer
+    Dot,
+    /// Manhattan distance, lower is closer
+    L1,
+}
 
-    // Sort by score descending
-    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+impl DistanceMetric {
+    /// Score `b` against `a` with this metric
+    pub fn score(self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            Self::Cosine => cosine_similarity(a, b),
+            Self::L2 => l2_distance(a, b),
+            Self::Dot => dot_product(a, b),
+            Self::L1 => l1_distance(a, b),
+        }
+    }
 
-    results.truncate(k);
+    /// Whether a higher score means a closer match
+    pub fn higher_is_better(self) -> bool {
+        matches!(self, Self::Cosine | Self::Dot)
+    }
+}
+
+/// Brute-force TopK search with the given metric
+///
+/// Results are ordered best first: descending score for cosine and dot,
+/// ascending distance for L2 and L1. Equal scores keep dataset order.
+pub fn brute_force_topk_metric(
+    query: &[f32],
+    vectors: &[(u64, Vec<f32>)],
+    k: usize,
+    metric: DistanceMetric,
+) -> Vec<SearchResult> {
+    topk_by_metric(query, vectors.iter().map(|(id, v)| (*id, v)), k, metric)
+}
+
+/// Brute-force TopK search over vectors produced lazily
+///
+/// Only the `k` best entries are held while `vectors` is consumed, so a
+/// dataset larger than memory can be fed batch by batch, e.g. from
+/// [`crate::storage::ParquetReader::vector_rows`]. Results and ordering
+/// match [`brute_force_topk_metric`] over the same sequence.
+pub fn brute_force_topk_stream<I>(
+    query: &[f32],
+    vectors: I,
+    k: usize,
+    metric: DistanceMetric,
+) -> Vec<SearchResult>
+where
+    I: Iterator<Item = (u64, Vec<f32>)>,
+{
+    topk_by_metric(query, vectors, k, metric)
+}
+
+fn topk_by_metric<V: AsRef<[f32]>>(
+    query: &[f32],
+    vectors: impl Iterator<Item = (u64, V)>,
+    k: usize,
+    metric: DistanceMetric,
+) -> Vec<SearchResult> {
+    // Rank L2 by squared distance and take the root of the survivors only
+    let score = |v: &[f32]| match metric {
+        DistanceMetric::L2 => l2_distance_squared(query, v),
+        _ => metric.score(query, v),
+    };
+
+    let mut heap = BinaryHeap::with_capacity(k.min(vectors.size_hint().0) + 1);
+    for (index, (id, vec)) in vectors.enumerate() {
+        push_bounded(
+            &mut heap,
+            k,
+            Ranked::new(index, id, score(vec.as_ref()), metric.higher_is_better()),
+        );
+    }
+
+    let mut results = into_results(heap);
+    if metric == DistanceMetric::L2 {
+        for r in &mut results {
+            r.score = r.score.sqrt();
+        }
+    }
     results
 }
 
+/// Keep the `k` best of precomputed `(id, score)` pairs, ordered as in
+/// [`brute_force_topk_metric`]
+pub(crate) fn topk_scored(
+    scores: impl Iterator<Item = (u64, f32)>,
+    k: usize,
+    higher_is_better: bool,
+) -> Vec<SearchResult> {
+    let mut heap = BinaryHeap::with_capacity(k.min(scores.size_hint().0) + 1);
+    for (index, (id, score)) in scores.enumerate() {
+        push_bounded(
+            &mut heap,
+            k,
+            Ranked::new(index, id, score, higher_is_better),
+        );
+    }
+    into_results(heap)
+}
+
+/// Rescore approximate candidates with full-precision vectors
+///
+/// Recomputes `metric` between `query` and each candidate's vector in
+/// `full_vectors` and returns the best `k`, ordered as in
+/// [`brute_force_topk_metric`]. Candidates without a full vector are dropped;
+/// ties keep candidate order.
+pub fn rerank(
+    query: &[f32],
+    candidates: &[SearchResult],
+    full_vectors: &HashMap<u64, Vec<f32>>,
+    metric: DistanceMetric,
+    k: usize,
+) -> Vec<SearchResult> {
+    let mut heap = BinaryHeap::with_capacity(k.min(candidates.len()) + 1);
+    for (index, candidate) in candidates.iter().enumerate() {
+        if let Some(vec) = full_vectors.get(&candidate.id) {
+            push_bounded(
+                &mut heap,
+                k,
+                Ranked::new(
+                    index,
+                    candidate.id,
+                    metric.score(query, vec),
+                    metric.higher_is_better(),
+                ),
+            );
+        }
+    }
+    into_results(heap)
+}
+
+/// Per-dimension int8 scalar quantizer
+///
+/// Each dimension's training range `[min, max]` is mapped linearly onto the
+/// 256 `i8` codes, cutting vector memory by 4x. Values outside the training
+/// range are clamped. Codes can be compared directly with
+/// [`l2_distance_i8`] and dequantized to rerank candidates with full
+/// precision distances.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScalarQuantizer {
+    min: Vec<f32>,
+    /// Width of one code step per dimension
+    scale: Vec<f32>,
+}
+
+impl ScalarQuantizer {
+    /// Learn per-dimension ranges from a training set
+    pub fn train(vectors: &[Vec<f32>]) -> Result<Self> {
+        let Some(first) = vectors.first() else {
+            return Err(Error::InvalidConfig(
+                "cannot train a quantizer on no vectors".to_string(),
+            ));
+        };
+
+        let mut min = first.clone();
+        let mut max = first.clone();
+        for v in vectors {
+            if v.len() != min.len() {
+                return Err(Error::DimensionMismatch {
+                    expected: min.len(),
+                    actual: v.len(),
+                });
+            }
+            for ((lo, hi), &x) in min.iter_mut().zip(&mut max).zip(v) {
+                *lo = lo.min(x);
+                *hi = hi.max(x);
+            }
+        }
+
+        let scale = min
+            .iter()
+            .zip(&max)
+            .map(|(lo, hi)| (hi - lo) / 255.0)
+            .collect();
+        Ok(Self { min, scale })
+    }
+
+    /// Vector dimension
+    pub fn dimension(&self) -> usize {
+        self.min.len()
+    }
+
+    /// Largest reconstruction error for a value within the training range
+    pub fn max_error(&self) -> f32 {
+        self.scale.iter().fold(0.0f32, |m, &s| m.max(s / 2.0))
+    }
+
+    /// Encode a vector as one `i8` code per dimension
+    ///
+    /// # Panics
+    /// Panics if the vector length differs from the trained dimension
+    pub fn quantize(&self, v: &[f32]) -> Vec<i8> {
+        assert_eq!(v.len(), self.dimension(), "Vector dimensions must match");
+        v.iter()
+            .zip(self.min.iter().zip(&self.scale))
+            .map(|(&x, (&lo, &scale))| {
+                let step = if scale > 0.0 {
+                    ((x - lo) / scale).round().clamp(0.0, 255.0)
+                } else {
+                    0.0
+                };
+                (step as i32 - 128) as i8
+            })
+            .collect()
+    }
+
+    /// Reconstruct an approximate vector from its codes
+    ///
+    /// # Panics
+    /// Panics if the code length differs from the trained dimension
+    pub fn dequantize(&self, q: &[i8]) -> Vec<f32> {
+        assert_eq!(q.len(), self.dimension(), "Vector dimensions must match");
+        q.iter()
+            .zip(self.min.iter().zip(&self.scale))
+            .map(|(&c, (&lo, &scale))| lo + (c as i32 + 128) as f32 * scale)
+            .collect()
+    }
+}
+
+/// Compute L2 distance between two int8 code vectors
+///
+/// Works in code space, so it is proportional to the float distance only when
+/// every dimension has the same quantization step. With per-dimension ranges
+/// it is an approximation suited to candidate generation; rerank with
+/// [`ScalarQuantizer::dequantize`] when exact order matters.
+pub fn l2_distance_i8(a: &[i8], b: &[i8]) -> f32 {
+    assert_eq!(a.len(), b.len(), "Vector dimensions must match");
+
+    let sum: i64 = a
+        .iter()
+        .zip(b)
+        .map(|(&x, &y)| {
+            let d = x as i32 - y as i32;
+            (d * d) as i64
+        })
+        .sum();
+    (sum as f32).sqrt()
+}
+
+/// Maximum Lloyd iterations when training [`ProductQuantizer`] codebooks
+const PQ_KMEANS_ITERATIONS: usize = 25;
+
+/// Product quantizer with one byte code per subvector
+///
+/// Vectors are split into `m` equal subvectors and each is replaced by the
+/// index of its nearest centroid in that subspace's codebook, so a vector
+/// costs `m` bytes. Distances to a query are computed asymmetrically: the
+/// query stays in full precision and a per-subspace table of distances to
+/// every centroid turns scoring a code into `m` lookups.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProductQuantizer {
+    dimension: usize,
+    /// Codebooks per subspace, each `n_centroids` vectors of `dimension / m`
+    codebooks: Vec<Vec<Vec<f32>>>,
+}
+
+impl ProductQuantizer {
+    /// Learn `n_centroids` centroids per subspace with k-means
+    ///
+    /// The dimension must be divisible by `m_subquantizers`, and
+    /// `n_centroids` must fit a byte (at most 256) and not exceed the number
+    /// of training vectors. Training is seeded, so it is deterministic.
+    pub fn train(vectors: &[Vec<f32>], m_subquantizers: usize, n_centroids: usize) -> Result<Self> {
+        let Some(first) = vectors.first() else {
+            return Err(Error::InvalidConfig(
+                "cannot train a quantizer on no vectors".to_string(),
+            ));
+        };
+        let dimension = first.len();
+        if m_subquantizers == 0 || dimension % m_subquantizers != 0 {
+            return Err(Error::InvalidConfig(format!(
+                "dimension {} is not divisible into {} subquantizers",
+                dimension, m_subquantizers
+            )));
+        }
+        if n_centroids == 0 || n_centroids > 256 || n_centroids > vectors.len() {
+            return Err(Error::InvalidConfig(format!(
+                "n_centroids must be between 1 and min(256, {} training vectors), got {}",
+                vectors.len(),
+                n_centroids
+            )));
+        }
+        if let Some(v) = vectors.iter().find(|v| v.len() != dimension) {
+            return Err(Error::DimensionMismatch {
+                expected: dimension,
+                actual: v.len(),
+            });
+        }
+
+        let sub_dim = dimension / m_subquantizers;
+        let codebooks = (0..m_subquantizers)
+            .into_par_iter()
+            .map(|sub| {
+                let range = sub * sub_dim..(sub + 1) * sub_dim;
+                let points: Vec<&[f32]> = vectors.iter().map(|v| &v[range.clone()]).collect();
+                cluster::kmeans_seeded(
+                    &points,
+                    n_centroids,
+                    PQ_KMEANS_ITERATIONS,
+                    DistanceMetric::L2,
+                    sub as u64,
+                )
+                .0
+            })
+            .collect();
+        Ok(Self {
+            dimension,
+            codebooks,
+        })
+    }
+
+    /// Vector dimension
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Number of subquantizers, i.e. bytes per code
+    pub fn subquantizers(&self) -> usize {
+        self.codebooks.len()
+    }
+
+    fn sub_dim(&self) -> usize {
+        self.dimension / self.codebooks.len()
+    }
+
+    /// Encode a vector as the nearest centroid index in each subspace
+    ///
+    /// # Panics
+    /// Panics if the vector length differs from the trained dimension
+    pub fn encode(&self, v: &[f32]) -> Vec<u8> {
+        assert_eq!(v.len(), self.dimension, "Vector dimensions must match");
+        v.chunks_exact(self.sub_dim())
+            .zip(&self.codebooks)
+            .map(|(sub, codebook)| nearest(codebook, sub) as u8)
+            .collect()
+    }
+
+    /// Reconstruct an approximate vector by concatenating code centroids
+    ///
+    /// # Panics
+    /// Panics if the code length differs from the number of subquantizers
+    pub fn decode(&self, code: &[u8]) -> Vec<f32> {
+        assert_eq!(code.len(), self.codebooks.len(), "Code length must match");
+        code.iter()
+            .zip(&self.codebooks)
+            .flat_map(|(&c, codebook)| codebook[c as usize].iter().copied())
+            .collect()
+    }
+
+    /// Precompute squared distances from `query` to every centroid
+    ///
+    /// Build once per query, then score each code with
+    /// [`PqDistanceTable::distance`].
+    ///
+    /// # Panics
+    /// Panics if the query length differs from the trained dimension
+    pub fn distance_table(&self, query: &[f32]) -> PqDistanceTable {
+        assert_eq!(query.len(), self.dimension, "Vector dimensions must match");
+        let table = query
+            .chunks_exact(self.sub_dim())
+            .zip(&self.codebooks)
+            .map(|(sub, codebook)| {
+                codebook
+                    .iter()
+                    .map(|c| l2_distance_squared(sub, c))
+                    .collect()
+            })
+            .collect();
+        PqDistanceTable { table }
+    }
+
+    /// Approximate L2 distance between a full-precision query and a code
+    ///
+    /// Builds a fresh [`PqDistanceTable`]; when scanning many codes for one
+    /// query, build the table once with [`Self::distance_table`] instead.
+    pub fn asymmetric_distance(&self, query: &[f32], code: &[u8]) -> f32 {
+        self.distance_table(query).distance(code)
+    }
+}
+
+/// Per-query lookup table from [`ProductQuantizer::distance_table`]
+#[derive(Debug, Clone)]
+pub struct PqDistanceTable {
+    /// Squared distance per subspace and centroid
+    table: Vec<Vec<f32>>,
+}
+
+impl PqDistanceTable {
+    /// Approximate L2 distance from the query to a code
+    ///
+    /// # Panics
+    /// Panics if the code length differs from the number of subquantizers
+    pub fn distance(&self, code: &[u8]) -> f32 {
+        assert_eq!(code.len(), self.table.len(), "Code length must match");
+        code.iter()
+            .zip(&self.table)
+            .map(|(&c, row)| row[c as usize])
+            .sum::<f32>()
+            .sqrt()
+    }
+}
+
+/// Index of the centroid closest to `v` in L2
+fn nearest(centroids: &[Vec<f32>], v: &[f32]) -> usize {
+    centroids
+        .iter()
+        .map(|c| l2_distance_squared(v, c))
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(0, |(i, _)| i)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -165,6 +939,46 @@ mod tests {
         assert!((sim - (-1.0)).abs() < 1e-6);
     }
 
+    #[test]
+    fn test_biased_cosine_similarity() {
+        let a = vec![1.0, 2.0, 0.5];
+        let b = vec![0.5, -1.0, 3.0];
+        let plain = cosine_similarity(&a, &b);
+        assert!((biased_cosine_similarity(&a, &b, 0.0) - plain).abs() < 1e-6);
+
+        // Bias of 1.0 over norms |a| * |b| shifts the score up
+        let norms = (5.25f32).sqrt() * (10.25f32).sqrt();
+        let shifted = biased_cosine_similarity(&a, &b, 1.0);
+        assert!((shifted - (plain + 1.0 / norms)).abs() < 1e-6);
+
+        // Identical unit vectors exceed 1.0 with a positive bias
+        let u = vec![1.0, 0.0];
+        assert!((biased_cosine_similarity(&u, &u, 0.5) - 1.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_weighted_cosine_similarity() {
+        // Differ only in the last dimension, which is weighted out
+        let a = vec![1.0, 2.0, 5.0];
+        let b = vec![1.0, 2.0, -3.0];
+        assert!(cosine_similarity(&a, &b) < 0.0);
+        let sim = weighted_cosine_similarity(&a, &b, &[1.0, 1.0, 0.0]);
+        assert!((sim - 1.0).abs() < 1e-6);
+
+        let c = vec![0.5, -1.0, 3.0];
+        for w in [1.0, 2.5] {
+            let uniform = weighted_cosine_similarity(&a, &c, &[w; 3]);
+            assert!((uniform - cosine_similarity(&a, &c)).abs() < 1e-6);
+        }
+        assert_eq!(weighted_cosine_similarity(&a, &b, &[0.0; 3]), 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Weights must match")]
+    fn test_weighted_cosine_weight_mismatch() {
+        weighted_cosine_similarity(&[1.0, 0.0], &[0.0, 1.0], &[1.0]);
+    }
+
     #[test]
     fn test_l2_distance() {
         let a = vec![0.0, 0.0, 0.0];
@@ -173,6 +987,19 @@ mod tests {
         assert!((dist - 5.0).abs() < 1e-6);
     }
 
+    #[test]
+    fn test_l2_sq_to_cosine() {
+        let mut a = vec![0.3, -1.2, 2.5, 0.7];
+        let mut b = vec![1.1, 0.4, -0.6, 2.0];
+        normalize(&mut a);
+        normalize(&mut b);
+
+        let l2 = l2_distance(&a, &b);
+        let converted = l2_sq_to_cosine(l2 * l2);
+        assert!((converted - cosine_similarity(&a, &b)).abs() < 1e-5);
+        assert!((cosine_to_l2_sq(converted) - l2 * l2).abs() < 1e-5);
+    }
+
     #[test]
     fn test_dot_product() {
         let a = vec![1.0, 2.0, 3.0];
@@ -181,6 +1008,42 @@ mod tests {
         assert!((dot - 32.0).abs() < 1e-6); // 1*4 + 2*5 + 3*6 = 32
     }
 
+    #[test]
+    fn test_multi_vector_score() {
+        let query = vec![vec![1.0, 0.0], vec![0.0, 1.0]];
+        let doc = vec![vec![1.0, 0.0], vec![1.0, 1.0]];
+
+        // q0: max(1.0, 0.707) = 1.0; q1: max(0.0, 0.707) = 0.707
+        let expected = 1.0 + std::f32::consts::FRAC_1_SQRT_2;
+        assert!((multi_vector_score(&query, &doc) - expected).abs() < 1e-6);
+        assert_eq!(multi_vector_score(&query, &[]), 0.0);
+
+        let poisoned = vec![vec![1.0, 0.0], vec![f32::NAN, 1.0]];
+        assert!(multi_vector_score(&query, &poisoned).is_nan());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_multi_vector_score_dimension_mismatch() {
+        multi_vector_score(&[vec![1.0, 0.0]], &[vec![1.0, 0.0, 0.0]]);
+    }
+
+    #[test]
+    fn test_matrix_apply() {
+        let m = Matrix::new(2, 3, vec![1.0, 0.0, 2.0, 0.0, 1.0, -1.0]).unwrap();
+        assert_eq!(m.apply(&[1.0, 2.0, 3.0]), vec![7.0, -1.0]);
+        assert_eq!(
+            Matrix::identity(3).apply(&[1.0, 2.0, 3.0]),
+            vec![1.0, 2.0, 3.0]
+        );
+        assert!(Matrix::new(2, 2, vec![1.0]).is_err());
+        assert_eq!(
+            Matrix::new(2, 0, vec![]).unwrap().apply(&[]),
+            vec![0.0, 0.0]
+        );
+        assert!(Matrix::identity(0).apply(&[]).is_empty());
+    }
+
     #[test]
     fn test_normalize() {
         let mut v = vec![3.0, 4.0];
@@ -223,6 +1086,31 @@ mod tests {
         assert_eq!(results[1].id, 3);
     }
 
+    #[test]
+    fn test_cosine_similarity_stable() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let dim = 4096;
+        let a: Vec<f32> = (0..dim).map(|_| rng.gen_range(0.0..1.0)).collect();
+        let b: Vec<f32> = (0..dim).map(|_| rng.gen_range(0.0..1.0)).collect();
+
+        let dot: f64 = a.iter().zip(&b).map(|(x, y)| *x as f64 * *y as f64).sum();
+        let norm_a: f64 = a.iter().map(|x| *x as f64 * *x as f64).sum::<f64>().sqrt();
+        let norm_b: f64 = b.iter().map(|x| *x as f64 * *x as f64).sum::<f64>().sqrt();
+        let truth = dot / (norm_a * norm_b);
+
+        // Sequential f32 accumulation, as the scalar fallback does
+        let naive = simd::dot_scalar(&a, &b)
+            / (simd::dot_scalar(&a, &a).sqrt() * simd::dot_scalar(&b, &b).sqrt());
+        let naive_err = (naive as f64 - truth).abs();
+        let stable_err = (cosine_similarity_stable(&a, &b) as f64 - truth).abs();
+        assert!(
+            stable_err < naive_err,
+            "stable error {stable_err} should be below naive error {naive_err}"
+        );
+    }
+
     #[test]
     #[should_panic]
     fn test_dimension_mismatch() {
@@ -230,4 +1118,345 @@ mod tests {
         let b = vec![1.0, 2.0, 3.0];
         cosine_similarity(&a, &b);
     }
+
+    #[test]
+    fn test_brute_force_topk_metric() {
+        let vectors = vec![
+            (1, vec![3.0, 0.0]),
+            (2, vec![1.0, 1.0]),
+            (3, vec![0.0, 1.0]),
+            (4, vec![1.0, -1.0]),
+        ];
+        let query = [1.0, 0.0];
+        let ids = |metric| -> Vec<u64> {
+            brute_force_topk_metric(&query, &vectors, 4, metric)
+                .iter()
+                .map(|r| r.id)
+                .collect()
+        };
+
+        // Ties (2 and 4 everywhere, 1 and 3 under L1) keep dataset order
+        assert_eq!(ids(DistanceMetric::Cosine), vec![1, 2, 4, 3]);
+        assert_eq!(ids(DistanceMetric::Dot), vec![1, 2, 4, 3]);
+        assert_eq!(ids(DistanceMetric::L2), vec![2, 4, 3, 1]);
+        assert_eq!(ids(DistanceMetric::L1), vec![2, 4, 1, 3]);
+
+        let l2 = brute_force_topk_metric(&query, &vectors, 1, DistanceMetric::L2);
+        assert_eq!(l2[0].score, 1.0);
+
+        assert!(brute_force_topk_metric(&query, &[], 5, DistanceMetric::L2).is_empty());
+        assert!(brute_force_topk(&query, &[], 5).is_empty());
+    }
+
+    #[test]
+    fn test_brute_force_topk_stream_matches_in_memory() {
+        let vectors: Vec<(u64, Vec<f32>)> = (0..10_000u64)
+            .map(|i| {
+                (
+                    i,
+                    (0..16)
+                        .map(|d| ((i * 16 + d) as f32 * 0.37).sin())
+                        .collect(),
+                )
+            })
+            .collect();
+        let query: Vec<f32> = (0..16).map(|d| (d as f32).cos()).collect();
+
+        let lazy = (0..10_000u64).map(|i| {
+            (
+                i,
+                (0..16)
+                    .map(|d| ((i * 16 + d) as f32 * 0.37).sin())
+                    .collect(),
+            )
+        });
+        let streamed = brute_force_topk_stream(&query, lazy, 25, DistanceMetric::Cosine);
+        assert_eq!(streamed, brute_force_topk(&query, &vectors, 25));
+
+        for metric in [DistanceMetric::L2, DistanceMetric::L1, DistanceMetric::Dot] {
+            assert_eq!(
+                brute_force_topk_stream(&query, vectors.iter().cloned(), 10, metric),
+                brute_force_topk_metric(&query, &vectors, 10, metric)
+            );
+        }
+        assert!(
+            brute_force_topk_stream(&query, std::iter::empty(), 5, DistanceMetric::L2).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_rerank_fixes_approximate_order() {
+        let full: HashMap<u64, Vec<f32>> = [
+            (1, vec![0.0, 1.0]),
+            (2, vec![1.0, 0.1]),
+            (3, vec![1.0, 1.0]),
+            (4, vec![1.0, 0.0]),
+        ]
+        .into_iter()
+        .collect();
+        let query = [1.0, 0.0];
+        // Quantized scores got the order backwards; 9 has no full vector
+        let approx: Vec<SearchResult> = [(1, 0.9), (9, 0.8), (3, 0.7), (2, 0.6), (4, 0.5)]
+            .into_iter()
+            .map(|(id, score)| SearchResult::new(id, score))
+            .collect();
+
+        let reranked = rerank(&query, &approx, &full, DistanceMetric::Cosine, 3);
+        let ids: Vec<u64> = reranked.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![4, 2, 3]);
+        assert!((reranked[0].score - 1.0).abs() < 1e-6);
+
+        let l2 = rerank(&query, &approx, &full, DistanceMetric::L2, 10);
+        let ids: Vec<u64> = l2.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![4, 2, 3, 1]);
+        assert!(rerank(&query, &approx, &full, DistanceMetric::L2, 0).is_empty());
+    }
+
+    #[test]
+    fn test_nan_scores_rank_last() {
+        let vectors = vec![
+            (0, vec![f32::NAN, 1.0]),
+            (1, vec![1.0, 0.0]),
+            (2, vec![-f32::NAN, 0.0]),
+            (3, vec![0.0, 1.0]),
+        ];
+        for metric in [DistanceMetric::Cosine, DistanceMetric::L2] {
+            let results = brute_force_topk_metric(&[1.0, 0.0], &vectors, 4, metric);
+            let ids: Vec<u64> = results.iter().map(|r| r.id).collect();
+            assert_eq!(&ids[..2], &[1, 3]);
+            assert!(results[2..].iter().all(|r| r.score.is_nan()));
+        }
+        assert_eq!(brute_force_topk_parallel(&[1.0, 0.0], &vectors, 1)[0].id, 1);
+
+        assert!(matches!(
+            cosine_similarity_checked(&[f32::NAN, 1.0], &[1.0, 0.0]),
+            Err(Error::InvalidConfig(_))
+        ));
+        assert!(cosine_similarity_checked(&[1.0], &[f32::INFINITY]).is_err());
+        assert!(cosine_similarity_checked(&[1.0], &[1.0, 0.0]).is_err());
+        assert_eq!(
+            cosine_similarity_checked(&[1.0, 0.0], &[2.0, 0.0]).unwrap(),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_bounded_topk_matches_full_sort() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(21);
+        // Coarse values so plenty of scores tie
+        let data: Vec<(u64, Vec<f32>)> = (0..3000u64)
+            .map(|i| (i, (0..8).map(|_| rng.gen_range(-2..3) as f32).collect()))
+            .collect();
+        let query: Vec<f32> = (0..8).map(|_| rng.gen_range(-2..3) as f32).collect();
+
+        let full_sort = |metric: DistanceMetric, k: usize| {
+            let mut all: Vec<SearchResult> = data
+                .iter()
+                .map(|(id, v)| SearchResult::new(*id, metric.score(&query, v)))
+                .collect();
+            if metric.higher_is_better() {
+                all.sort_by(|a, b| b.score.total_cmp(&a.score));
+            } else {
+                all.sort_by(|a, b| a.score.total_cmp(&b.score));
+            }
+            all.truncate(k);
+            all
+        };
+
+        for k in [0, 1, 10, 100, 5000] {
+            for metric in [
+                DistanceMetric::Cosine,
+                DistanceMetric::L2,
+                DistanceMetric::Dot,
+                DistanceMetric::L1,
+            ] {
+                let ids = |r: Vec<SearchResult>| r.into_iter().map(|r| r.id).collect::<Vec<_>>();
+                let expected = full_sort(metric, k);
+                let actual = brute_force_topk_metric(&query, &data, k, metric);
+                assert_eq!(
+                    ids(actual.clone()),
+                    ids(expected.clone()),
+                    "{:?} k={}",
+                    metric,
+                    k
+                );
+                for (a, e) in actual.iter().zip(&expected) {
+                    assert!((a.score - e.score).abs() < 1e-5);
+                }
+            }
+            assert_eq!(
+                brute_force_topk_parallel(&query, &data, k),
+                full_sort(DistanceMetric::Cosine, k)
+            );
+        }
+    }
+
+    #[test]
+    fn test_l1_distance() {
+        assert_eq!(l1_distance(&[0.0, 0.0], &[3.0, 4.0]), 7.0);
+        assert_eq!(l1_distance(&[1.0, -2.0, 3.0], &[1.0, -2.0, 3.0]), 0.0);
+        let a: Vec<f32> = (0..37).map(|i| i as f32 * 0.5).collect();
+        let b: Vec<f32> = (0..37).map(|i| -(i as f32)).collect();
+        let expected: f32 = (0..37).map(|i| i as f32 * 1.5).sum();
+        assert!((l1_distance(&a, &b) - expected).abs() < 1e-3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Vector dimensions must match")]
+    fn test_l1_distance_dimension_mismatch() {
+        l1_distance(&[1.0], &[1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_l2_distance_squared() {
+        let a = [1.0, 2.0, 3.0];
+        let b = [4.0, 6.0, 3.0];
+        assert!((l2_distance_squared(&a, &b) - 25.0).abs() < 1e-6);
+        assert!((l2_distance(&a, &b) - 5.0).abs() < 1e-6);
+
+        let data = vec![(1, b.to_vec()), (2, a.to_vec())];
+        let results = brute_force_topk_metric(&a, &data, 2, DistanceMetric::L2);
+        assert_eq!(results[0], SearchResult::new(2, 0.0));
+        assert!((results[1].score - 5.0).abs() < 1e-6);
+    }
+
+    /// `clusters` well separated groups of `per_cluster` noisy points
+    fn clustered(clusters: usize, per_cluster: usize, dim: usize) -> Vec<(usize, Vec<f32>)> {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+        let mut rng = StdRng::seed_from_u64(11);
+        let centers: Vec<Vec<f32>> = (0..clusters)
+            .map(|_| (0..dim).map(|_| rng.gen_range(-10.0..10.0)).collect())
+            .collect();
+        centers
+            .iter()
+            .enumerate()
+            .flat_map(|(c, center)| {
+                let points: Vec<(usize, Vec<f32>)> = (0..per_cluster)
+                    .map(|_| {
+                        (
+                            c,
+                            center
+                                .iter()
+                                .map(|x| x + rng.gen_range(-0.5..0.5))
+                                .collect(),
+                        )
+                    })
+                    .collect();
+                points
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_product_quantizer_reconstruction() {
+        let data = clustered(8, 50, 16);
+        let vectors: Vec<Vec<f32>> = data.iter().map(|(_, v)| v.clone()).collect();
+        let pq = ProductQuantizer::train(&vectors, 4, 16).unwrap();
+        assert_eq!(pq.subquantizers(), 4);
+
+        let mut total_error = 0.0;
+        for v in &vectors {
+            let code = pq.encode(v);
+            assert_eq!(code.len(), 4);
+            let decoded = pq.decode(&code);
+            let error = l2_distance(v, &decoded);
+            assert!((pq.asymmetric_distance(v, &code) - error).abs() < 1e-3);
+            total_error += error;
+        }
+        // Within-cluster noise is about 1.15 in L2, against norms around 23
+        let mean_error = total_error / vectors.len() as f32;
+        assert!(mean_error < 1.5, "mean reconstruction error {}", mean_error);
+
+        assert!(ProductQuantizer::train(&vectors, 5, 16).is_err());
+        assert!(ProductQuantizer::train(&vectors, 4, 257).is_err());
+        assert!(ProductQuantizer::train(&[], 4, 16).is_err());
+    }
+
+    #[test]
+    fn test_product_quantizer_ranks_nearest_neighbor() {
+        let data = clustered(8, 50, 16);
+        let vectors: Vec<Vec<f32>> = data.iter().map(|(_, v)| v.clone()).collect();
+        let pq = ProductQuantizer::train(&vectors, 4, 16).unwrap();
+        let codes: Vec<Vec<u8>> = vectors.iter().map(|v| pq.encode(v)).collect();
+
+        for (cluster, query) in data.iter().step_by(37) {
+            let table = pq.distance_table(query);
+            let best = codes
+                .iter()
+                .enumerate()
+                .min_by(|a, b| table.distance(a.1).total_cmp(&table.distance(b.1)))
+                .unwrap()
+                .0;
+            assert_eq!(data[best].0, *cluster);
+        }
+    }
+
+    #[test]
+    fn test_scalar_quantizer_reconstruction() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(5);
+        let data: Vec<Vec<f32>> = (0..500)
+            .map(|_| {
+                (0..32)
+                    .map(|d| rng.gen_range(-1.0..1.0) * (d + 1) as f32)
+                    .collect()
+            })
+            .collect();
+        let sq = ScalarQuantizer::train(&data).unwrap();
+        assert_eq!(sq.dimension(), 32);
+
+        let bound = sq.max_error() + 1e-5;
+        assert!(bound < 32.0 / 255.0 + 1e-3);
+        for v in &data {
+            let restored = sq.dequantize(&sq.quantize(v));
+            for (x, y) in v.iter().zip(&restored) {
+                assert!((x - y).abs() <= bound, "{} vs {}", x, y);
+            }
+        }
+
+        // Out-of-range values clamp, constant dimensions round-trip exactly
+        let constant = ScalarQuantizer::train(&[vec![0.0, 3.0], vec![1.0, 3.0]]).unwrap();
+        assert_eq!(constant.quantize(&[5.0, 3.0]), vec![127, -128]);
+        assert_eq!(constant.quantize(&[-5.0, 3.0]), vec![-128, -128]);
+        assert_eq!(constant.dequantize(&[127, -128]), vec![1.0, 3.0]);
+
+        assert!(ScalarQuantizer::train(&[]).is_err());
+        assert!(ScalarQuantizer::train(&[vec![1.0], vec![1.0, 2.0]]).is_err());
+    }
+
+    #[test]
+    fn test_quantized_topk_matches_float() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(9);
+        let data: Vec<(u64, Vec<f32>)> = (0..2000u64)
+            .map(|i| (i, (0..64).map(|_| rng.gen_range(-1.0..1.0)).collect()))
+            .collect();
+        let vectors: Vec<Vec<f32>> = data.iter().map(|(_, v)| v.clone()).collect();
+        let sq = ScalarQuantizer::train(&vectors).unwrap();
+        let codes: Vec<Vec<i8>> = vectors.iter().map(|v| sq.quantize(v)).collect();
+
+        for (_, query) in data.iter().step_by(200) {
+            let truth: Vec<u64> = brute_force_topk_metric(query, &data, 10, DistanceMetric::L2)
+                .into_iter()
+                .map(|r| r.id)
+                .collect();
+
+            let q = sq.quantize(query);
+            let mut approx: Vec<(u64, f32)> = codes
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (i as u64, l2_distance_i8(&q, c)))
+                .collect();
+            approx.sort_by(|a, b| a.1.total_cmp(&b.1));
+            let hits = approx[..10]
+                .iter()
+                .filter(|(id, _)| truth.contains(id))
+                .count();
+            assert!(hits >= 8, "only {} of 10 top results matched", hits);
+        }
+    }
 }
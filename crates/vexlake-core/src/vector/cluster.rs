@@ -0,0 +1,192 @@
+//! K-means clustering
+//!
+//! Lloyd's algorithm with k-means++ seeding, shared by IVF list training and
+//! product quantizer codebooks and usable on its own to summarize a dataset
+//! by its centroids. The assignment step runs in parallel with rayon.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
+
+use super::{l2_distance_squared, DistanceMetric};
+
+/// Cluster `vectors` into `k` groups
+///
+/// Returns the centroids and, for each input vector, the index of its
+/// centroid. Vectors are assigned to the best-scoring centroid under
+/// `metric` and centroids are the mean of their members. Stops after
+/// `max_iters` rounds or once no assignment changes. Seeding is
+/// deterministic, so equal inputs give equal clusters.
+///
+/// `k` is capped at the number of vectors; no vectors or `k == 0` give no
+/// centroids. All vectors must have the same length.
+pub fn kmeans(
+    vectors: &[Vec<f32>],
+    k: usize,
+    max_iters: usize,
+    metric: DistanceMetric,
+) -> (Vec<Vec<f32>>, Vec<usize>) {
+    let points: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+    kmeans_seeded(&points, k, max_iters, metric, k as u64)
+}
+
+/// [`kmeans`] over borrowed points with an explicit RNG seed
+pub(crate) fn kmeans_seeded(
+    points: &[&[f32]],
+    k: usize,
+    max_iters: usize,
+    metric: DistanceMetric,
+    seed: u64,
+) -> (Vec<Vec<f32>>, Vec<usize>) {
+    let k = k.min(points.len());
+    if k == 0 {
+        return (Vec::new(), vec![0; points.len()]);
+    }
+    let dim = points[0].len();
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    // Each new seed is drawn with probability proportional to its squared
+    // distance from the nearest seed so far, spreading seeds across clusters
+    let mut centroids = vec![points[rng.gen_range(0..points.len())].to_vec()];
+    let mut closest: Vec<f32> = points
+        .iter()
+        .map(|p| seeding_distance(metric, p, &centroids[0]))
+        .collect();
+    while centroids.len() < k {
+        let total: f32 = closest.iter().sum();
+        let next = if total > 0.0 {
+            let mut target = rng.gen_range(0.0..total);
+            closest
+                .iter()
+                .position(|&d| {
+                    target -= d;
+                    target < 0.0
+                })
+                .unwrap_or(points.len() - 1)
+        } else {
+            rng.gen_range(0..points.len())
+        };
+        let seed = points[next].to_vec();
+        for (d, p) in closest.iter_mut().zip(points) {
+            *d = d.min(seeding_distance(metric, p, &seed));
+        }
+        centroids.push(seed);
+    }
+
+    let mut assignment = vec![usize::MAX; points.len()];
+    for _ in 0..max_iters {
+        let next: Vec<usize> = points
+            .par_iter()
+            .map(|p| nearest(metric, &centroids, p))
+            .collect();
+        if next == assignment {
+            break;
+        }
+        assignment = next;
+
+        let mut sums = vec![vec![0.0f32; dim]; k];
+        let mut counts = vec![0usize; k];
+        for (p, &c) in points.iter().zip(&assignment) {
+            counts[c] += 1;
+            for (s, x) in sums[c].iter_mut().zip(p.iter()) {
+                *s += x;
+            }
+        }
+        for (c, (sum, count)) in sums.into_iter().zip(counts).enumerate() {
+            centroids[c] = if count == 0 {
+                // Reseed an empty cluster from a random point
+                points[rng.gen_range(0..points.len())].to_vec()
+            } else {
+                sum.into_iter().map(|s| s / count as f32).collect()
+            };
+        }
+    }
+
+    if assignment.contains(&usize::MAX) {
+        assignment = points
+            .par_iter()
+            .map(|p| nearest(metric, &centroids, p))
+            .collect();
+    }
+    (centroids, assignment)
+}
+
+/// Non-negative dissimilarity used to weight k-means++ seeding
+///
+/// Inner product is not a distance, so it seeds by squared L2.
+fn seeding_distance(metric: DistanceMetric, a: &[f32], b: &[f32]) -> f32 {
+    match metric {
+        DistanceMetric::Cosine => (1.0 - metric.score(a, b)).max(0.0),
+        DistanceMetric::L1 => metric.score(a, b).powi(2),
+        DistanceMetric::L2 | DistanceMetric::Dot => l2_distance_squared(a, b),
+    }
+}
+
+/// Index of the best-scoring centroid, the first on ties
+fn nearest(metric: DistanceMetric, centroids: &[Vec<f32>], p: &[f32]) -> usize {
+    let score = |c: &[f32]| match metric {
+        DistanceMetric::L2 => l2_distance_squared(p, c),
+        _ => metric.score(p, c),
+    };
+    let mut best = 0;
+    let mut best_score = score(&centroids[0]);
+    for (i, c) in centroids.iter().enumerate().skip(1) {
+        let s = score(c);
+        let better = if metric.higher_is_better() {
+            s > best_score
+        } else {
+            s < best_score
+        };
+        if better {
+            best = i;
+            best_score = s;
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Standard normal sample via Box-Muller
+    fn gaussian(rng: &mut StdRng) -> f32 {
+        let u: f32 = rng.gen_range(f32::EPSILON..1.0);
+        let v: f32 = rng.gen();
+        (-2.0 * u.ln()).sqrt() * (2.0 * std::f32::consts::PI * v).cos()
+    }
+
+    #[test]
+    fn test_kmeans_recovers_blobs() {
+        let means = [[0.0f32, 0.0], [10.0, 10.0], [-10.0, 8.0]];
+        let mut rng = StdRng::seed_from_u64(7);
+        let vectors: Vec<Vec<f32>> = (0..300)
+            .map(|i| {
+                let mean = means[i % 3];
+                mean.iter().map(|m| m + 0.5 * gaussian(&mut rng)).collect()
+            })
+            .collect();
+
+        let (centroids, assignment) = kmeans(&vectors, 3, 50, DistanceMetric::L2);
+        assert_eq!(centroids.len(), 3);
+        assert_eq!(assignment.len(), vectors.len());
+        for mean in &means {
+            let closest = centroids
+                .iter()
+                .map(|c| l2_distance_squared(c, mean).sqrt())
+                .fold(f32::INFINITY, f32::min);
+            assert!(
+                closest < 0.3,
+                "no centroid near {:?}: {:?}",
+                mean,
+                centroids
+            );
+        }
+        // Members of one blob share a cluster
+        for (i, &c) in assignment.iter().enumerate() {
+            assert_eq!(c, assignment[i % 3]);
+        }
+
+        assert_eq!(kmeans(&vectors[..2], 5, 10, DistanceMetric::L2).0.len(), 2);
+        assert!(kmeans(&[], 3, 10, DistanceMetric::Cosine).0.is_empty());
+    }
+}
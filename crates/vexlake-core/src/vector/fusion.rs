@@ -0,0 +1,88 @@
+//! Fusion of ranked result lists for hybrid search
+//!
+//! Combines the outputs of several retrievers, e.g. a dense HNSW search and a
+//! sparse keyword search, into one ranking. IDs appearing in several lists are
+//! merged and their contributions summed.
+
+use std::collections::HashMap;
+
+use super::SearchResult;
+use crate::{Error, Result};
+
+/// Fuse ranked lists with Reciprocal Rank Fusion
+///
+/// Each list contributes `1 / (k_rrf + rank)` per ID, with 1-based ranks, so
+/// only positions matter and raw scores need not be comparable across lists.
+/// `k_rrf` is typically 60. The returned scores are the fused RRF scores.
+pub fn reciprocal_rank_fusion(result_lists: &[Vec<SearchResult>], k_rrf: f32) -> Vec<SearchResult> {
+    let mut fused: HashMap<u64, f32> = HashMap::new();
+    for list in result_lists {
+        for (rank, result) in list.iter().enumerate() {
+            *fused.entry(result.id).or_default() += 1.0 / (k_rrf + rank as f32 + 1.0);
+        }
+    }
+    into_ranked(fused)
+}
+
+/// Fuse lists by a weighted sum of their scores
+///
+/// Scores should be on comparable scales (e.g. normalized to [0, 1]). An ID
+/// missing from a list contributes nothing for it.
+pub fn linear_fusion(lists: &[Vec<SearchResult>], weights: &[f32]) -> Result<Vec<SearchResult>> {
+    if lists.len() != weights.len() {
+        return Err(Error::InvalidConfig(format!(
+            "{} result lists but {} weights",
+            lists.len(),
+            weights.len()
+        )));
+    }
+
+    let mut fused: HashMap<u64, f32> = HashMap::new();
+    for (list, weight) in lists.iter().zip(weights) {
+        for result in list {
+            *fused.entry(result.id).or_default() += weight * result.score;
+        }
+    }
+    Ok(into_ranked(fused))
+}
+
+/// Sort by fused score descending, breaking ties by ID for determinism
+fn into_ranked(fused: HashMap<u64, f32>) -> Vec<SearchResult> {
+    let mut results: Vec<SearchResult> = fused
+        .into_iter()
+        .map(|(id, score)| SearchResult::new(id, score))
+        .collect();
+    results.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)));
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fusion_orderings() {
+        let dense = vec![
+            SearchResult::new(1, 0.9),
+            SearchResult::new(2, 0.8),
+            SearchResult::new(3, 0.7),
+        ];
+        let sparse = vec![
+            SearchResult::new(3, 12.0),
+            SearchResult::new(1, 9.0),
+            SearchResult::new(4, 2.0),
+        ];
+
+        // 1: ranks 1 and 2; 3: ranks 3 and 1; 2: rank 2 only; 4: rank 3 only
+        let rrf = reciprocal_rank_fusion(&[dense.clone(), sparse.clone()], 60.0);
+        let ids: Vec<u64> = rrf.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![1, 3, 2, 4]);
+        assert!((rrf[0].score - (1.0 / 61.0 + 1.0 / 62.0)).abs() < 1e-6);
+
+        let linear = linear_fusion(&[dense.clone(), sparse], &[1.0, 0.0]).unwrap();
+        let ids: Vec<u64> = linear.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+
+        assert!(linear_fusion(&[dense], &[0.5, 0.5]).is_err());
+    }
+}
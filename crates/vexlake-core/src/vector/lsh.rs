@@ -0,0 +1,161 @@
+//! Near-duplicate detection with locality-sensitive hashing
+//!
+//! Vectors are hashed by the signs of their projections onto random
+//! hyperplanes (SimHash). Vectors at a small angle share most sign bits and so
+//! tend to land in the same bucket, which limits exact cosine comparisons to
+//! the few vectors in that bucket instead of the whole dataset.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::HashMap;
+
+use super::{cosine_similarity, dot_product};
+use crate::{Error, Result};
+
+/// Random-hyperplane LSH deduplicator
+pub struct LshDeduper {
+    dim: usize,
+    planes: Vec<Vec<f32>>,
+    buckets: HashMap<u64, Vec<u64>>,
+    vectors: HashMap<u64, Vec<f32>>,
+}
+
+impl LshDeduper {
+    /// Create a deduplicator with `num_hashes` hyperplanes (1 to 64)
+    ///
+    /// More hyperplanes give smaller buckets, so fewer exact comparisons but a
+    /// higher chance that a near-duplicate lands in a different bucket.
+    pub fn new(dim: usize, num_hashes: usize, seed: u64) -> Result<Self> {
+        if dim == 0 || !(1..=64).contains(&num_hashes) {
+            return Err(Error::InvalidConfig(format!(
+                "LSH needs dim > 0 and 1-64 hashes, got dim {} and {} hashes",
+                dim, num_hashes
+            )));
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let planes = (0..num_hashes)
+            .map(|_| (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect();
+
+        Ok(Self {
+            dim,
+            planes,
+            buckets: HashMap::new(),
+            vectors: HashMap::new(),
+        })
+    }
+
+    /// Bucket signature of a vector: one sign bit per hyperplane
+    pub fn signature(&self, vector: &[f32]) -> Result<u64> {
+        self.check_dimension(vector)?;
+        Ok(self
+            .planes
+            .iter()
+            .enumerate()
+            .filter(|(_, plane)| dot_product(plane, vector) >= 0.0)
+            .fold(0, |sig, (bit, _)| sig | (1 << bit)))
+    }
+
+    /// IDs of stored vectors sharing a bucket with `vector`
+    pub fn candidates(&self, vector: &[f32]) -> Result<Vec<u64>> {
+        let signature = self.signature(vector)?;
+        Ok(self.buckets.get(&signature).cloned().unwrap_or_default())
+    }
+
+    /// Find a stored vector with cosine similarity of at least `threshold`
+    ///
+    /// Only candidates from the same bucket are compared exactly.
+    pub fn find_duplicate(&self, vector: &[f32], threshold: f32) -> Result<Option<u64>> {
+        Ok(self
+            .candidates(vector)?
+            .into_iter()
+            .find(|id| cosine_similarity(vector, &self.vectors[id]) >= threshold))
+    }
+
+    /// Store a vector unconditionally, replacing any vector with the same ID
+    pub fn insert(&mut self, id: u64, vector: Vec<f32>) -> Result<()> {
+        let signature = self.signature(&vector)?;
+        if let Some(old) = self.vectors.insert(id, vector) {
+            let old_signature = self.signature(&old)?;
+            if let Some(bucket) = self.buckets.get_mut(&old_signature) {
+                bucket.retain(|&other| other != id);
+            }
+        }
+        self.buckets.entry(signature).or_default().push(id);
+        Ok(())
+    }
+
+    /// Store a vector unless it duplicates a stored one
+    ///
+    /// Returns the ID of the existing duplicate, or `None` if it was stored.
+    pub fn insert_if_unique(
+        &mut self,
+        id: u64,
+        vector: Vec<f32>,
+        threshold: f32,
+    ) -> Result<Option<u64>> {
+        if let Some(existing) = self.find_duplicate(&vector, threshold)? {
+            return Ok(Some(existing));
+        }
+        self.insert(id, vector)?;
+        Ok(None)
+    }
+
+    /// Number of stored vectors
+    pub fn len(&self) -> usize {
+        self.vectors.len()
+    }
+
+    /// Check if no vectors are stored
+    pub fn is_empty(&self) -> bool {
+        self.vectors.is_empty()
+    }
+
+    fn check_dimension(&self, vector: &[f32]) -> Result<()> {
+        if vector.len() != self.dim {
+            return Err(Error::DimensionMismatch {
+                expected: self.dim,
+                actual: vector.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lsh_dedup() {
+        let mut dedup = LshDeduper::new(8, 12, 42).unwrap();
+        let base = vec![0.9, -0.3, 0.5, 0.1, -0.7, 0.2, 0.4, -0.1];
+        let near: Vec<f32> = base.iter().map(|x| x + 0.001).collect();
+        let opposite: Vec<f32> = base.iter().map(|x| -x).collect();
+
+        assert_eq!(
+            dedup.signature(&base).unwrap(),
+            dedup.signature(&near).unwrap()
+        );
+        assert_ne!(
+            dedup.signature(&base).unwrap(),
+            dedup.signature(&opposite).unwrap()
+        );
+
+        let mut tweaked = base.clone();
+        tweaked[0] += 0.05;
+
+        assert_eq!(dedup.insert_if_unique(1, base, 0.99).unwrap(), None);
+        assert_eq!(dedup.insert_if_unique(2, near, 0.99).unwrap(), Some(1));
+        assert_eq!(dedup.insert_if_unique(3, opposite, 0.99).unwrap(), None);
+        assert_eq!(dedup.len(), 2);
+
+        // Sharing a bucket is not enough: exact verification applies the threshold
+        assert_eq!(dedup.candidates(&tweaked).unwrap(), vec![1]);
+        assert_eq!(dedup.find_duplicate(&tweaked, 0.9999).unwrap(), None);
+        assert_eq!(dedup.find_duplicate(&tweaked, 0.99).unwrap(), Some(1));
+
+        assert!(LshDeduper::new(8, 65, 0).is_err());
+        assert!(dedup.signature(&[1.0]).is_err());
+    }
+}
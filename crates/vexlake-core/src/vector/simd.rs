@@ -0,0 +1,361 @@
+//! Runtime-dispatched distance kernels
+//!
+//! The best kernel set for the running CPU is selected once, on first use, and
+//! cached in a `OnceLock` as plain function pointers. Later calls pay a single
+//! indirect call instead of repeating feature detection.
+//!
+//! x86_64 uses AVX2/FMA kernels and aarch64 uses NEON kernels. AVX-512
+//! capable machines currently use the AVX2/FMA kernels, since the AVX-512
+//! intrinsics need a newer toolchain than the crate's MSRV.
+
+use std::sync::OnceLock;
+
+/// Kernel function signature: both slices have the same length
+type Kernel = fn(&[f32], &[f32]) -> f32;
+
+/// Set of distance kernels for one instruction set
+pub(crate) struct Kernels {
+    /// Name of the selected backend
+    pub name: &'static str,
+    /// Dot product
+    pub dot: Kernel,
+    /// Squared L2 distance
+    pub l2_squared: Kernel,
+    /// L1 (Manhattan) distance
+    pub l1: Kernel,
+}
+
+static KERNELS: OnceLock<Kernels> = OnceLock::new();
+
+/// Get the kernels for this CPU, detecting them on first use
+pub(crate) fn kernels() -> &'static Kernels {
+    KERNELS.get_or_init(detect)
+}
+
+/// Name of the kernel backend selected for this CPU
+pub fn backend() -> &'static str {
+    kernels().name
+}
+
+fn detect() -> Kernels {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+            return Kernels {
+                name: "avx2",
+                dot: avx2::dot,
+                l2_squared: avx2::l2_squared,
+                l1: avx2::l1,
+            };
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            return Kernels {
+                name: "neon",
+                dot: neon::dot,
+                l2_squared: neon::l2_squared,
+                l1: neon::l1,
+            };
+        }
+    }
+
+    Kernels {
+        name: "scalar",
+        dot: dot_scalar,
+        l2_squared: l2_squared_scalar,
+        l1: l1_scalar,
+    }
+}
+
+/// Scalar dot product
+pub fn dot_scalar(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+/// Scalar squared L2 distance
+pub fn l2_squared_scalar(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b.iter()).map(|(x, y)| (x - y).powi(2)).sum()
+}
+
+/// Scalar L1 distance
+pub fn l1_scalar(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b.iter()).map(|(x, y)| (x - y).abs()).sum()
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    // SAFETY (for the safe wrappers below): they are only installed in the
+    // kernel table after `detect` confirmed AVX2 and FMA support.
+
+    pub(super) fn dot(a: &[f32], b: &[f32]) -> f32 {
+        unsafe { dot_impl(a, b) }
+    }
+
+    pub(super) fn l2_squared(a: &[f32], b: &[f32]) -> f32 {
+        unsafe { l2_squared_impl(a, b) }
+    }
+
+    pub(super) fn l1(a: &[f32], b: &[f32]) -> f32 {
+        unsafe { l1_impl(a, b) }
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn dot_impl(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len().min(b.len());
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc0 = _mm256_setzero_ps();
+        let mut acc1 = _mm256_setzero_ps();
+
+        let mut i = 0;
+        while i + 16 <= n {
+            acc0 = _mm256_fmadd_ps(_mm256_loadu_ps(pa.add(i)), _mm256_loadu_ps(pb.add(i)), acc0);
+            acc1 = _mm256_fmadd_ps(
+                _mm256_loadu_ps(pa.add(i + 8)),
+                _mm256_loadu_ps(pb.add(i + 8)),
+                acc1,
+            );
+            i += 16;
+        }
+        if i + 8 <= n {
+            acc0 = _mm256_fmadd_ps(_mm256_loadu_ps(pa.add(i)), _mm256_loadu_ps(pb.add(i)), acc0);
+            i += 8;
+        }
+
+        let mut sum = hsum(_mm256_add_ps(acc0, acc1));
+        while i < n {
+            sum += a[i] * b[i];
+            i += 1;
+        }
+        sum
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn l2_squared_impl(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len().min(b.len());
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc0 = _mm256_setzero_ps();
+        let mut acc1 = _mm256_setzero_ps();
+
+        let mut i = 0;
+        while i + 16 <= n {
+            let d0 = _mm256_sub_ps(_mm256_loadu_ps(pa.add(i)), _mm256_loadu_ps(pb.add(i)));
+            let d1 = _mm256_sub_ps(
+                _mm256_loadu_ps(pa.add(i + 8)),
+                _mm256_loadu_ps(pb.add(i + 8)),
+            );
+            acc0 = _mm256_fmadd_ps(d0, d0, acc0);
+            acc1 = _mm256_fmadd_ps(d1, d1, acc1);
+            i += 16;
+        }
+        if i + 8 <= n {
+            let d = _mm256_sub_ps(_mm256_loadu_ps(pa.add(i)), _mm256_loadu_ps(pb.add(i)));
+            acc0 = _mm256_fmadd_ps(d, d, acc0);
+            i += 8;
+        }
+
+        let mut sum = hsum(_mm256_add_ps(acc0, acc1));
+        while i < n {
+            let d = a[i] - b[i];
+            sum += d * d;
+            i += 1;
+        }
+        sum
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn l1_impl(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len().min(b.len());
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        // Clearing the sign bit takes the absolute value
+        let sign = _mm256_set1_ps(-0.0);
+        let mut acc0 = _mm256_setzero_ps();
+        let mut acc1 = _mm256_setzero_ps();
+
+        let mut i = 0;
+        while i + 16 <= n {
+            let d0 = _mm256_sub_ps(_mm256_loadu_ps(pa.add(i)), _mm256_loadu_ps(pb.add(i)));
+            let d1 = _mm256_sub_ps(
+                _mm256_loadu_ps(pa.add(i + 8)),
+                _mm256_loadu_ps(pb.add(i + 8)),
+            );
+            acc0 = _mm256_add_ps(acc0, _mm256_andnot_ps(sign, d0));
+            acc1 = _mm256_add_ps(acc1, _mm256_andnot_ps(sign, d1));
+            i += 16;
+        }
+        if i + 8 <= n {
+            let d = _mm256_sub_ps(_mm256_loadu_ps(pa.add(i)), _mm256_loadu_ps(pb.add(i)));
+            acc0 = _mm256_add_ps(acc0, _mm256_andnot_ps(sign, d));
+            i += 8;
+        }
+
+        let mut sum = hsum(_mm256_add_ps(acc0, acc1));
+        while i < n {
+            sum += (a[i] - b[i]).abs();
+            i += 1;
+        }
+        sum
+    }
+
+    /// Horizontal sum of the eight lanes
+    #[target_feature(enable = "avx2")]
+    unsafe fn hsum(v: __m256) -> f32 {
+        let lo = _mm256_castps256_ps128(v);
+        let hi = _mm256_extractf128_ps(v, 1);
+        let s = _mm_add_ps(lo, hi);
+        let s = _mm_add_ps(s, _mm_movehl_ps(s, s));
+        let s = _mm_add_ss(s, _mm_shuffle_ps(s, s, 0x55));
+        _mm_cvtss_f32(s)
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use std::arch::aarch64::*;
+
+    // SAFETY (for the safe wrappers below): they are only installed in the
+    // kernel table after `detect` confirmed NEON support.
+
+    pub(super) fn dot(a: &[f32], b: &[f32]) -> f32 {
+        unsafe { dot_impl(a, b) }
+    }
+
+    pub(super) fn l2_squared(a: &[f32], b: &[f32]) -> f32 {
+        unsafe { l2_squared_impl(a, b) }
+    }
+
+    pub(super) fn l1(a: &[f32], b: &[f32]) -> f32 {
+        unsafe { l1_impl(a, b) }
+    }
+
+    #[target_feature(enable = "neon")]
+    unsafe fn dot_impl(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len().min(b.len());
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc0 = vdupq_n_f32(0.0);
+        let mut acc1 = vdupq_n_f32(0.0);
+
+        let mut i = 0;
+        while i + 8 <= n {
+            acc0 = vfmaq_f32(acc0, vld1q_f32(pa.add(i)), vld1q_f32(pb.add(i)));
+            acc1 = vfmaq_f32(acc1, vld1q_f32(pa.add(i + 4)), vld1q_f32(pb.add(i + 4)));
+            i += 8;
+        }
+        if i + 4 <= n {
+            acc0 = vfmaq_f32(acc0, vld1q_f32(pa.add(i)), vld1q_f32(pb.add(i)));
+            i += 4;
+        }
+
+        let mut sum = vaddvq_f32(vaddq_f32(acc0, acc1));
+        while i < n {
+            sum += a[i] * b[i];
+            i += 1;
+        }
+        sum
+    }
+
+    #[target_feature(enable = "neon")]
+    unsafe fn l2_squared_impl(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len().min(b.len());
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc0 = vdupq_n_f32(0.0);
+        let mut acc1 = vdupq_n_f32(0.0);
+
+        let mut i = 0;
+        while i + 8 <= n {
+            let d0 = vsubq_f32(vld1q_f32(pa.add(i)), vld1q_f32(pb.add(i)));
+            let d1 = vsubq_f32(vld1q_f32(pa.add(i + 4)), vld1q_f32(pb.add(i + 4)));
+            acc0 = vfmaq_f32(acc0, d0, d0);
+            acc1 = vfmaq_f32(acc1, d1, d1);
+            i += 8;
+        }
+        if i + 4 <= n {
+            let d = vsubq_f32(vld1q_f32(pa.add(i)), vld1q_f32(pb.add(i)));
+            acc0 = vfmaq_f32(acc0, d, d);
+            i += 4;
+        }
+
+        let mut sum = vaddvq_f32(vaddq_f32(acc0, acc1));
+        while i < n {
+            let d = a[i] - b[i];
+            sum += d * d;
+            i += 1;
+        }
+        sum
+    }
+
+    #[target_feature(enable = "neon")]
+    unsafe fn l1_impl(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len().min(b.len());
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc0 = vdupq_n_f32(0.0);
+        let mut acc1 = vdupq_n_f32(0.0);
+
+        let mut i = 0;
+        while i + 8 <= n {
+            acc0 = vaddq_f32(acc0, vabdq_f32(vld1q_f32(pa.add(i)), vld1q_f32(pb.add(i))));
+            acc1 = vaddq_f32(
+                acc1,
+                vabdq_f32(vld1q_f32(pa.add(i + 4)), vld1q_f32(pb.add(i + 4))),
+            );
+            i += 8;
+        }
+        if i + 4 <= n {
+            acc0 = vaddq_f32(acc0, vabdq_f32(vld1q_f32(pa.add(i)), vld1q_f32(pb.add(i))));
+            i += 4;
+        }
+
+        let mut sum = vaddvq_f32(vaddq_f32(acc0, acc1));
+        while i < n {
+            sum += (a[i] - b[i]).abs();
+            i += 1;
+        }
+        sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn test_dispatch_matches_scalar() {
+        let mut rng = StdRng::seed_from_u64(1);
+        // Odd lengths exercise the remainder loops
+        for dim in [1, 7, 8, 15, 16, 33, 128, 1000] {
+            let a: Vec<f32> = (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            let b: Vec<f32> = (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect();
+
+            let k = kernels();
+            let close = |x: f32, y: f32| (x - y).abs() <= 1e-5 * y.abs().max(1.0);
+            assert!(close((k.dot)(&a, &b), dot_scalar(&a, &b)));
+            assert!(close((k.l2_squared)(&a, &b), l2_squared_scalar(&a, &b)));
+            assert!(close((k.l1)(&a, &b), l1_scalar(&a, &b)));
+        }
+        assert!(!backend().is_empty());
+    }
+
+    #[test]
+    fn test_public_functions_match_scalar_768() {
+        use crate::vector::{cosine_similarity, dot_product, l2_distance};
+
+        let mut rng = StdRng::seed_from_u64(768);
+        let a: Vec<f32> = (0..768).map(|_| rng.gen_range(-1.0..1.0)).collect();
+        let b: Vec<f32> = (0..768).map(|_| rng.gen_range(-1.0..1.0)).collect();
+
+        let scalar_cosine =
+            dot_scalar(&a, &b) / (dot_scalar(&a, &a).sqrt() * dot_scalar(&b, &b).sqrt());
+        assert!((cosine_similarity(&a, &b) - scalar_cosine).abs() < 1e-5);
+
+        let scalar_l2 = l2_squared_scalar(&a, &b).sqrt();
+        assert!((l2_distance(&a, &b) - scalar_l2).abs() < 1e-5 * scalar_l2);
+
+        let scalar_dot = dot_scalar(&a, &b);
+        assert!((dot_product(&a, &b) - scalar_dot).abs() < 1e-5 * scalar_dot.abs().max(1.0));
+    }
+}
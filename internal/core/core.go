@@ -2,36 +2,201 @@ package core
 
 /*
 #cgo LDFLAGS: -L${SRCDIR}/../../target/release -lvexlake_core
+#include <stdint.h>
 #include <stdlib.h>
 
+// Arrow C Data Interface structs (https://arrow.apache.org/docs/format/CDataInterface.html)
+struct ArrowSchema {
+	const char* format;
+	const char* name;
+	const char* metadata;
+	int64_t flags;
+	int64_t n_children;
+	struct ArrowSchema** children;
+	struct ArrowSchema* dictionary;
+	void (*release)(struct ArrowSchema*);
+	void* private_data;
+};
+
+struct ArrowArray {
+	int64_t length;
+	int64_t null_count;
+	int64_t offset;
+	int64_t n_buffers;
+	int64_t n_children;
+	const void** buffers;
+	struct ArrowArray** children;
+	struct ArrowArray* dictionary;
+	void (*release)(struct ArrowArray*);
+	void* private_data;
+};
+
+static void vexlake_release_arrow(struct ArrowArray* array, struct ArrowSchema* schema) {
+	if (array->release) array->release(array);
+	if (schema->release) schema->release(schema);
+}
+
 int vexlake_health_check();
 const char* vexlake_version();
 int vexlake_init(int dim);
+int vexlake_init_auto();
+int vexlake_init_ex(int dim, int metric, int auto_normalize);
 void vexlake_shutdown();
-int vexlake_insert(unsigned long long id, const float* vec_ptr, int len);
-char* vexlake_search(const float* query_ptr, int len, int k, int ef);
+unsigned long long vexlake_create(int dim);
+int vexlake_destroy(unsigned long long handle);
+int vexlake_index_insert(unsigned long long handle, unsigned long long id, const float* vec_ptr, int len);
+int vexlake_index_delete(unsigned long long handle, unsigned long long id);
+char* vexlake_index_search(unsigned long long handle, const float* query_ptr, int len, int k, int ef);
+int vexlake_index_search_arrow(unsigned long long handle, const float* query_ptr, int len, int k, int ef, struct ArrowArray* out_array, struct ArrowSchema* out_schema);
+char* vexlake_metrics_json();
+int vexlake_index_save(unsigned long long handle, const char* path);
+int vexlake_load(const char* path);
+unsigned long long vexlake_open(const char* path);
+int vexlake_normalize_batch(float* vecs_ptr, int n, int dim);
 void vexlake_free_string(char* ptr);
+const char* vexlake_last_error();
 */
 import "C"
 
 import (
 	"encoding/json"
 	"fmt"
+	"runtime"
+	"unsafe"
 )
 
+// ErrorCode matches the Rust VexErrorCode enum
+type ErrorCode int
+
+const (
+	CodeInternal       ErrorCode = -1
+	CodeDimMismatch    ErrorCode = -2
+	CodeNotInitialized ErrorCode = -3
+	CodeLockPoisoned   ErrorCode = -4
+)
+
+// Error is a failed engine call with its code and message
+type Error struct {
+	Code    ErrorCode
+	Op      string
+	Message string
+}
+
+func (e *Error) Error() string {
+	return fmt.Sprintf("%s (code: %d): %s", e.Op, e.Code, e.Message)
+}
+
+// check runs an engine call and, on failure, reads the thread-local error
+// message before the goroutine can move to another OS thread
+func check(op string, call func() C.int) error {
+	runtime.LockOSThread()
+	defer runtime.UnlockOSThread()
+	if res := call(); res != 0 {
+		return &Error{Code: ErrorCode(res), Op: op, Message: C.GoString(C.vexlake_last_error())}
+	}
+	return nil
+}
+
 // SearchResult matches the Rust SearchResult struct
 type SearchResult struct {
 	ID    uint64  `json:"id"`
 	Score float32 `json:"score"`
 }
 
+// LatencySummary matches the Rust LatencySummary struct
+type LatencySummary struct {
+	Count uint64 `json:"count"`
+	P50Us uint64 `json:"p50_us"`
+	P90Us uint64 `json:"p90_us"`
+	P99Us uint64 `json:"p99_us"`
+}
+
+// StorageErrorCounts matches the Rust StorageErrorCounts struct
+type StorageErrorCounts struct {
+	NotFound         uint64 `json:"not_found"`
+	PermissionDenied uint64 `json:"permission_denied"`
+	RateLimited      uint64 `json:"rate_limited"`
+	Other            uint64 `json:"other"`
+}
+
+// StorageRequestSummary matches the Rust StorageRequestSummary struct
+type StorageRequestSummary struct {
+	InFlight uint64             `json:"in_flight"`
+	Total    uint64             `json:"total"`
+	Errors   StorageErrorCounts `json:"errors"`
+}
+
+// Metrics matches the Rust MetricsSnapshot struct
+type Metrics struct {
+	Search          LatencySummary        `json:"search"`
+	Insert          LatencySummary        `json:"insert"`
+	StorageRead     LatencySummary        `json:"storage_read"`
+	StorageWrite    LatencySummary        `json:"storage_write"`
+	StorageRequests StorageRequestSummary `json:"storage_requests"`
+}
+
+// Index is one Rust index, addressed by an opaque handle
+type Index struct {
+	handle C.ulonglong
+}
+
+// defaultIndex is the engine used by the package-level functions
+var defaultIndex = &Index{handle: 0}
+
+// NewIndex creates an independent index; dim 0 takes the dimension from the first insert
+func NewIndex(dim int) (*Index, error) {
+	handle := C.vexlake_create(C.int(dim))
+	if handle == 0 {
+		return nil, fmt.Errorf("failed to create index")
+	}
+	return &Index{handle: handle}, nil
+}
+
+// OpenIndex loads an index saved with Index.Save into a new handle
+func OpenIndex(path string) (*Index, error) {
+	cpath := C.CString(path)
+	defer C.free(unsafe.Pointer(cpath))
+
+	runtime.LockOSThread()
+	defer runtime.UnlockOSThread()
+	handle := C.vexlake_open(cpath)
+	if handle == 0 {
+		return nil, &Error{Code: CodeInternal, Op: "failed to open index", Message: C.GoString(C.vexlake_last_error())}
+	}
+	return &Index{handle: handle}, nil
+}
+
+// Close frees the index; it must not be used afterwards
+func (idx *Index) Close() error {
+	if C.vexlake_destroy(idx.handle) != 0 {
+		return fmt.Errorf("unknown index handle %d", uint64(idx.handle))
+	}
+	return nil
+}
+
 // Init initializes the Rust engine
 func Init(dim int) error {
-	res := C.vexlake_init(C.int(dim))
-	if res != 0 {
-		return fmt.Errorf("failed to initialize Rust engine (code: %d)", res)
+	return check("failed to initialize Rust engine", func() C.int {
+		return C.vexlake_init(C.int(dim))
+	})
+}
+
+// InitEx initializes the Rust engine with a metric (0 = cosine, 1 = L2, 2 = dot, 3 = L1) and optional auto-normalization
+func InitEx(dim, metric int, autoNormalize bool) error {
+	norm := 0
+	if autoNormalize {
+		norm = 1
 	}
-	return nil
+	return check("failed to initialize Rust engine", func() C.int {
+		return C.vexlake_init_ex(C.int(dim), C.int(metric), C.int(norm))
+	})
+}
+
+// InitAuto initializes the Rust engine, taking the dimension from the first insert
+func InitAuto() error {
+	return check("failed to initialize Rust engine", func() C.int {
+		return C.vexlake_init_auto()
+	})
 }
 
 // Shutdown cleans up the Rust engine
@@ -49,27 +214,78 @@ func Version() string {
 	return C.GoString(C.vexlake_version())
 }
 
-// Insert adds a vector to the index
+// Insert adds a vector to the default engine
 func Insert(id uint64, vec []float32) error {
+	return defaultIndex.Insert(id, vec)
+}
+
+// Insert adds a vector to the index
+func (idx *Index) Insert(id uint64, vec []float32) error {
 	if len(vec) == 0 {
 		return fmt.Errorf("empty vector")
 	}
-	res := C.vexlake_insert(C.ulonglong(id), (*C.float)(&vec[0]), C.int(len(vec)))
-	if res != 0 {
-		return fmt.Errorf("failed to insert vector (code: %d)", res)
-	}
-	return nil
+	return check("failed to insert vector", func() C.int {
+		return C.vexlake_index_insert(idx.handle, C.ulonglong(id), (*C.float)(&vec[0]), C.int(len(vec)))
+	})
 }
 
-// Search find the nearest neighbors for a query vector
+// Delete removes a vector from the default engine
+func Delete(id uint64) (bool, error) {
+	return defaultIndex.Delete(id)
+}
+
+// Delete removes a vector from the index, reporting whether it was present
+func (idx *Index) Delete(id uint64) (bool, error) {
+	var res C.int
+	err := check("failed to delete vector", func() C.int {
+		res = C.vexlake_index_delete(idx.handle, C.ulonglong(id))
+		if res > 0 {
+			return 0
+		}
+		return res
+	})
+	return err == nil && res == 0, err
+}
+
+// Save writes the default engine to a local file
+func Save(path string) error {
+	return defaultIndex.Save(path)
+}
+
+// Save writes the index to a local file
+func (idx *Index) Save(path string) error {
+	cpath := C.CString(path)
+	defer C.free(unsafe.Pointer(cpath))
+	return check("failed to save index", func() C.int {
+		return C.vexlake_index_save(idx.handle, cpath)
+	})
+}
+
+// Load replaces the default engine with an index saved to a local file
+func Load(path string) error {
+	cpath := C.CString(path)
+	defer C.free(unsafe.Pointer(cpath))
+	return check("failed to load index", func() C.int {
+		return C.vexlake_load(cpath)
+	})
+}
+
+// Search find the nearest neighbors for a query vector in the default engine
 func Search(query []float32, k, ef int) ([]SearchResult, error) {
+	return defaultIndex.Search(query, k, ef)
+}
+
+// Search find the nearest neighbors for a query vector
+func (idx *Index) Search(query []float32, k, ef int) ([]SearchResult, error) {
 	if len(query) == 0 {
 		return nil, fmt.Errorf("empty query")
 	}
 
-	ptr := C.vexlake_search((*C.float)(&query[0]), C.int(len(query)), C.int(k), C.int(ef))
+	runtime.LockOSThread()
+	defer runtime.UnlockOSThread()
+	ptr := C.vexlake_index_search(idx.handle, (*C.float)(&query[0]), C.int(len(query)), C.int(k), C.int(ef))
 	if ptr == nil {
-		return nil, fmt.Errorf("search failed")
+		return nil, &Error{Code: CodeInternal, Op: "search failed", Message: C.GoString(C.vexlake_last_error())}
 	}
 	defer C.vexlake_free_string(ptr)
 
@@ -81,3 +297,73 @@ func Search(query []float32, k, ef int) ([]SearchResult, error) {
 
 	return results, nil
 }
+
+// SearchArrow is SearchArrow on the default engine
+func SearchArrow(query []float32, k, ef int) ([]SearchResult, error) {
+	return defaultIndex.SearchArrow(query, k, ef)
+}
+
+// SearchArrow finds the nearest neighbors, receiving the results as Arrow
+// columns instead of JSON
+func (idx *Index) SearchArrow(query []float32, k, ef int) ([]SearchResult, error) {
+	if len(query) == 0 {
+		return nil, fmt.Errorf("empty query")
+	}
+
+	var array C.struct_ArrowArray
+	var schema C.struct_ArrowSchema
+	err := check("search failed", func() C.int {
+		return C.vexlake_index_search_arrow(idx.handle, (*C.float)(&query[0]), C.int(len(query)), C.int(k), C.int(ef), &array, &schema)
+	})
+	if err != nil {
+		return nil, err
+	}
+	defer C.vexlake_release_arrow(&array, &schema)
+
+	// Struct array with children id (uint64) and score (float32); the values
+	// are in buffer 1 of each child
+	n := int(array.length)
+	children := unsafe.Slice(array.children, 2)
+	column := func(child *C.struct_ArrowArray) unsafe.Pointer {
+		return unsafe.Slice(child.buffers, 2)[1]
+	}
+	idOffset := int(children[0].offset + array.offset)
+	scoreOffset := int(children[1].offset + array.offset)
+	ids := unsafe.Slice((*uint64)(column(children[0])), idOffset+n)[idOffset:]
+	scores := unsafe.Slice((*float32)(column(children[1])), scoreOffset+n)[scoreOffset:]
+
+	results := make([]SearchResult, n)
+	for i := range results {
+		results[i] = SearchResult{ID: ids[i], Score: scores[i]}
+	}
+	return results, nil
+}
+
+// GetMetrics returns latency percentiles and storage request counters
+func GetMetrics() (*Metrics, error) {
+	ptr := C.vexlake_metrics_json()
+	if ptr == nil {
+		return nil, fmt.Errorf("failed to get metrics")
+	}
+	defer C.vexlake_free_string(ptr)
+
+	var metrics Metrics
+	if err := json.Unmarshal([]byte(C.GoString(ptr)), &metrics); err != nil {
+		return nil, fmt.Errorf("failed to parse metrics: %w", err)
+	}
+
+	return &metrics, nil
+}
+
+// NormalizeBatch normalizes a row-major buffer of vectors of length dim in place
+func NormalizeBatch(vecs []float32, dim int) error {
+	if dim <= 0 || len(vecs)%dim != 0 {
+		return fmt.Errorf("buffer length %d is not a multiple of dimension %d", len(vecs), dim)
+	}
+	if len(vecs) == 0 {
+		return nil
+	}
+	return check("failed to normalize vectors", func() C.int {
+		return C.vexlake_normalize_batch((*C.float)(&vecs[0]), C.int(len(vecs)/dim), C.int(dim))
+	})
+}